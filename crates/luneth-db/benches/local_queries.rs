@@ -0,0 +1,300 @@
+//! `record_local` 大库查询基准
+//!
+//! 在临时目录中生成 `LUNETH_BENCH_ROWS`（默认 100000）条合成记录，测量列表、全文检索与
+//! 计数查询。每个用例先预热一次，再运行 `LUNETH_BENCH_ITERS`（默认 20）次，
+//! 输出最短、中位与平均耗时。
+
+use std::error::Error;
+use std::future::Future;
+use std::hint::black_box;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chrono::{TimeDelta, Utc};
+use luneth_db::impl_local::{LocalFilterCondition, PageStart, SortField, SortOrder, SortSpec};
+use luneth_db::{DbError, DbOperator, record_local};
+use sea_orm::{ActiveModelBehavior as _, EntityTrait as _, Set, TransactionTrait as _};
+use serde_json::{Map, Value};
+
+type BenchResult<T> = Result<T, Box<dyn Error>>;
+
+const DEFAULT_ROWS: u64 = 100_000;
+const DEFAULT_ITERS: usize = 20;
+
+/// 每条 `INSERT` 语句包含的记录数，避免超出绑定参数个数上限
+const INSERT_CHUNK_SIZE: usize = 500;
+
+const PAGE_SIZE: u64 = 50;
+
+const WORDS: [&str; 16] = [
+    "summer", "night", "office", "river", "garden", "secret", "morning", "rain", "city", "station",
+    "letter", "mirror", "winter", "island", "hotel", "promise",
+];
+
+#[tokio::main]
+async fn main() -> BenchResult<()> {
+    let rows = env_or("LUNETH_BENCH_ROWS", DEFAULT_ROWS);
+    let iters = env_or("LUNETH_BENCH_ITERS", DEFAULT_ITERS).max(1);
+
+    let dir = std::env::temp_dir().join(format!("luneth-bench-{}", std::process::id()));
+    let result = run(&dir, rows, iters).await;
+    if let Err(e) = std::fs::remove_dir_all(&dir) {
+        eprintln!("Failed to remove {}: {e}", dir.display());
+    }
+    result
+}
+
+async fn run(dir: &Path, rows: u64, iters: usize) -> BenchResult<()> {
+    let db = DbOperator::builder()
+        .backup_before_migrate(false)
+        .open(dir.join(luneth_db::DB_FILE_NAME))
+        .await?;
+
+    let started = Instant::now();
+    seed(&db, rows).await?;
+    println!("seeded {rows} records in {:.2?}\n", started.elapsed());
+    println!(
+        "{:<48} {:>10} {:>10} {:>10}",
+        "case", "min", "median", "mean"
+    );
+
+    bench_count(&db, iters).await?;
+    bench_query(&db, rows, iters).await?;
+    bench_search(&db, iters).await
+}
+
+async fn bench_count(db: &DbOperator, iters: usize) -> BenchResult<()> {
+    measure("query_total_count: all", iters, || {
+        db.query_total_count(vec![])
+    })
+    .await?;
+    measure("query_total_count: viewed", iters, || {
+        db.query_total_count(vec![LocalFilterCondition::Viewed])
+    })
+    .await?;
+    measure("query_total_count: liked, rating >= 3", iters, || {
+        db.query_total_count(vec![
+            LocalFilterCondition::Liked,
+            LocalFilterCondition::Rating {
+                min: Some(3),
+                max: None,
+            },
+        ])
+    })
+    .await
+}
+
+async fn bench_query(db: &DbOperator, rows: u64, iters: usize) -> BenchResult<()> {
+    measure("query_local: first page, updated_at desc", iters, || {
+        db.query_local(
+            None,
+            Some(PAGE_SIZE),
+            vec![],
+            sort(SortField::UpdatedAt, SortOrder::Desc),
+        )
+    })
+    .await?;
+    measure("query_local: viewed, release_date desc", iters, || {
+        db.query_local(
+            None,
+            Some(PAGE_SIZE),
+            vec![LocalFilterCondition::Viewed],
+            sort(SortField::ReleaseDate, SortOrder::Desc),
+        )
+    })
+    .await?;
+    measure("query_local: not submitted, rating desc", iters, || {
+        db.query_local(
+            None,
+            Some(PAGE_SIZE),
+            vec![LocalFilterCondition::Not(Box::new(
+                LocalFilterCondition::Submit,
+            ))],
+            sort(SortField::Rating, SortOrder::Desc),
+        )
+    })
+    .await?;
+    measure("query_local: release date range, title asc", iters, || {
+        db.query_local(
+            None,
+            Some(PAGE_SIZE),
+            vec![LocalFilterCondition::ReleaseDate {
+                from: Some("2015-01-01".to_owned()),
+                to: Some("2016-12-31".to_owned()),
+            }],
+            sort(SortField::Title, SortOrder::Asc),
+        )
+    })
+    .await?;
+    measure("query_local: deep offset, created_at asc", iters, || {
+        db.query_local(
+            Some(rows / 2),
+            Some(PAGE_SIZE),
+            vec![],
+            sort(SortField::CreatedAt, SortOrder::Asc),
+        )
+    })
+    .await
+}
+
+async fn bench_search(db: &DbOperator, iters: usize) -> BenchResult<()> {
+    measure("search_local: one word, by rank", iters, || {
+        db.search_local(
+            "river".to_owned(),
+            vec![],
+            None,
+            PageStart::First,
+            PAGE_SIZE,
+        )
+    })
+    .await?;
+    measure("search_local: two words, viewed, by date", iters, || {
+        db.search_local(
+            "winter hotel".to_owned(),
+            vec![LocalFilterCondition::Viewed],
+            Some(sort(SortField::ReleaseDate, SortOrder::Desc)),
+            PageStart::First,
+            PAGE_SIZE,
+        )
+    })
+    .await?;
+    measure("search_local: idol name", iters, || {
+        db.search_local(
+            "Idol 042".to_owned(),
+            vec![],
+            None,
+            PageStart::First,
+            PAGE_SIZE,
+        )
+    })
+    .await
+}
+
+/// 运行用例并输出耗时
+async fn measure<F, Fut, T>(name: &str, iters: usize, mut case: F) -> BenchResult<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, DbError>>,
+{
+    black_box(case().await?);
+
+    let mut samples = Vec::with_capacity(iters);
+    for _ in 0..iters {
+        let started = Instant::now();
+        black_box(case().await?);
+        samples.push(started.elapsed());
+    }
+    samples.sort_unstable();
+
+    let total = samples.iter().sum::<Duration>();
+    let mean = total / u32::try_from(samples.len())?;
+    println!(
+        "{name:<48} {:>10.2?} {:>10.2?} {:>10.2?}",
+        samples[0],
+        samples[samples.len() / 2],
+        mean
+    );
+    Ok(())
+}
+
+/// 在一个事务中分块写入合成记录
+async fn seed(db: &DbOperator, rows: u64) -> BenchResult<()> {
+    let mut rng = Lcg(0x5eed);
+    let now = Utc::now();
+    let txn = db.get_db().begin().await?;
+
+    let mut batch = Vec::with_capacity(INSERT_CHUNK_SIZE);
+    for i in 0..rows {
+        batch.push(synthetic_record(&mut rng, i, now));
+        if batch.len() == INSERT_CHUNK_SIZE {
+            record_local::Entity::insert_many(std::mem::take(&mut batch))
+                .exec_without_returning(&txn)
+                .await?;
+        }
+    }
+    if !batch.is_empty() {
+        record_local::Entity::insert_many(batch)
+            .exec_without_returning(&txn)
+            .await?;
+    }
+
+    txn.commit().await?;
+    Ok(())
+}
+
+fn synthetic_record(
+    rng: &mut Lcg,
+    index: u64,
+    now: chrono::DateTime<Utc>,
+) -> record_local::ActiveModel {
+    let title = (0..3)
+        .map(|_| rng.pick(&WORDS))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let created_at = now - TimeDelta::minutes(index.try_into().unwrap_or(i64::MAX));
+    let updated_at = created_at + TimeDelta::hours(rng.below(24 * 365).try_into().unwrap_or(0));
+    let viewed = rng.below(3) > 0;
+    let view_count = if viewed { rng.below(20) + 1 } else { 0 };
+
+    let mut record = record_local::ActiveModel::new();
+    record.id = Set(format!("BEN-{index:06}"));
+    record.title = Set(title);
+    record.release_date = Set(format!(
+        "{}-{:02}-{:02}",
+        2005 + rng.below(20),
+        rng.below(12) + 1,
+        rng.below(28) + 1
+    ));
+    record.length = Set((60 + rng.below(120)).to_string());
+    record.cover = Set(String::new());
+    record.studio = Set(attribute(&format!("Studio {:02}", rng.below(20))));
+    record.genre = Set(attribute(&format!("Genre {:02}", rng.below(40))));
+    record.idols = Set(attribute(&format!("Idol {:03}", rng.below(500))));
+    record.local_image_count = Set(rng.below(10).try_into().unwrap_or(0));
+    record.viewed = Set(viewed);
+    record.view_count = Set(view_count.try_into().unwrap_or(0));
+    record.last_viewed_at = Set(viewed.then_some(updated_at));
+    record.is_liked = Set(rng.below(10) == 0);
+    record.is_submitted = Set(rng.below(2) == 0);
+    record.is_cached_locally = Set(rng.below(4) == 0);
+    record.rating = Set(rng.below(6).try_into().unwrap_or(0));
+    record.created_at = Set(created_at);
+    record.updated_at = Set(updated_at);
+    record
+}
+
+/// 与爬取结果相同的 `{名称: 链接}` 结构
+fn attribute(name: &str) -> Value {
+    let mut map = Map::new();
+    map.insert(name.to_owned(), Value::String(String::new()));
+    Value::Object(map)
+}
+
+fn sort(field: SortField, order: SortOrder) -> SortSpec {
+    SortSpec { field, order }
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// 固定种子的线性同余生成器，保证每次生成相同的数据
+struct Lcg(u64);
+
+impl Lcg {
+    fn below(&mut self, bound: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 33) % bound
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        let index = self.below(items.len().try_into().unwrap_or(u64::MAX));
+        items[usize::try_from(index).unwrap_or_default()]
+    }
+}
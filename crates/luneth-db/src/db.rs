@@ -1,98 +1,178 @@
+use std::path::Path;
+
+use crate::migration::Migrator;
 use crate::types::DbError;
 use sea_orm::{
-    ConnectOptions, ConnectionTrait as _, Database, DatabaseConnection, DbBackend,
-    EntityTrait as _, Schema, Statement,
+    ConnectionTrait as _, DatabaseConnection, DbBackend, EntityTrait as _, Statement,
+    TransactionTrait as _,
 };
-use tauri::{AppHandle, Manager as _};
+use sea_orm_migration::{MigratorTrait as _, seaql_migrations};
 
-use crate::entities::{history_op, history_task, record_local, record_remote};
+use crate::entities::{history_task, record_remote};
 
+pub mod builder;
+pub mod impl_attribute;
+pub mod impl_backup;
+pub mod impl_collection;
+pub mod impl_facet;
 pub mod impl_history;
+pub mod impl_integrity;
 pub mod impl_local;
+pub mod impl_reconcile;
 pub mod impl_remote;
+pub mod impl_revision;
+pub mod impl_search;
+pub mod impl_tag;
+pub mod impl_transfer;
+pub mod impl_view;
+
+pub use builder::DbOperatorBuilder;
 
 type Result<T> = std::result::Result<T, DbError>;
 
+/// 应用数据目录下的数据库文件名
+pub const DB_FILE_NAME: &str = "luneth.db";
+
+/// 以写入行的主键作为唯一约束冲突的键
+fn with_id_key(err: DbError, id: Option<sea_orm::Value>) -> DbError {
+    match id {
+        Some(sea_orm::Value::String(Some(id))) => err.with_key(*id),
+        _ => err,
+    }
+}
+
+/// 读取已执行的迁移，拒绝包含本程序未知迁移的数据库
+async fn applied_migrations(
+    conn: &impl sea_orm::ConnectionTrait,
+) -> Result<Vec<seaql_migrations::Model>> {
+    let known: Vec<String> = Migrator::migrations()
+        .iter()
+        .map(|m| m.name().to_owned())
+        .collect();
+    let applied = seaql_migrations::Entity::find().all(conn).await?;
+    if let Some(unknown) = applied.iter().find(|m| !known.contains(&m.version)) {
+        return Err(DbError::SchemaTooNew(unknown.version.clone()));
+    }
+    Ok(applied)
+}
+
 /// 数据库操作器
 pub struct DbOperator {
     db: DatabaseConnection,
 }
 
 impl DbOperator {
-    /// 初始化数据库连接
-    pub async fn init(app_handle: &AppHandle) -> Result<Self> {
+    /// 创建连接构建器，用于自定义连接选项
+    pub fn builder() -> DbOperatorBuilder {
+        DbOperatorBuilder::default()
+    }
+
+    /// 以默认选项打开（或创建）指定路径的数据库文件
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::builder().open(path).await
+    }
+
+    /// 以默认选项打开内存数据库
+    pub async fn open_in_memory() -> Result<Self> {
+        Self::builder().open_in_memory().await
+    }
+
+    /// 初始化应用数据目录下的 [`DB_FILE_NAME`]
+    #[cfg(feature = "tauri")]
+    pub async fn init(app_handle: &tauri::AppHandle) -> Result<Self> {
+        use tauri::Manager as _;
+
         // 获取应用本地数据目录 - Tauri v2 API
         let app_local_data_dir = app_handle
             .path()
             .app_local_data_dir()
             .map_err(|e| DbError::TauriError(e.to_string()))?;
 
-        // 确保目录存在
-        std::fs::create_dir_all(&app_local_data_dir)
-            .map_err(|e| DbError::CreateFailed(e.to_string()))?;
-
-        // 构建数据库文件路径
-        let db_path = app_local_data_dir.join("luneth.db");
-        log::debug!("Database file path: {}", db_path.display());
-        let db_url = format!("sqlite://{}?mode=rwc", db_path.display());
-
-        // 配置连接选项
-        let mut opt = ConnectOptions::new(db_url);
-        // 在生产环境中关闭日志
-        opt.sqlx_logging(false);
-
-        // 建立数据库连接
-        let db = Database::connect(opt)
-            .await
-            .map_err(|e| DbError::ConnectionFailed(e.to_string()))?;
-
-        let operator = Self { db };
-
-        // 创建表
-        operator.create_tables_if_not_exist().await?;
-
-        Ok(operator)
+        Self::open(app_local_data_dir.join(DB_FILE_NAME)).await
     }
 
-    /// 创建所有表（如果不存在）
-    async fn create_tables_if_not_exist(&self) -> Result<()> {
-        let schema = Schema::new(sea_orm::DatabaseBackend::Sqlite);
-        let db_sqlite = DbBackend::Sqlite;
+    /// 将数据库升级到当前版本
+    ///
+    /// 拒绝打开由更新版本程序写入的数据库；存在待执行迁移且给出了 `backup_path`
+    /// 时先备份原数据库文件。
+    async fn migrate(&self, backup_path: Option<&Path>) -> Result<()> {
+        Migrator::install(&self.db).await?;
+        let applied = applied_migrations(&self.db).await?;
 
-        // 创建 record_local 表
-        let mut stmt = db_sqlite.build(&schema.create_table_from_entity(record_local::Entity));
-        stmt.sql = stmt
-            .sql
-            .replace("CREATE TABLE", "CREATE TABLE IF NOT EXISTS");
-        self.execute_db_stmt(stmt).await?;
+        let pending = Migrator::get_pending_migrations(&self.db).await?;
+        if pending.is_empty() {
+            log::debug!("Database schema is up to date");
+            return Ok(());
+        }
 
-        // 创建 record_remote 表
-        let mut stmt = db_sqlite.build(&schema.create_table_from_entity(record_remote::Entity));
-        stmt.sql = stmt
-            .sql
-            .replace("CREATE TABLE", "CREATE TABLE IF NOT EXISTS");
-        self.execute_db_stmt(stmt).await?;
+        if let Some(db_path) = backup_path
+            && self.has_user_tables().await?
+        {
+            let version = applied
+                .last()
+                .map_or("legacy", |m| m.version.as_str())
+                .to_owned();
+            let file_name = db_path
+                .file_name()
+                .map_or_else(|| DB_FILE_NAME.into(), |n| n.to_string_lossy());
+            let backup_path = db_path.with_file_name(format!(
+                "{file_name}.{version}.{}.bak",
+                chrono::Utc::now().format("%Y%m%d%H%M%S")
+            ));
+            log::info!(
+                "Backing up database before migration to {}",
+                backup_path.display()
+            );
+            self.vacuum_into(&backup_path).await?;
+        }
 
-        // 创建 history_op 表
-        let mut stmt = db_sqlite.build(&schema.create_table_from_entity(history_op::Entity));
-        stmt.sql = stmt
-            .sql
-            .replace("CREATE TABLE", "CREATE TABLE IF NOT EXISTS");
-        self.execute_db_stmt(stmt).await?;
+        for migration in &pending {
+            log::info!("Applying migration {}", migration.name());
+        }
+        // 连接池中其他连接的表结构缓存可能过期，`DROP ... IF EXISTS` 会被误判为无操作，
+        // 因此全部迁移在同一事务（同一连接）中执行，失败时整体回滚
+        let txn = self.db.begin().await?;
+        Migrator::up(&txn, None)
+            .await
+            .map_err(|e| DbError::MigrationFailed(e.to_string()))?;
+        txn.commit().await?;
 
-        // 创建 history_task 表
-        let mut stmt = db_sqlite.build(&schema.create_table_from_entity(history_task::Entity));
-        stmt.sql = stmt
-            .sql
-            .replace("CREATE TABLE", "CREATE TABLE IF NOT EXISTS");
-        self.execute_db_stmt(stmt).await?;
+        Ok(())
+    }
 
+    /// 按数据变化情况刷新查询计划所用的统计信息，开销很小，适合在打开数据库时执行
+    async fn optimize(&self) -> Result<()> {
+        self.db
+            .execute_unprepared("PRAGMA optimize = 0x10002")
+            .await?;
         Ok(())
     }
 
-    /// 执行语句
-    async fn execute_db_stmt(&self, stmt: Statement) -> Result<()> {
-        self.db.execute(stmt).await?;
+    /// 数据库中是否已存在业务表（迁移表除外）
+    async fn has_user_tables(&self) -> Result<bool> {
+        let stmt = Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT COUNT(*) AS cnt FROM sqlite_master \
+             WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name <> 'seaql_migrations'",
+        );
+        let count = self
+            .db
+            .query_one(stmt)
+            .await?
+            .map(|row| row.try_get::<i64>("", "cnt"))
+            .transpose()?
+            .unwrap_or_default();
+        Ok(count > 0)
+    }
+
+    /// 使用 `VACUUM INTO` 将当前数据库在线复制到指定文件
+    async fn vacuum_into(&self, target: &Path) -> Result<()> {
+        let target = target.to_string_lossy().replace('\'', "''");
+        let stmt = Statement::from_string(DbBackend::Sqlite, format!("VACUUM INTO '{target}'"));
+        self.db
+            .execute(stmt)
+            .await
+            .map_err(|e| DbError::BackupFailed(e.to_string()))?;
         Ok(())
     }
 
@@ -106,3 +186,352 @@ impl DbOperator {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ConnectionTrait as _, Database, QueryResult};
+
+    use super::*;
+    use crate::entities::history_op;
+    use crate::test_util::TempDir;
+    use crate::types::{OperationStatus, OperationType, TaskStatus, TaskType};
+
+    /// 迁移管理之前的版本通过实体定义以 `CREATE TABLE IF NOT EXISTS` 建表
+    const LEGACY_SCHEMA: [&str; 4] = [
+        r#"CREATE TABLE IF NOT EXISTS "record_local" (
+            "id" varchar NOT NULL PRIMARY KEY,
+            "title" varchar NOT NULL,
+            "release_date" varchar NOT NULL,
+            "length" varchar NOT NULL,
+            "director" json_text NOT NULL,
+            "studio" json_text NOT NULL,
+            "label" json_text NOT NULL,
+            "series" json_text NOT NULL,
+            "genre" json_text NOT NULL,
+            "idols" json_text NOT NULL,
+            "share_magnet_links" json_text NOT NULL,
+            "local_image_count" integer NOT NULL,
+            "cover" varchar NOT NULL,
+            "sample_image_links" json_text NOT NULL,
+            "viewed" boolean NOT NULL,
+            "is_liked" boolean NOT NULL,
+            "is_submitted" boolean NOT NULL,
+            "is_cached_locally" boolean NOT NULL,
+            "created_at" timestamp_with_timezone_text NOT NULL,
+            "updated_at" timestamp_with_timezone_text NOT NULL
+        )"#,
+        r#"CREATE TABLE IF NOT EXISTS "record_remote" (
+            "id" varchar NOT NULL PRIMARY KEY,
+            "title" varchar NOT NULL,
+            "date" varchar NOT NULL,
+            "duration" integer NOT NULL,
+            "director" varchar NOT NULL,
+            "studio" varchar NOT NULL,
+            "label" varchar NOT NULL,
+            "series" varchar NOT NULL,
+            "genres" json_text NOT NULL,
+            "idols" json_text NOT NULL,
+            "has_links" boolean NOT NULL,
+            "links" json_text NOT NULL,
+            "created_at" timestamp_with_timezone_text NOT NULL,
+            "updated_at" timestamp_with_timezone_text NOT NULL
+        )"#,
+        r#"CREATE TABLE IF NOT EXISTS "history_op" (
+            "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT,
+            "recorder_id" varchar NOT NULL,
+            "operation" varchar NOT NULL,
+            "timestamp" timestamp_with_timezone_text NOT NULL,
+            "status" varchar NOT NULL,
+            "user" varchar NOT NULL,
+            "error_message" varchar NULL,
+            "created_at" timestamp_with_timezone_text NOT NULL,
+            "updated_at" timestamp_with_timezone_text NOT NULL
+        )"#,
+        r#"CREATE TABLE IF NOT EXISTS "history_task" (
+            "id" varchar NOT NULL PRIMARY KEY,
+            "task_type" varchar NOT NULL,
+            "start_time" timestamp_with_timezone_text NOT NULL,
+            "end_time" timestamp_with_timezone_text NULL,
+            "status" varchar NOT NULL,
+            "target_ids" json_text NOT NULL,
+            "failed_ids" json_text NOT NULL,
+            "total_count" integer NOT NULL,
+            "failed_count" integer NOT NULL,
+            "created_at" timestamp_with_timezone_text NOT NULL,
+            "updated_at" timestamp_with_timezone_text NOT NULL
+        )"#,
+    ];
+
+    const LEGACY_RECORD: &str = r#"INSERT INTO record_local VALUES (
+        'ABC-001', 'Legacy title', '2020-01-01', '120分钟',
+        '{}', '{"Studio A": "https://example.com/studio/a"}', '{}', '{}',
+        '{"Drama": "https://example.com/genre/drama"}', '{"Idol A": ""}', '[]',
+        3, 'https://example.com/cover.jpg', '[]', 1, 0, 0, 1,
+        '2020-01-02T00:00:00+00:00', '2020-01-03T00:00:00+00:00'
+    )"#;
+
+    async fn create_legacy_db(path: &Path, statements: &[&str]) {
+        let url = format!("sqlite://{}?mode=rwc", path.display());
+        let db = Database::connect(url).await.expect("connect legacy db");
+        for sql in LEGACY_SCHEMA.iter().chain(statements) {
+            db.execute_unprepared(sql).await.expect("create legacy db");
+        }
+        db.close().await.expect("close legacy db");
+    }
+
+    async fn query_all(db: &DbOperator, sql: &str) -> Vec<QueryResult> {
+        db.db
+            .query_all(Statement::from_string(DbBackend::Sqlite, sql))
+            .await
+            .expect("query")
+    }
+
+    async fn applied_versions(db: &DbOperator) -> Vec<String> {
+        applied_migrations(&db.db)
+            .await
+            .expect("applied migrations")
+            .into_iter()
+            .map(|m| m.version)
+            .collect()
+    }
+
+    fn migration_names() -> Vec<String> {
+        Migrator::migrations()
+            .iter()
+            .map(|m| m.name().to_owned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn migrate_adopts_legacy_database() {
+        let dir = TempDir::new("legacy");
+        let path = dir.path().join(DB_FILE_NAME);
+        create_legacy_db(&path, &[LEGACY_RECORD]).await;
+
+        let db = DbOperator::open(&path).await.expect("open legacy db");
+
+        // 初始迁移记为已执行，后续迁移全部生效
+        assert_eq!(applied_versions(&db).await, migration_names());
+
+        let rows = query_all(
+            &db,
+            "SELECT title, local_image_count, viewed, deleted_at, rating, view_count
+             FROM record_local WHERE id = 'ABC-001'",
+        )
+        .await;
+        assert_eq!(rows.len(), 1, "legacy record should survive migration");
+        let row = &rows[0];
+        assert_eq!(
+            row.try_get::<String>("", "title").expect("title"),
+            "Legacy title"
+        );
+        assert_eq!(
+            row.try_get::<i32>("", "local_image_count")
+                .expect("local_image_count"),
+            3
+        );
+        assert!(row.try_get::<bool>("", "viewed").expect("viewed"));
+        assert_eq!(
+            row.try_get::<Option<String>>("", "deleted_at")
+                .expect("deleted_at"),
+            None
+        );
+        assert_eq!(row.try_get::<i32>("", "rating").expect("rating"), 0);
+        // 已看过的旧记录计一次浏览
+        assert_eq!(row.try_get::<i32>("", "view_count").expect("view_count"), 1);
+
+        // 已有记录的属性拆分到关系表
+        let attributes = query_all(
+            &db,
+            "SELECT attribute.kind, attribute.name FROM record_attribute
+             JOIN attribute ON attribute.id = record_attribute.attribute_id
+             WHERE record_attribute.record_id = 'ABC-001'
+             ORDER BY attribute.kind",
+        )
+        .await;
+        let attributes: Vec<(String, String)> = attributes
+            .iter()
+            .map(|row| {
+                (
+                    row.try_get("", "kind").expect("kind"),
+                    row.try_get("", "name").expect("name"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                ("GENRE".to_owned(), "Drama".to_owned()),
+                ("IDOL".to_owned(), "Idol A".to_owned()),
+                ("STUDIO".to_owned(), "Studio A".to_owned()),
+            ]
+        );
+
+        // 迁移前备份了旧数据库
+        let backups = std::fs::read_dir(dir.path())
+            .expect("read dir")
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                name.starts_with("luneth.db.legacy.") && name.ends_with(".bak")
+            })
+            .count();
+        assert_eq!(backups, 1, "legacy database should be backed up once");
+
+        db.db.close().await.expect("close");
+
+        // 再次打开时没有待执行的迁移
+        let db = DbOperator::open(&path).await.expect("reopen");
+        assert_eq!(applied_versions(&db).await, migration_names());
+        let count = query_all(&db, "SELECT id FROM record_local").await.len();
+        assert_eq!(count, 1, "reopening should not touch records");
+    }
+
+    #[tokio::test]
+    async fn migrate_skips_malformed_attribute_json() {
+        let dir = TempDir::new("legacy-json");
+        let path = dir.path().join(DB_FILE_NAME);
+        let malformed = LEGACY_RECORD.replace("'ABC-001'", "'ABC-002'").replace(
+            r#"'{"Drama": "https://example.com/genre/drama"}'"#,
+            "'{Drama'",
+        );
+        create_legacy_db(&path, &[LEGACY_RECORD, &malformed]).await;
+
+        let db = DbOperator::open(&path).await.expect("open legacy db");
+        assert_eq!(applied_versions(&db).await, migration_names());
+
+        let attributes = query_all(
+            &db,
+            "SELECT record_attribute.record_id, attribute.kind FROM record_attribute
+             JOIN attribute ON attribute.id = record_attribute.attribute_id
+             ORDER BY record_attribute.record_id, attribute.kind",
+        )
+        .await;
+        let attributes: Vec<(String, String)> = attributes
+            .iter()
+            .map(|row| {
+                (
+                    row.try_get("", "record_id").expect("record_id"),
+                    row.try_get("", "kind").expect("kind"),
+                )
+            })
+            .collect();
+        // 损坏的类别列被跳过，同一记录的其他列照常拆分
+        assert_eq!(
+            attributes,
+            [
+                ("ABC-001".to_owned(), "GENRE".to_owned()),
+                ("ABC-001".to_owned(), "IDOL".to_owned()),
+                ("ABC-001".to_owned(), "STUDIO".to_owned()),
+                ("ABC-002".to_owned(), "IDOL".to_owned()),
+                ("ABC-002".to_owned(), "STUDIO".to_owned()),
+            ]
+        );
+        let indexed = query_all(
+            &db,
+            "SELECT id FROM record_local_fts WHERE record_local_fts MATCH 'Legacy'",
+        )
+        .await;
+        assert_eq!(indexed.len(), 2, "both records should be indexed");
+    }
+
+    #[tokio::test]
+    async fn migrate_normalizes_history_values() {
+        let dir = TempDir::new("legacy-history");
+        let path = dir.path().join(DB_FILE_NAME);
+        create_legacy_db(
+            &path,
+            &[
+                "INSERT INTO history_op
+                    (recorder_id, operation, timestamp, status, user, created_at, updated_at)
+                 VALUES
+                    ('ABC-001', 'crawlRecord', '2020-01-01T00:00:00+00:00', 'success', 'u',
+                     '2020-01-01T00:00:00+00:00', '2020-01-01T00:00:00+00:00'),
+                    ('ABC-002', ' crawl-record ', '2020-01-01T00:00:00+00:00', 'Failed', 'u',
+                     '2020-01-01T00:00:00+00:00', '2020-01-01T00:00:00+00:00'),
+                    ('ABC-003', 'mystery', '2020-01-01T00:00:00+00:00', 'done', 'u',
+                     '2020-01-01T00:00:00+00:00', '2020-01-01T00:00:00+00:00')",
+                "INSERT INTO history_task
+                    (id, task_type, start_time, status, target_ids, failed_ids,
+                     total_count, failed_count, created_at, updated_at)
+                 VALUES
+                    ('t1', 'crawl', '2020-01-01T00:00:00+00:00', 'Success', '[]', '[]', 0, 0,
+                     '2020-01-01T00:00:00+00:00', '2020-01-01T00:00:00+00:00'),
+                    ('t2', 'legacy-sync', '2020-01-01T00:00:00+00:00', 'running', '[]', '[]', 0, 0,
+                     '2020-01-01T00:00:00+00:00', '2020-01-01T00:00:00+00:00')",
+            ],
+        )
+        .await;
+
+        let db = DbOperator::open(&path).await.expect("open legacy db");
+
+        // 旧写法被规范化，无法识别的取值记为 UNKNOWN，不删除任何历史
+        let mut ops = history_op::Entity::find()
+            .all(&db.db)
+            .await
+            .expect("load history ops");
+        ops.sort_by(|a, b| a.recorder_id.cmp(&b.recorder_id));
+        let ops: Vec<_> = ops
+            .into_iter()
+            .map(|op| (op.recorder_id, op.operation, op.status))
+            .collect();
+        assert_eq!(
+            ops,
+            [
+                (
+                    "ABC-001".to_owned(),
+                    OperationType::CrawlRecord,
+                    OperationStatus::Success
+                ),
+                (
+                    "ABC-002".to_owned(),
+                    OperationType::CrawlRecord,
+                    OperationStatus::Failed
+                ),
+                (
+                    "ABC-003".to_owned(),
+                    OperationType::Unknown,
+                    OperationStatus::Unknown
+                ),
+            ]
+        );
+
+        let mut tasks = history_task::Entity::find()
+            .all(&db.db)
+            .await
+            .expect("load history tasks");
+        tasks.sort_by(|a, b| a.id.cmp(&b.id));
+        let tasks: Vec<_> = tasks
+            .into_iter()
+            .map(|task| (task.id, task.task_type, task.status))
+            .collect();
+        assert_eq!(
+            tasks,
+            [
+                ("t1".to_owned(), TaskType::Crawl, TaskStatus::Success),
+                ("t2".to_owned(), TaskType::Unknown, TaskStatus::Unknown),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn migrate_rejects_unknown_migration() {
+        let db = DbOperator::open_in_memory().await.expect("open");
+        db.db
+            .execute_unprepared(
+                "INSERT INTO seaql_migrations (version, applied_at)
+                 VALUES ('m20991231_000001_from_the_future', 0)",
+            )
+            .await
+            .expect("insert unknown migration");
+
+        let result = db.migrate(None).await;
+        assert!(
+            matches!(
+                &result,
+                Err(DbError::SchemaTooNew(version)) if version == "m20991231_000001_from_the_future"
+            ),
+            "unexpected result: {result:?}"
+        );
+    }
+}
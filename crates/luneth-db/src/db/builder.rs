@@ -0,0 +1,236 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use sea_orm::sqlx::sqlite::{SqliteJournalMode, SqliteSynchronous};
+use sea_orm::{ConnectOptions, Database};
+
+use super::{DbOperator, Result};
+use crate::types::DbError;
+
+/// 内存数据库连接永不回收，否则连接关闭后数据随之丢失
+const MEMORY_CONNECTION_LIFETIME: Duration = Duration::from_secs(60 * 60 * 24 * 365);
+
+/// 文件数据库连接池的默认连接数
+///
+/// WAL 模式下读连接可以并发，写入仍由数据库串行化。
+const DEFAULT_MAX_CONNECTIONS: u32 = 8;
+const DEFAULT_MIN_CONNECTIONS: u32 = 1;
+
+/// 等待其他连接释放写锁的默认时间
+const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 数据库连接构建器
+#[derive(Debug, Clone)]
+pub struct DbOperatorBuilder {
+    max_connections: Option<u32>,
+    min_connections: Option<u32>,
+    connect_timeout: Option<Duration>,
+    acquire_timeout: Option<Duration>,
+    busy_timeout: Duration,
+    wal: bool,
+    sqlx_logging: bool,
+    backup_before_migrate: bool,
+}
+
+impl Default for DbOperatorBuilder {
+    fn default() -> Self {
+        Self {
+            max_connections: None,
+            min_connections: None,
+            connect_timeout: None,
+            acquire_timeout: None,
+            busy_timeout: DEFAULT_BUSY_TIMEOUT,
+            wal: true,
+            // 在生产环境中关闭日志
+            sqlx_logging: false,
+            backup_before_migrate: true,
+        }
+    }
+}
+
+impl DbOperatorBuilder {
+    /// 连接池最大连接数
+    pub fn max_connections(mut self, value: u32) -> Self {
+        self.max_connections = Some(value);
+        self
+    }
+
+    /// 连接池最小连接数
+    pub fn min_connections(mut self, value: u32) -> Self {
+        self.min_connections = Some(value);
+        self
+    }
+
+    /// 建立连接的超时时间
+    pub fn connect_timeout(mut self, value: Duration) -> Self {
+        self.connect_timeout = Some(value);
+        self
+    }
+
+    /// 从连接池获取连接的超时时间
+    pub fn acquire_timeout(mut self, value: Duration) -> Self {
+        self.acquire_timeout = Some(value);
+        self
+    }
+
+    /// 写锁被占用时的等待时间，超时后返回 `SQLITE_BUSY` 错误
+    pub fn busy_timeout(mut self, value: Duration) -> Self {
+        self.busy_timeout = value;
+        self
+    }
+
+    /// 是否使用 WAL 日志模式，仅对文件数据库生效
+    ///
+    /// 开启时同步级别降为 `NORMAL`，断电可能丢失最近提交的事务，但不会损坏数据库。
+    pub fn wal(mut self, value: bool) -> Self {
+        self.wal = value;
+        self
+    }
+
+    /// 是否输出 sqlx 语句日志
+    pub fn sqlx_logging(mut self, value: bool) -> Self {
+        self.sqlx_logging = value;
+        self
+    }
+
+    /// 执行迁移前是否备份数据库文件
+    pub fn backup_before_migrate(mut self, value: bool) -> Self {
+        self.backup_before_migrate = value;
+        self
+    }
+
+    /// 打开（或创建）指定路径的数据库文件
+    pub async fn open(self, path: impl AsRef<Path>) -> Result<DbOperator> {
+        let db_path = path.as_ref();
+        if let Some(parent) = db_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| DbError::CreateFailed(e.to_string()))?;
+        }
+
+        log::debug!("Database file path: {}", db_path.display());
+        // 文件名不经过 URL 解析，路径中可以包含 `?`、`#`、`%` 等字符
+        let mut opt = ConnectOptions::new("sqlite://");
+        opt.max_connections(DEFAULT_MAX_CONNECTIONS)
+            .min_connections(DEFAULT_MIN_CONNECTIONS);
+        let operator = self.connect(opt, Some(db_path.to_path_buf())).await?;
+
+        let backup_path = self.backup_before_migrate.then_some(db_path);
+        operator.migrate(backup_path).await?;
+        operator.optimize().await?;
+
+        Ok(operator)
+    }
+
+    /// 打开一个仅存在于内存中的数据库，适用于脚本与测试
+    pub async fn open_in_memory(self) -> Result<DbOperator> {
+        // 内存数据库只存在于单个连接中
+        let mut opt = ConnectOptions::new("sqlite::memory:");
+        opt.max_connections(1)
+            .min_connections(1)
+            .idle_timeout(MEMORY_CONNECTION_LIFETIME)
+            .max_lifetime(MEMORY_CONNECTION_LIFETIME);
+
+        let builder = Self {
+            max_connections: None,
+            min_connections: None,
+            wal: false,
+            ..self
+        };
+        let operator = builder.connect(opt, None).await?;
+        operator.migrate(None).await?;
+
+        Ok(operator)
+    }
+
+    /// 建立连接，给出 `filename` 时打开（或创建）该数据库文件
+    async fn connect(
+        &self,
+        mut opt: ConnectOptions,
+        filename: Option<PathBuf>,
+    ) -> Result<DbOperator> {
+        if let Some(value) = self.max_connections {
+            opt.max_connections(value);
+        }
+        if let Some(value) = self.min_connections {
+            opt.min_connections(value);
+        }
+        if let Some(value) = self.connect_timeout {
+            opt.connect_timeout(value);
+        }
+        if let Some(value) = self.acquire_timeout {
+            opt.acquire_timeout(value);
+        }
+        opt.sqlx_logging(self.sqlx_logging);
+
+        let busy_timeout = self.busy_timeout;
+        let wal = self.wal;
+        opt.map_sqlx_sqlite_opts(move |sqlite| {
+            let sqlite = match &filename {
+                Some(filename) => sqlite.filename(filename).create_if_missing(true),
+                None => sqlite,
+            };
+            let sqlite = sqlite.busy_timeout(busy_timeout);
+            if wal {
+                sqlite
+                    .journal_mode(SqliteJournalMode::Wal)
+                    .synchronous(SqliteSynchronous::Normal)
+            } else {
+                sqlite
+            }
+        });
+
+        // 建立数据库连接
+        let db = Database::connect(opt)
+            .await
+            .map_err(|e| DbError::ConnectionFailed(e.to_string()))?;
+
+        Ok(DbOperator { db })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{TempDir, record};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn open_path_with_url_characters() {
+        let dir = TempDir::new("builder");
+        let path = dir.path().join("a?b#c%20d").join("luneth.db");
+
+        let db = DbOperatorBuilder::default()
+            .open(&path)
+            .await
+            .expect("open db");
+        db.insert_local(record("ABC-001")).await.expect("insert");
+        db.get_db().clone().close().await.expect("close");
+
+        assert!(path.is_file(), "database should be created at {path:?}");
+        let db = DbOperatorBuilder::default()
+            .open(&path)
+            .await
+            .expect("reopen db");
+        assert!(
+            db.find_record_local_by_id("ABC-001")
+                .await
+                .expect("find")
+                .is_some(),
+            "record should persist in the same file"
+        );
+    }
+
+    #[tokio::test]
+    async fn open_in_memory_is_migrated() {
+        let db = DbOperatorBuilder::default()
+            .max_connections(4)
+            .open_in_memory()
+            .await
+            .expect("open db");
+        db.insert_local(record("ABC-001")).await.expect("insert");
+
+        // 内存数据库始终只有一个连接，各次查询看到同一份数据
+        for _ in 0..4 {
+            assert_eq!(db.query_total_count(vec![]).await.expect("count"), 1);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use sea_orm::{
+    ActiveModelTrait as _, ColumnTrait as _, DbErr, EntityTrait as _, IntoActiveModel as _,
+    ModelTrait as _, QueryFilter as _, QueryOrder as _, Set, TransactionTrait as _,
+};
+
+use super::Result;
+use super::impl_revision::{RevisionSource, insert_revision};
+use crate::types::{AttributeKind, OperationType};
+use crate::{attribute, record_local};
+
+impl super::DbOperator {
+    /// 查询某一类别的全部属性，按名称排序
+    pub async fn query_attributes(&self, kind: AttributeKind) -> Result<Vec<attribute::Model>> {
+        let attributes = attribute::Entity::find()
+            .filter(attribute::Column::Kind.eq(kind))
+            .order_by_asc(attribute::Column::Name)
+            .all(&self.db)
+            .await?;
+        Ok(attributes)
+    }
+
+    /// 查询记录关联的全部属性
+    pub async fn find_record_attributes(&self, record_id: &str) -> Result<Vec<attribute::Model>> {
+        let Some(record) = record_local::Entity::find_by_id(record_id)
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(vec![]);
+        };
+        let attributes = record
+            .find_related(attribute::Entity)
+            .order_by_asc(attribute::Column::Kind)
+            .order_by_asc(attribute::Column::Name)
+            .all(&self.db)
+            .await?;
+        Ok(attributes)
+    }
+
+    /// 重命名属性，并同步更新所有关联记录的 JSON 列
+    ///
+    /// 同类别下已存在同名属性时合并到该属性，返回合并后的属性。被改写的记录写入修订历史。
+    pub async fn rename_attribute(&self, id: i32, name: String) -> Result<attribute::Model> {
+        let txn = self.db.begin().await?;
+
+        let source = attribute::Entity::find_by_id(id)
+            .one(&txn)
+            .await?
+            .ok_or_else(|| DbErr::RecordNotFound(format!("attribute {id}")))?;
+        if source.name == name {
+            return Ok(source);
+        }
+        let target = attribute::Entity::find()
+            .filter(attribute::Column::Kind.eq(source.kind))
+            .filter(attribute::Column::Name.eq(name.as_str()))
+            .one(&txn)
+            .await?;
+        let records = source.find_related(record_local::Entity).all(&txn).await?;
+
+        let renamed = if let Some(target) = target {
+            target
+        } else {
+            let mut active = source.clone().into_active_model();
+            active.name = Set(name.clone());
+            active.update(&txn).await?
+        };
+
+        // 改写快照后由触发器重建关联
+        let column = source.kind.record_column();
+        let revision_source = RevisionSource::new(OperationType::Update);
+        for record in records {
+            let mut active = record.clone().into_active_model();
+            let mut map = match active.get(column).into_value() {
+                Some(sea_orm::Value::Json(Some(json))) => match *json {
+                    serde_json::Value::Object(map) => map,
+                    _ => serde_json::Map::new(),
+                },
+                _ => serde_json::Map::new(),
+            };
+            if let Some(link) = map.remove(&source.name) {
+                map.entry(name.clone()).or_insert(link);
+            }
+            active.set(column, serde_json::Value::Object(map).into());
+            let updated = active.update(&txn).await?;
+            insert_revision(&txn, &record, &updated, &revision_source, None).await?;
+        }
+
+        if renamed.id != source.id {
+            source.delete(&txn).await?;
+        }
+
+        txn.commit().await?;
+        Ok(renamed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{attribute, memory_db, record};
+
+    async fn insert_with_genre(db: &super::super::DbOperator, id: &str, genre: &[&str]) {
+        let mut r = record(id);
+        r.genre = Set(attribute(genre));
+        db.insert_local(r).await.expect("insert");
+    }
+
+    fn names(attributes: &[attribute::Model]) -> Vec<&str> {
+        attributes.iter().map(|a| a.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn rename_attribute_updates_records() {
+        let db = memory_db().await;
+        insert_with_genre(&db, "A-1", &["Drama", "Comedy"]).await;
+
+        let genres = db
+            .query_attributes(AttributeKind::Genre)
+            .await
+            .expect("query");
+        assert_eq!(names(&genres), ["Comedy", "Drama"]);
+        let drama = genres[1].clone();
+
+        let renamed = db
+            .rename_attribute(drama.id, "Romance".to_owned())
+            .await
+            .expect("rename");
+        assert_eq!(renamed.id, drama.id);
+        assert_eq!(renamed.name, "Romance");
+
+        let record = db
+            .find_record_local_by_id("A-1")
+            .await
+            .expect("find")
+            .expect("record");
+        assert_eq!(record.genre, attribute(&["Comedy", "Romance"]));
+        let attributes = db.find_record_attributes("A-1").await.expect("attributes");
+        assert_eq!(names(&attributes), ["Comedy", "Romance"]);
+    }
+
+    #[tokio::test]
+    async fn rename_attribute_merges_into_existing() {
+        let db = memory_db().await;
+        insert_with_genre(&db, "A-1", &["Drama"]).await;
+        insert_with_genre(&db, "A-2", &["Romance"]).await;
+
+        let genres = db
+            .query_attributes(AttributeKind::Genre)
+            .await
+            .expect("query");
+        let (drama, romance) = (genres[0].clone(), genres[1].clone());
+        assert_eq!(names(&genres), ["Drama", "Romance"]);
+
+        let merged = db
+            .rename_attribute(drama.id, "Romance".to_owned())
+            .await
+            .expect("merge");
+        assert_eq!(merged, romance);
+        assert_eq!(
+            db.query_attributes(AttributeKind::Genre)
+                .await
+                .expect("query"),
+            std::slice::from_ref(&romance)
+        );
+        assert_eq!(
+            db.find_record_attributes("A-1").await.expect("attributes"),
+            [romance]
+        );
+
+        // 被改写的记录写入修订，未改写的记录没有修订
+        let revisions = db.query_record_revisions("A-1").await.expect("revisions");
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].operation, OperationType::Update);
+        assert_eq!(
+            revisions[0].diff,
+            serde_json::json!({
+                "genre": { "old": attribute(&["Drama"]), "new": attribute(&["Romance"]) }
+            })
+        );
+        assert!(
+            db.query_record_revisions("A-2")
+                .await
+                .expect("revisions")
+                .is_empty()
+        );
+    }
+}
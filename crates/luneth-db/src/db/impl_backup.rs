@@ -0,0 +1,491 @@
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use sea_orm::{ConnectOptions, ConnectionTrait as _, Database, DbBackend, Statement};
+use serde::Serialize;
+
+use super::{DbOperator, Result};
+use crate::types::DbError;
+
+const SNAPSHOT_PREFIX: &str = "luneth-";
+const SNAPSHOT_EXTENSION: &str = "db";
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%d%H%M%S%3f";
+const IMAGES_ARCHIVE_SUFFIX: &str = ".images.tar.gz";
+/// 待恢复文件的后缀，下次启动时换入
+const PENDING_SUFFIX: &str = ".restore";
+
+/// 快照选项
+#[derive(Debug, Clone)]
+pub struct SnapshotOptions {
+    /// 快照目录
+    pub dir: PathBuf,
+    /// 保留的快照个数，为 0 时不清理
+    pub retention: usize,
+    /// 一并归档的图片目录
+    pub images_dir: Option<PathBuf>,
+}
+
+/// 快照文件信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SnapshotInfo {
+    pub name: String,
+    pub path: PathBuf,
+    /// 同名的图片归档
+    pub images_archive: Option<PathBuf>,
+    /// 数据库文件大小（字节）
+    pub size: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SnapshotInfo {
+    /// 由快照文件路径解析，文件名不符合快照格式时返回 `None`
+    fn from_path(path: &Path) -> Option<Self> {
+        if path.extension()? != SNAPSHOT_EXTENSION {
+            return None;
+        }
+        let name = path.file_stem()?.to_str()?;
+        let stamp = name.strip_prefix(SNAPSHOT_PREFIX)?;
+        let created_at = NaiveDateTime::parse_from_str(stamp, SNAPSHOT_TIME_FORMAT)
+            .ok()?
+            .and_utc();
+        let size = fs::metadata(path).ok()?.len();
+        let archive = images_archive_path(path);
+        Some(Self {
+            name: name.to_owned(),
+            path: path.to_owned(),
+            images_archive: archive.is_file().then_some(archive),
+            size,
+            created_at,
+        })
+    }
+}
+
+impl DbOperator {
+    /// 在线创建数据库快照，并按保留个数清理旧快照
+    ///
+    /// 数据库通过 `VACUUM INTO` 复制，不阻塞其他连接的读写；图片归档在阻塞线程中写入。
+    /// 任一步骤失败时删除本次生成的文件。
+    pub async fn create_snapshot(&self, options: &SnapshotOptions) -> Result<SnapshotInfo> {
+        fs::create_dir_all(&options.dir).map_err(|e| backup_err(&e))?;
+        let name = format!(
+            "{SNAPSHOT_PREFIX}{}",
+            Utc::now().format(SNAPSHOT_TIME_FORMAT)
+        );
+        let path = options.dir.join(format!("{name}.{SNAPSHOT_EXTENSION}"));
+        self.vacuum_into(&path).await?;
+
+        if let Some(images_dir) = &options.images_dir {
+            let images_dir = images_dir.clone();
+            let archive = images_archive_path(&path);
+            let archived = tokio::task::spawn_blocking(move || archive_dir(&images_dir, &archive))
+                .await
+                .map_err(|e| DbError::BackupFailed(e.to_string()))
+                .and_then(|r| r.map_err(|e| backup_err(&e)));
+            if let Err(e) = archived {
+                remove_snapshot_files(&path);
+                return Err(e);
+            }
+        }
+
+        let info = SnapshotInfo::from_path(&path)
+            .ok_or_else(|| DbError::BackupFailed(format!("invalid snapshot {}", path.display())))?;
+        log::info!("Created database snapshot {}", path.display());
+
+        if options.retention > 0 {
+            Self::prune_snapshots(&options.dir, options.retention)?;
+        }
+        Ok(info)
+    }
+
+    /// 列出目录中的快照，最新的在前；目录不存在时返回空列表
+    pub fn list_snapshots(dir: &Path) -> Result<Vec<SnapshotInfo>> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(backup_err(&e)),
+        };
+        let mut snapshots = entries
+            .filter_map(|entry| SnapshotInfo::from_path(&entry.ok()?.path()))
+            .collect::<Vec<_>>();
+        snapshots.sort_by_key(|s| Reverse(s.created_at));
+        Ok(snapshots)
+    }
+
+    /// 只保留最新的 `retention` 个快照，返回被删除的快照
+    pub fn prune_snapshots(dir: &Path, retention: usize) -> Result<Vec<SnapshotInfo>> {
+        let expired = Self::list_snapshots(dir)?
+            .into_iter()
+            .skip(retention)
+            .collect::<Vec<_>>();
+        for snapshot in &expired {
+            log::info!("Removing expired snapshot {}", snapshot.path.display());
+            remove_snapshot_files(&snapshot.path);
+        }
+        Ok(expired)
+    }
+
+    /// 校验快照可用于恢复
+    ///
+    /// 以只读方式打开快照，要求完整性检查通过、包含迁移记录且没有本程序未知的迁移；
+    /// 存在图片归档时同时校验归档可完整读取。
+    pub async fn validate_snapshot(path: &Path) -> Result<()> {
+        if !path.is_file() {
+            return Err(DbError::NotFound(path.display().to_string()));
+        }
+        let filename = path.to_path_buf();
+        let mut opt = ConnectOptions::new("sqlite://");
+        opt.map_sqlx_sqlite_opts(move |sqlite| sqlite.filename(&filename).read_only(true));
+        let conn = Database::connect(opt)
+            .await
+            .map_err(|e| DbError::ConnectionFailed(e.to_string()))?;
+
+        let checked = async {
+            let stmt = Statement::from_string(DbBackend::Sqlite, "PRAGMA integrity_check");
+            let result = conn
+                .query_one(stmt)
+                .await?
+                .map(|row| row.try_get_by_index::<String>(0))
+                .transpose()?
+                .unwrap_or_default();
+            if result != "ok" {
+                return Err(DbError::BackupFailed(format!(
+                    "integrity check failed: {result}"
+                )));
+            }
+            if super::applied_migrations(&conn).await?.is_empty() {
+                return Err(DbError::BackupFailed("no migrations applied".to_owned()));
+            }
+            Ok(())
+        }
+        .await;
+        conn.close().await?;
+        checked?;
+
+        let archive = images_archive_path(path);
+        if archive.is_file() {
+            tokio::task::spawn_blocking(move || verify_archive(&archive))
+                .await
+                .map_err(|e| DbError::BackupFailed(e.to_string()))?
+                .map_err(|e| backup_err(&e))?;
+        }
+        Ok(())
+    }
+
+    /// 校验快照并将其暂存到数据库旁，下次启动时由 [`Self::apply_pending_restore`] 换入
+    ///
+    /// 给出 `images_dir` 且快照带有图片归档时，归档一并暂存。
+    pub async fn stage_restore(
+        snapshot: &Path,
+        db_path: &Path,
+        images_dir: Option<&Path>,
+    ) -> Result<()> {
+        Self::validate_snapshot(snapshot).await?;
+
+        copy_atomic(snapshot, &pending_path(db_path)).map_err(|e| backup_err(&e))?;
+        if let Some(images_dir) = images_dir {
+            let archive = images_archive_path(snapshot);
+            let pending_images = pending_images_path(images_dir);
+            if archive.is_file() {
+                copy_atomic(&archive, &pending_images).map_err(|e| backup_err(&e))?;
+            } else {
+                remove_if_exists(&pending_images).map_err(|e| backup_err(&e))?;
+            }
+        }
+        log::info!(
+            "Staged snapshot {} for restore on next start",
+            snapshot.display()
+        );
+        Ok(())
+    }
+
+    /// 是否存在待恢复的快照
+    pub fn has_pending_restore(db_path: &Path) -> bool {
+        pending_path(db_path).is_file()
+    }
+
+    /// 取消已暂存的恢复
+    pub fn cancel_pending_restore(db_path: &Path, images_dir: Option<&Path>) -> Result<()> {
+        remove_if_exists(&pending_path(db_path)).map_err(|e| backup_err(&e))?;
+        if let Some(images_dir) = images_dir {
+            remove_if_exists(&pending_images_path(images_dir)).map_err(|e| backup_err(&e))?;
+        }
+        Ok(())
+    }
+
+    /// 在打开数据库之前换入暂存的快照，返回是否执行了恢复
+    ///
+    /// 原数据库（连同 `-wal`、`-shm` 文件）与图片目录改名保留为 `.pre-restore` 备份。
+    /// 任一步骤失败时撤销已完成的改名，原数据库与暂存文件保持不变。
+    pub fn apply_pending_restore(db_path: &Path, images_dir: Option<&Path>) -> Result<bool> {
+        let pending = pending_path(db_path);
+        if !pending.is_file() {
+            return Ok(false);
+        }
+        let stamp = Utc::now().format("%Y%m%d%H%M%S").to_string();
+
+        let mut undo = Vec::new();
+        if let Err(e) = swap_in_pending(db_path, images_dir, &stamp, &mut undo) {
+            rollback(undo);
+            return Err(backup_err(&e));
+        }
+
+        if let Some(images_dir) = images_dir {
+            let pending_images = pending_images_path(images_dir);
+            if let Err(e) = remove_if_exists(&pending_images) {
+                log::warn!("Failed to remove {}: {e}", pending_images.display());
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// [`DbOperator::apply_pending_restore`] 中可撤销的一步
+enum Undo {
+    Rename { from: PathBuf, to: PathBuf },
+    RemoveDir(PathBuf),
+}
+
+/// 改名并记录撤销方式
+fn rename_undoable(from: &Path, to: &Path, undo: &mut Vec<Undo>) -> io::Result<()> {
+    fs::rename(from, to)?;
+    undo.push(Undo::Rename {
+        from: from.to_owned(),
+        to: to.to_owned(),
+    });
+    Ok(())
+}
+
+/// 换入暂存的数据库与图片，已完成的步骤记入 `undo`
+fn swap_in_pending(
+    db_path: &Path,
+    images_dir: Option<&Path>,
+    stamp: &str,
+    undo: &mut Vec<Undo>,
+) -> io::Result<()> {
+    let backup = with_suffix(db_path, &format!(".pre-restore.{stamp}.bak"));
+    if db_path.exists() {
+        rename_undoable(db_path, &backup, undo)?;
+    }
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let sidecar = with_suffix(db_path, suffix);
+        if sidecar.exists() {
+            rename_undoable(&sidecar, &with_suffix(&backup, suffix), undo)?;
+        }
+    }
+    rename_undoable(&pending_path(db_path), db_path, undo)?;
+    log::info!(
+        "Restored database from snapshot, previous database kept at {}",
+        backup.display()
+    );
+
+    let Some(images_dir) = images_dir else {
+        return Ok(());
+    };
+    let pending_images = pending_images_path(images_dir);
+    if !pending_images.is_file() {
+        return Ok(());
+    }
+    if images_dir.exists() {
+        let previous = with_suffix(images_dir, &format!(".pre-restore.{stamp}"));
+        rename_undoable(images_dir, &previous, undo)?;
+    }
+    fs::create_dir_all(images_dir)?;
+    undo.push(Undo::RemoveDir(images_dir.to_owned()));
+    tar::Archive::new(GzDecoder::new(File::open(&pending_images)?)).unpack(images_dir)?;
+    log::info!(
+        "Restored images from snapshot into {}",
+        images_dir.display()
+    );
+    Ok(())
+}
+
+/// 按相反顺序撤销已完成的步骤，失败时只记录日志
+fn rollback(undo: Vec<Undo>) {
+    for step in undo.into_iter().rev() {
+        let result = match &step {
+            Undo::Rename { from, to } => fs::rename(to, from),
+            Undo::RemoveDir(dir) => fs::remove_dir_all(dir),
+        };
+        if let Err(e) = result {
+            match step {
+                Undo::Rename { from, to } => log::error!(
+                    "Failed to move {} back to {}: {e}",
+                    to.display(),
+                    from.display()
+                ),
+                Undo::RemoveDir(dir) => {
+                    log::error!("Failed to remove {}: {e}", dir.display());
+                }
+            }
+        }
+    }
+}
+
+fn backup_err(err: &io::Error) -> DbError {
+    DbError::BackupFailed(err.to_string())
+}
+
+/// 在路径的文件名后追加后缀
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn images_archive_path(snapshot: &Path) -> PathBuf {
+    let stem = snapshot.file_stem().unwrap_or_default().to_string_lossy();
+    snapshot.with_file_name(format!("{stem}{IMAGES_ARCHIVE_SUFFIX}"))
+}
+
+fn pending_path(db_path: &Path) -> PathBuf {
+    with_suffix(db_path, PENDING_SUFFIX)
+}
+
+fn pending_images_path(images_dir: &Path) -> PathBuf {
+    with_suffix(images_dir, &format!("{PENDING_SUFFIX}.tar.gz"))
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// 删除快照及其图片归档，失败时只记录日志
+fn remove_snapshot_files(snapshot: &Path) {
+    for path in [snapshot.to_owned(), images_archive_path(snapshot)] {
+        if let Err(e) = remove_if_exists(&path) {
+            log::warn!("Failed to remove {}: {e}", path.display());
+        }
+    }
+}
+
+/// 先写入临时文件再改名，避免留下不完整的目标文件
+fn copy_atomic(from: &Path, to: &Path) -> io::Result<()> {
+    let tmp = with_suffix(to, ".tmp");
+    fs::copy(from, &tmp)?;
+    fs::rename(&tmp, to)
+}
+
+/// 将目录内容打包为 gzip 压缩的 tar 归档
+fn archive_dir(dir: &Path, archive: &Path) -> io::Result<()> {
+    let tmp = with_suffix(archive, ".tmp");
+    let encoder = GzEncoder::new(File::create(&tmp)?, Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    if dir.is_dir() {
+        builder.append_dir_all(".", dir)?;
+    }
+    builder.into_inner()?.finish()?;
+    fs::rename(&tmp, archive)
+}
+
+/// 完整读取归档，校验其未损坏
+fn verify_archive(archive: &Path) -> io::Result<()> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(archive)?));
+    for entry in archive.entries()? {
+        io::copy(&mut entry?, &mut io::sink())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DB_FILE_NAME;
+    use crate::test_util::{TempDir, record};
+
+    /// 准备带两条记录与两张图片的数据库，其快照只包含第一条记录与第一张图片
+    async fn staged_fixture(dir: &Path) -> (PathBuf, PathBuf) {
+        let db_path = dir.join(DB_FILE_NAME);
+        let images_dir = dir.join("images");
+        fs::create_dir_all(&images_dir).expect("create images dir");
+
+        let db = DbOperator::open(&db_path).await.expect("open");
+        db.insert_local(record("A-1")).await.expect("insert");
+        fs::write(images_dir.join("a.jpg"), "a").expect("write image");
+        let snapshot = db
+            .create_snapshot(&SnapshotOptions {
+                dir: dir.join("snapshots"),
+                retention: 0,
+                images_dir: Some(images_dir.clone()),
+            })
+            .await
+            .expect("snapshot");
+        db.insert_local(record("A-2")).await.expect("insert");
+        fs::write(images_dir.join("b.jpg"), "b").expect("write image");
+        db.db.close().await.expect("close");
+
+        DbOperator::stage_restore(&snapshot.path, &db_path, Some(&images_dir))
+            .await
+            .expect("stage restore");
+        (db_path, images_dir)
+    }
+
+    async fn record_ids(db_path: &Path) -> Vec<String> {
+        let db = DbOperator::open(db_path).await.expect("open");
+        let mut ids = db.query_all_record_ids().await.expect("ids");
+        db.db.close().await.expect("close");
+        ids.sort_unstable();
+        ids
+    }
+
+    fn pre_restore_entries(dir: &Path) -> usize {
+        fs::read_dir(dir)
+            .expect("read dir")
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .contains(".pre-restore.")
+            })
+            .count()
+    }
+
+    #[tokio::test]
+    async fn apply_pending_restore_swaps_in_snapshot() {
+        let dir = TempDir::new("restore");
+        let (db_path, images_dir) = staged_fixture(dir.path()).await;
+        assert!(DbOperator::has_pending_restore(&db_path));
+
+        let restored =
+            DbOperator::apply_pending_restore(&db_path, Some(&images_dir)).expect("restore");
+        assert!(restored);
+        assert!(!DbOperator::has_pending_restore(&db_path));
+        assert!(!pending_images_path(&images_dir).exists());
+
+        assert_eq!(record_ids(&db_path).await, ["A-1"]);
+        assert!(images_dir.join("a.jpg").is_file());
+        assert!(!images_dir.join("b.jpg").exists());
+        // 原数据库与图片目录均保留为备份
+        assert_eq!(pre_restore_entries(dir.path()), 2);
+
+        let restored =
+            DbOperator::apply_pending_restore(&db_path, Some(&images_dir)).expect("no-op");
+        assert!(!restored);
+    }
+
+    #[tokio::test]
+    async fn apply_pending_restore_rolls_back_on_failure() {
+        let dir = TempDir::new("restore-fail");
+        let (db_path, images_dir) = staged_fixture(dir.path()).await;
+        fs::write(pending_images_path(&images_dir), "not an archive").expect("corrupt archive");
+
+        let result = DbOperator::apply_pending_restore(&db_path, Some(&images_dir));
+        assert!(
+            matches!(result, Err(DbError::BackupFailed(_))),
+            "unexpected result: {result:?}"
+        );
+
+        // 原数据库与图片目录原样保留，暂存的快照留待下次启动
+        assert!(DbOperator::has_pending_restore(&db_path));
+        assert_eq!(pre_restore_entries(dir.path()), 0);
+        assert!(images_dir.join("b.jpg").is_file());
+        assert_eq!(record_ids(&db_path).await, ["A-1", "A-2"]);
+    }
+}
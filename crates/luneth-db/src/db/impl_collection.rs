@@ -0,0 +1,292 @@
+use std::collections::{HashMap, HashSet};
+
+use sea_orm::{
+    ActiveModelTrait as _, ColumnTrait as _, ConnectionTrait, EntityTrait as _,
+    IntoActiveModel as _, ModelTrait as _, QueryFilter as _, QueryOrder as _, QuerySelect as _,
+    RelationTrait as _, Set, TransactionTrait as _, sea_query::JoinType,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Result;
+use super::impl_tag::{LINK_CHUNK_SIZE, existing_record_ids, normalize_name};
+use crate::entities::{collection, collection_record, record_local};
+use crate::types::DbError;
+
+/// 列表及其中的记录数，不含回收站中的记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionSummary {
+    #[serde(flatten)]
+    pub collection: collection::Model,
+    pub record_count: u64,
+}
+
+impl super::DbOperator {
+    /// 全部列表，按名称排序
+    pub async fn query_collections(&self) -> Result<Vec<CollectionSummary>> {
+        let collections = collection::Entity::find()
+            .order_by_asc(collection::Column::Name)
+            .all(&self.db)
+            .await?;
+        let counts = collection_record::Entity::find()
+            .select_only()
+            .column(collection_record::Column::CollectionId)
+            .column_as(collection_record::Column::RecordId.count(), "count")
+            .join(
+                JoinType::InnerJoin,
+                collection_record::Relation::RecordLocal.def(),
+            )
+            .filter(record_local::Column::DeletedAt.is_null())
+            .group_by(collection_record::Column::CollectionId)
+            .into_tuple::<(i32, i64)>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        let collections = collections
+            .into_iter()
+            .map(|collection| CollectionSummary {
+                record_count: counts.get(&collection.id).map_or(0, |&c| c.unsigned_abs()),
+                collection,
+            })
+            .collect();
+        Ok(collections)
+    }
+
+    /// 新建列表，名称去除首尾空白后不能为空，重名时返回唯一约束错误
+    pub async fn create_collection(
+        &self,
+        name: &str,
+        description: String,
+    ) -> Result<collection::Model> {
+        let now = chrono::Utc::now();
+        let collection = collection::ActiveModel {
+            name: Set(normalize_name(name)?),
+            description: Set(description),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+        log::info!("Created collection {} ({})", collection.name, collection.id);
+        Ok(collection)
+    }
+
+    /// 修改列表名称与描述
+    pub async fn update_collection(
+        &self,
+        id: i32,
+        name: &str,
+        description: String,
+    ) -> Result<collection::Model> {
+        let mut collection = find_collection(&self.db, id).await?.into_active_model();
+        collection.name = Set(normalize_name(name)?);
+        collection.description = Set(description);
+        collection.updated_at = Set(chrono::Utc::now());
+        Ok(collection.update(&self.db).await?)
+    }
+
+    /// 删除列表，列表中的记录不受影响
+    pub async fn delete_collection(&self, id: i32) -> Result<()> {
+        let collection = find_collection(&self.db, id).await?;
+        collection.delete(&self.db).await?;
+        log::info!("Deleted collection {id}");
+        Ok(())
+    }
+
+    /// 列表中的记录，按列表内顺序排列，不含回收站中的记录
+    pub async fn query_collection_records(&self, id: i32) -> Result<Vec<record_local::Model>> {
+        let records = record_local::Entity::find()
+            .inner_join(collection_record::Entity)
+            .filter(collection_record::Column::CollectionId.eq(id))
+            .filter(record_local::Column::DeletedAt.is_null())
+            .order_by_asc(collection_record::Column::Position)
+            .all(&self.db)
+            .await?;
+        Ok(records)
+    }
+
+    /// 包含该记录的全部列表，按名称排序
+    pub async fn find_record_collections(&self, record_id: &str) -> Result<Vec<collection::Model>> {
+        let collections = collection::Entity::find()
+            .inner_join(collection_record::Entity)
+            .filter(collection_record::Column::RecordId.eq(record_id))
+            .order_by_asc(collection::Column::Name)
+            .all(&self.db)
+            .await?;
+        Ok(collections)
+    }
+
+    /// 按给定顺序将记录追加到列表末尾，返回新加入的记录数
+    ///
+    /// 已在列表中的记录保持原位置，不存在的记录被忽略。
+    pub async fn add_to_collection(&self, id: i32, record_ids: &[String]) -> Result<u64> {
+        let txn = self.db.begin().await?;
+        let collection = find_collection(&txn, id).await?;
+        let mut members = member_ids(&txn, id)
+            .await?
+            .into_iter()
+            .collect::<HashSet<_>>();
+        // 清除回收站记录时关联被级联删除，位置可能不连续
+        let mut position = collection_record::Entity::find()
+            .select_only()
+            .column_as(collection_record::Column::Position.max(), "position")
+            .filter(collection_record::Column::CollectionId.eq(id))
+            .into_tuple::<Option<i32>>()
+            .one(&txn)
+            .await?
+            .flatten()
+            .map_or(0, |max| max.saturating_add(1));
+
+        let now = chrono::Utc::now();
+        let mut added = 0;
+        for chunk in record_ids.chunks(LINK_CHUNK_SIZE) {
+            let links = existing_record_ids(&txn, chunk)
+                .await?
+                .into_iter()
+                .filter(|record_id| members.insert(record_id.clone()))
+                .map(|record_id| {
+                    let link = collection_record::ActiveModel {
+                        collection_id: Set(id),
+                        record_id: Set(record_id),
+                        position: Set(position),
+                        added_at: Set(now),
+                    };
+                    position = position.saturating_add(1);
+                    link
+                })
+                .collect::<Vec<_>>();
+            if links.is_empty() {
+                continue;
+            }
+            added += links.len() as u64;
+            collection_record::Entity::insert_many(links)
+                .exec_without_returning(&txn)
+                .await?;
+        }
+        if added > 0 {
+            touch(&txn, collection).await?;
+        }
+        txn.commit().await?;
+
+        log::info!("Added {added} records to collection {id}");
+        Ok(added)
+    }
+
+    /// 从列表中移除记录，返回移除的记录数，其余记录保持相对顺序
+    pub async fn remove_from_collection(&self, id: i32, record_ids: &[String]) -> Result<u64> {
+        let txn = self.db.begin().await?;
+        let collection = find_collection(&txn, id).await?;
+        let mut removed = 0;
+        for chunk in record_ids.chunks(LINK_CHUNK_SIZE) {
+            removed += collection_record::Entity::delete_many()
+                .filter(collection_record::Column::CollectionId.eq(id))
+                .filter(collection_record::Column::RecordId.is_in(chunk.iter().cloned()))
+                .exec(&txn)
+                .await?
+                .rows_affected;
+        }
+        if removed > 0 {
+            renumber(&txn, id, &member_ids(&txn, id).await?).await?;
+            touch(&txn, collection).await?;
+        }
+        txn.commit().await?;
+
+        log::info!("Removed {removed} records from collection {id}");
+        Ok(removed)
+    }
+
+    /// 调整列表内顺序
+    ///
+    /// `record_ids` 中的记录按给定顺序排在最前，未列出的记录保持相对顺序排在其后，
+    /// 不在列表中的编号被忽略。
+    pub async fn reorder_collection(&self, id: i32, record_ids: &[String]) -> Result<()> {
+        let txn = self.db.begin().await?;
+        let collection = find_collection(&txn, id).await?;
+        let members = member_ids(&txn, id).await?;
+
+        let member_set = members.iter().map(String::as_str).collect::<HashSet<_>>();
+        let mut seen = HashSet::new();
+        let mut ordered = record_ids
+            .iter()
+            .filter(|record_id| {
+                member_set.contains(record_id.as_str()) && seen.insert(record_id.as_str())
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        ordered.extend(
+            members
+                .iter()
+                .filter(|record_id| !seen.contains(record_id.as_str()))
+                .cloned(),
+        );
+
+        renumber(&txn, id, &ordered).await?;
+        touch(&txn, collection).await?;
+        txn.commit().await?;
+
+        log::info!("Reordered collection {id}");
+        Ok(())
+    }
+}
+
+async fn find_collection(conn: &impl ConnectionTrait, id: i32) -> Result<collection::Model> {
+    collection::Entity::find_by_id(id)
+        .one(conn)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("collection {id}")))
+}
+
+/// 列表中的全部记录编号，按列表内顺序排列，包括回收站中的记录
+async fn member_ids(conn: &impl ConnectionTrait, id: i32) -> Result<Vec<String>> {
+    let ids = collection_record::Entity::find()
+        .select_only()
+        .column(collection_record::Column::RecordId)
+        .filter(collection_record::Column::CollectionId.eq(id))
+        .order_by_asc(collection_record::Column::Position)
+        .order_by_asc(collection_record::Column::AddedAt)
+        .into_tuple::<String>()
+        .all(conn)
+        .await?;
+    Ok(ids)
+}
+
+/// 按给定顺序将位置重排为从 0 开始的连续编号
+async fn renumber(conn: &impl ConnectionTrait, id: i32, ordered: &[String]) -> Result<()> {
+    let current = collection_record::Entity::find()
+        .select_only()
+        .columns([
+            collection_record::Column::RecordId,
+            collection_record::Column::Position,
+        ])
+        .filter(collection_record::Column::CollectionId.eq(id))
+        .into_tuple::<(String, i32)>()
+        .all(conn)
+        .await?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    for (position, record_id) in ordered.iter().enumerate() {
+        let position = i32::try_from(position).unwrap_or(i32::MAX);
+        if current.get(record_id) == Some(&position) {
+            continue;
+        }
+        collection_record::ActiveModel {
+            collection_id: Set(id),
+            record_id: Set(record_id.clone()),
+            position: Set(position),
+            ..Default::default()
+        }
+        .update(conn)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn touch(conn: &impl ConnectionTrait, collection: collection::Model) -> Result<()> {
+    let mut collection = collection.into_active_model();
+    collection.updated_at = Set(chrono::Utc::now());
+    collection.update(conn).await?;
+    Ok(())
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use sea_orm::{
+    DbBackend, EntityTrait as _, FromQueryResult, QueryFilter as _, QuerySelect as _, QueryTrait,
+    Statement,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Result;
+use super::impl_local::{LocalFilterCondition, to_conditions};
+use super::impl_search::SearchTerms;
+use crate::entities::{record_local::Column, record_local::Entity as entity};
+use crate::types::AttributeKind;
+
+/// 某一属性值在当前结果中的记录数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromQueryResult)]
+pub struct FacetValue {
+    /// `attribute` 表主键
+    pub id: i32,
+    pub name: String,
+    pub count: i64,
+}
+
+#[derive(FromQueryResult)]
+struct FacetRow {
+    kind: AttributeKind,
+    id: i32,
+    name: String,
+    count: i64,
+}
+
+/// 未过滤时按属性统计全部关联，只排除回收站中的记录
+const ALL_COUNTS: &str = "
+counts AS (
+    SELECT record_attribute.attribute_id, COUNT(*) AS count
+    FROM record_attribute JOIN record_local ON record_local.id = record_attribute.record_id
+    WHERE record_local.deleted_at IS NULL
+    GROUP BY record_attribute.attribute_id
+)";
+
+/// 只统计 `matched`（命中的记录编号）的关联
+///
+/// `CROSS JOIN` 固定由命中记录驱动、经主键索引查关联，避免按属性逐条回查记录。
+const MATCHED_COUNTS: &str = "
+counts AS (
+    SELECT record_attribute.attribute_id, COUNT(*) AS count
+    FROM matched CROSS JOIN record_attribute ON record_attribute.record_id = matched.id
+    GROUP BY record_attribute.attribute_id
+)";
+
+/// 每个类别按记录数从多到少取前若干个属性值
+const FACET_RANKING: &str = "
+ranked AS (
+    SELECT attribute.kind, attribute.id, attribute.name, counts.count,
+        ROW_NUMBER() OVER (
+            PARTITION BY attribute.kind ORDER BY counts.count DESC, attribute.name ASC
+        ) AS rn
+    FROM counts JOIN attribute ON attribute.id = counts.attribute_id
+)
+SELECT kind, id, name, count FROM ranked WHERE rn <= ? ORDER BY kind, rn";
+
+impl super::DbOperator {
+    /// 统计当前过滤条件与检索词下，各类别记录数最多的前 `limit` 个属性值
+    ///
+    /// 回收站中的记录不计入；没有任何关联的类别不会出现在结果中。
+    pub async fn query_facets(
+        &self,
+        filters: Vec<LocalFilterCondition>,
+        name: Option<String>,
+        limit: u64,
+    ) -> Result<HashMap<AttributeKind, Vec<FacetValue>>> {
+        let stmt = if filters.is_empty() && name.is_none() {
+            Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                format!("WITH {ALL_COUNTS}, {FACET_RANKING}"),
+                [limit.into()],
+            )
+        } else {
+            let mut query = entity::find()
+                .select_only()
+                .column(Column::Id)
+                .filter(to_conditions(filters));
+            if let Some(name) = name {
+                SearchTerms::parse(&name).apply(QueryTrait::query(&mut query));
+            }
+
+            let matched = query.build(DbBackend::Sqlite);
+            let mut values = matched.values.map(|v| v.0).unwrap_or_default();
+            values.push(limit.into());
+            Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                format!(
+                    "WITH matched AS ({}), {MATCHED_COUNTS}, {FACET_RANKING}",
+                    matched.sql
+                ),
+                values,
+            )
+        };
+
+        let rows = FacetRow::find_by_statement(stmt).all(&self.db).await?;
+        let mut facets: HashMap<AttributeKind, Vec<FacetValue>> = HashMap::new();
+        for row in rows {
+            facets.entry(row.kind).or_default().push(FacetValue {
+                id: row.id,
+                name: row.name,
+                count: row.count,
+            });
+        }
+        Ok(facets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Set;
+
+    use super::*;
+    use crate::db::impl_revision::RevisionSource;
+    use crate::test_util::{attribute, memory_db, record};
+    use crate::types::OperationType;
+
+    fn counts(
+        facets: &HashMap<AttributeKind, Vec<FacetValue>>,
+        kind: AttributeKind,
+    ) -> Vec<(&str, i64)> {
+        facets
+            .get(&kind)
+            .map(|values| {
+                values
+                    .iter()
+                    .map(|value| (value.name.as_str(), value.count))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn genre_counts(facets: &HashMap<AttributeKind, Vec<FacetValue>>) -> Vec<(String, i64)> {
+        counts(facets, AttributeKind::Genre)
+            .into_iter()
+            .map(|(name, count)| (name.to_owned(), count))
+            .collect()
+    }
+
+    /// 编号、标题、类别与演员
+    async fn seed(db: &super::super::DbOperator, records: &[(&str, &str, &[&str], &[&str])]) {
+        let source = RevisionSource::new(OperationType::CrawlRecord);
+        for (id, title, genres, idols) in records {
+            let mut r = record(id);
+            r.title = Set((*title).to_owned());
+            r.genre = Set(attribute(genres));
+            r.idols = Set(attribute(idols));
+            db.upsert_local(r, &source).await.expect("insert");
+        }
+    }
+
+    #[tokio::test]
+    async fn query_facets_counts_per_kind() {
+        let db = memory_db().await;
+        seed(
+            &db,
+            &[
+                ("A-1", "river", &["Drama", "Comedy"], &["Alice"]),
+                ("A-2", "hotel", &["Drama"], &["Alice", "Bob"]),
+            ],
+        )
+        .await;
+
+        let facets = db.query_facets(vec![], None, 10).await.expect("facets");
+        // 没有关联的类别不出现
+        let mut kinds = facets.keys().copied().collect::<Vec<_>>();
+        kinds.sort_by_key(|kind| format!("{kind:?}"));
+        assert_eq!(kinds, [AttributeKind::Genre, AttributeKind::Idol]);
+        assert_eq!(
+            counts(&facets, AttributeKind::Genre),
+            [("Drama", 2), ("Comedy", 1)]
+        );
+        assert_eq!(
+            counts(&facets, AttributeKind::Idol),
+            [("Alice", 2), ("Bob", 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn query_facets_limits_each_kind() {
+        let db = memory_db().await;
+        seed(
+            &db,
+            &[
+                (
+                    "A-1",
+                    "river",
+                    &["Drama", "Comedy", "Action"],
+                    &["Alice", "Bob", "Carol"],
+                ),
+                ("A-2", "hotel", &["Drama", "Comedy", "Action"], &["Bob"]),
+                ("A-3", "island", &["Drama", "Horror"], &[]),
+            ],
+        )
+        .await;
+
+        // 记录数相同的按名称排序
+        let facets = db.query_facets(vec![], None, 2).await.expect("facets");
+        assert_eq!(
+            counts(&facets, AttributeKind::Genre),
+            [("Drama", 3), ("Action", 2)]
+        );
+        assert_eq!(
+            counts(&facets, AttributeKind::Idol),
+            [("Bob", 2), ("Alice", 1)]
+        );
+
+        // 带过滤条件时同样按类别分别截取
+        let facets = db
+            .query_facets(vec![LocalFilterCondition::All(vec![])], None, 1)
+            .await
+            .expect("filtered facets");
+        assert_eq!(counts(&facets, AttributeKind::Genre), [("Drama", 3)]);
+        assert_eq!(counts(&facets, AttributeKind::Idol), [("Bob", 2)]);
+    }
+
+    #[tokio::test]
+    async fn query_facets_follows_filters_and_name() {
+        let db = memory_db().await;
+        seed(
+            &db,
+            &[
+                ("A-1", "summer river", &["Drama", "Comedy"], &["Alice"]),
+                ("A-2", "winter river", &["Drama"], &["Bob"]),
+                ("A-3", "hotel", &["Comedy"], &["Alice"]),
+            ],
+        )
+        .await;
+
+        let facets = db
+            .query_facets(
+                vec![LocalFilterCondition::Genre(vec!["Comedy".to_owned()])],
+                None,
+                10,
+            )
+            .await
+            .expect("filtered facets");
+        assert_eq!(
+            counts(&facets, AttributeKind::Genre),
+            [("Comedy", 2), ("Drama", 1)]
+        );
+        assert_eq!(counts(&facets, AttributeKind::Idol), [("Alice", 2)]);
+
+        let facets = db
+            .query_facets(vec![], Some("river".to_owned()), 10)
+            .await
+            .expect("searched facets");
+        assert_eq!(
+            counts(&facets, AttributeKind::Genre),
+            [("Drama", 2), ("Comedy", 1)]
+        );
+        assert_eq!(
+            counts(&facets, AttributeKind::Idol),
+            [("Alice", 1), ("Bob", 1)]
+        );
+
+        // 过滤条件与检索词同时生效
+        let facets = db
+            .query_facets(
+                vec![LocalFilterCondition::Genre(vec!["Comedy".to_owned()])],
+                Some("river".to_owned()),
+                10,
+            )
+            .await
+            .expect("combined facets");
+        assert_eq!(
+            counts(&facets, AttributeKind::Genre),
+            [("Comedy", 1), ("Drama", 1)]
+        );
+        assert_eq!(counts(&facets, AttributeKind::Idol), [("Alice", 1)]);
+    }
+
+    #[tokio::test]
+    async fn query_facets_excludes_trash() {
+        let db = memory_db().await;
+        let source = RevisionSource::new(OperationType::CrawlRecord);
+        for (id, genres) in [
+            ("A-1", &["Drama", "Comedy"][..]),
+            ("A-2", &["Drama"][..]),
+            ("A-3", &["Comedy"][..]),
+        ] {
+            let mut r = record(id);
+            r.genre = Set(attribute(genres));
+            db.upsert_local(r, &source).await.expect("insert");
+        }
+        db.trash_record_local("A-3").await.expect("trash");
+
+        // 未过滤与带过滤条件两条路径的结果一致
+        let all = db.query_facets(vec![], None, 10).await.expect("facets");
+        assert_eq!(
+            genre_counts(&all),
+            [("Drama".to_owned(), 2), ("Comedy".to_owned(), 1)]
+        );
+        let filtered = db
+            .query_facets(vec![LocalFilterCondition::All(vec![])], None, 10)
+            .await
+            .expect("filtered facets");
+        assert_eq!(genre_counts(&filtered), genre_counts(&all));
+    }
+}
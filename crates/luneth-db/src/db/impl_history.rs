@@ -1,12 +1,176 @@
-use sea_orm::{ActiveModelTrait as _, EntityTrait as _};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait as _, ColumnTrait as _, Condition, EntityTrait as _, FromQueryResult,
+    ModelTrait as _, Order, PaginatorTrait as _, QueryFilter as _, QueryOrder as _,
+    QuerySelect as _, sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
 
 use super::Result;
-use crate::history_op;
+use super::impl_local::SortOrder;
+use crate::types::{OperationStatus, OperationType, TaskStatus};
+use crate::{history_op, history_task};
+
+/// 操作历史过滤条件，各项同时满足，未设置的项不限
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryOpFilter {
+    /// 属于任一操作类型
+    pub operations: Vec<OperationType>,
+    /// 属于任一状态
+    pub statuses: Vec<OperationStatus>,
+    pub recorder_id: Option<String>,
+    /// 操作时间区间（含端点）
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// 错误信息包含该子串
+    pub error_contains: Option<String>,
+}
+
+impl HistoryOpFilter {
+    fn to_condition(&self) -> Condition {
+        let mut cond = Condition::all();
+        if !self.operations.is_empty() {
+            cond = cond.add(
+                history_op::Column::Operation
+                    .is_in(self.operations.iter().map(ToString::to_string)),
+            );
+        }
+        if !self.statuses.is_empty() {
+            cond = cond.add(history_op::Column::Status.is_in(self.statuses.iter().copied()));
+        }
+        if let Some(recorder_id) = &self.recorder_id {
+            cond = cond.add(history_op::Column::RecorderId.eq(recorder_id.as_str()));
+        }
+        if let Some(from) = self.from {
+            cond = cond.add(history_op::Column::Timestamp.gte(from));
+        }
+        if let Some(to) = self.to {
+            cond = cond.add(history_op::Column::Timestamp.lte(to));
+        }
+        if let Some(error) = &self.error_contains {
+            cond = cond.add(history_op::Column::ErrorMessage.contains(error.as_str()));
+        }
+        cond
+    }
+}
+
+/// 操作历史排序字段
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryOpSortField {
+    #[default]
+    Timestamp,
+    Operation,
+    Status,
+    RecorderId,
+}
+
+/// 操作历史排序方式，默认按操作时间倒序
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryOpSort {
+    pub field: HistoryOpSortField,
+    pub order: SortOrder,
+}
+
+impl HistoryOpSort {
+    fn columns(self) -> Vec<(history_op::Column, Order)> {
+        let order = match self.order {
+            SortOrder::Asc => Order::Asc,
+            SortOrder::Desc => Order::Desc,
+        };
+        let column = match self.field {
+            HistoryOpSortField::Timestamp => history_op::Column::Timestamp,
+            HistoryOpSortField::Operation => history_op::Column::Operation,
+            HistoryOpSortField::Status => history_op::Column::Status,
+            HistoryOpSortField::RecorderId => history_op::Column::RecorderId,
+        };
+        // 主键保证同值时顺序稳定
+        vec![(column, order.clone()), (history_op::Column::Id, order)]
+    }
+}
+
+/// 一页操作历史及满足条件的总数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryOpPage {
+    pub items: Vec<history_op::Model>,
+    pub total: u64,
+}
+
+/// 某天某类操作在某状态下的次数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromQueryResult)]
+pub struct HistoryOpDailyCount {
+    /// `YYYY-MM-DD`
+    pub day: String,
+    pub operation: OperationType,
+    pub status: OperationStatus,
+    pub count: i64,
+}
+
+/// 一次任务运行及其逐项操作结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskRun {
+    #[serde(flatten)]
+    pub task: history_task::Model,
+    /// 按时间顺序排列
+    pub operations: Vec<history_op::Model>,
+    /// 对该任务的重试，按开始时间排列
+    pub retries: Vec<history_task::Model>,
+}
 
 impl super::DbOperator {
-    pub async fn query_history_op(&self) -> Result<Vec<history_op::Model>> {
-        let history = history_op::Entity::find().all(&self.db).await?;
-        Ok(history)
+    /// 按条件分页查询操作历史
+    pub async fn query_history_op(
+        &self,
+        filter: &HistoryOpFilter,
+        sort: HistoryOpSort,
+        offset: Option<u64>,
+        limit: Option<u64>,
+    ) -> Result<HistoryOpPage> {
+        let query = history_op::Entity::find().filter(filter.to_condition());
+        let total = query.clone().count(&self.db).await?;
+
+        let items = sort
+            .columns()
+            .into_iter()
+            .fold(query, |q, (column, order)| q.order_by(column, order))
+            .offset(offset)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+        Ok(HistoryOpPage { items, total })
+    }
+
+    /// 按天统计满足条件的各类操作在各状态下的次数，日期从新到旧
+    ///
+    /// `utc_offset_minutes` 为划分日期所用时区相对 UTC 的偏移。
+    pub async fn query_history_op_daily(
+        &self,
+        filter: &HistoryOpFilter,
+        utc_offset_minutes: i32,
+    ) -> Result<Vec<HistoryOpDailyCount>> {
+        let day = Expr::cust_with_values(
+            "date(history_op.timestamp, ?)",
+            [format!("{utc_offset_minutes:+} minutes")],
+        );
+        let counts = history_op::Entity::find()
+            .select_only()
+            .column_as(day, "day")
+            .column(history_op::Column::Operation)
+            .column(history_op::Column::Status)
+            .column_as(history_op::Column::Id.count(), "count")
+            .filter(filter.to_condition())
+            .group_by(Expr::col("day"))
+            .group_by(history_op::Column::Operation)
+            .group_by(history_op::Column::Status)
+            .order_by_desc(Expr::col("day"))
+            .order_by_asc(history_op::Column::Operation)
+            .order_by_asc(history_op::Column::Status)
+            .into_model::<HistoryOpDailyCount>()
+            .all(&self.db)
+            .await?;
+        Ok(counts)
     }
 
     pub async fn insert_history_op(
@@ -16,4 +180,270 @@ impl super::DbOperator {
         let model = history_op.insert(&self.db).await?;
         Ok(model)
     }
+
+    pub async fn insert_history_task(
+        &self,
+        history_task: history_task::ActiveModel,
+    ) -> Result<history_task::Model> {
+        let model = history_task.insert(&self.db).await?;
+        Ok(model)
+    }
+
+    pub async fn update_history_task(
+        &self,
+        history_task: history_task::ActiveModel,
+    ) -> Result<history_task::Model> {
+        let model = history_task.update(&self.db).await?;
+        Ok(model)
+    }
+
+    /// 按开始时间倒序查询任务运行记录
+    pub async fn query_history_task(
+        &self,
+        offset: Option<u64>,
+        limit: Option<u64>,
+    ) -> Result<Vec<history_task::Model>> {
+        let tasks = history_task::Entity::find()
+            .order_by_desc(history_task::Column::StartTime)
+            .order_by_desc(history_task::Column::Id)
+            .offset(offset)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+        Ok(tasks)
+    }
+
+    /// 查询任务运行记录及其产生的全部操作
+    pub async fn find_task_run(&self, id: &str) -> Result<Option<TaskRun>> {
+        let Some(task) = self.find_history_task_by_id(id).await? else {
+            return Ok(None);
+        };
+        let operations = task
+            .find_related(history_op::Entity)
+            .order_by_asc(history_op::Column::Timestamp)
+            .order_by_asc(history_op::Column::Id)
+            .all(&self.db)
+            .await?;
+        let retries = history_task::Entity::find()
+            .filter(history_task::Column::ParentId.eq(task.id.as_str()))
+            .order_by_asc(history_task::Column::StartTime)
+            .all(&self.db)
+            .await?;
+        Ok(Some(TaskRun {
+            task,
+            operations,
+            retries,
+        }))
+    }
+
+    /// 将仍处于等待状态的任务标记为中止
+    ///
+    /// 用于启动时清理上次异常退出时未能结束的任务，返回处理的任务数。
+    pub async fn abort_pending_history_tasks(&self) -> Result<u64> {
+        let pending = history_task::Entity::find()
+            .filter(history_task::Column::Status.eq(TaskStatus::Pending))
+            .all(&self.db)
+            .await?;
+        let count = pending.len() as u64;
+        for task in pending {
+            let failed_ids = task.get_failed_ids().unwrap_or_default();
+            task.abort(
+                &failed_ids,
+                "Application exited before the task finished".to_owned(),
+            )
+            .update(&self.db)
+            .await?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+    use sea_orm::{ActiveModelBehavior as _, Set};
+
+    use super::*;
+    use crate::test_util::memory_db;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, day, hour, 0, 0)
+            .single()
+            .expect("time")
+    }
+
+    async fn insert_op(
+        db: &super::super::DbOperator,
+        recorder_id: &str,
+        operation: OperationType,
+        status: OperationStatus,
+        timestamp: DateTime<Utc>,
+        error: Option<&str>,
+    ) -> history_op::Model {
+        let mut op = history_op::ActiveModel::new();
+        op.recorder_id = Set(recorder_id.to_owned());
+        op.operation = Set(operation);
+        op.status = Set(status);
+        op.timestamp = Set(timestamp);
+        op.user = Set("local".to_owned());
+        op.error_message = Set(error.map(ToOwned::to_owned));
+        db.insert_history_op(op).await.expect("insert op")
+    }
+
+    fn recorders(page: &HistoryOpPage) -> Vec<&str> {
+        page.items
+            .iter()
+            .map(|op| op.recorder_id.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn query_history_op_filters() {
+        let db = memory_db().await;
+        let crawl = OperationType::CrawlRecord;
+        let (ok, failed) = (OperationStatus::Success, OperationStatus::Failed);
+        insert_op(&db, "A-1", crawl, ok, at(1, 0), None).await;
+        insert_op(&db, "A-2", crawl, failed, at(2, 0), Some("timeout")).await;
+        insert_op(&db, "A-3", crawl, failed, at(3, 0), Some("not found")).await;
+        insert_op(&db, "A-4", OperationType::Liked, ok, at(4, 0), None).await;
+
+        let query = async |filter: HistoryOpFilter| {
+            db.query_history_op(&filter, HistoryOpSort::default(), None, None)
+                .await
+                .expect("query")
+        };
+
+        let page = query(HistoryOpFilter {
+            operations: vec![crawl],
+            statuses: vec![failed],
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(recorders(&page), ["A-3", "A-2"]);
+
+        let page = query(HistoryOpFilter {
+            recorder_id: Some("A-4".to_owned()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(recorders(&page), ["A-4"]);
+
+        // 区间包含两个端点
+        let page = query(HistoryOpFilter {
+            from: Some(at(2, 0)),
+            to: Some(at(3, 0)),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(recorders(&page), ["A-3", "A-2"]);
+
+        let page = query(HistoryOpFilter {
+            error_contains: Some("time".to_owned()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(recorders(&page), ["A-2"]);
+    }
+
+    #[tokio::test]
+    async fn query_history_op_counts_total_before_paging() {
+        let db = memory_db().await;
+        for (i, id) in ["A-1", "A-2", "A-3", "A-4", "A-5"].into_iter().enumerate() {
+            let day = u32::try_from(i).expect("day") + 1;
+            insert_op(
+                &db,
+                id,
+                OperationType::Viewed,
+                OperationStatus::Success,
+                at(day, 0),
+                None,
+            )
+            .await;
+        }
+
+        let sort = HistoryOpSort {
+            field: HistoryOpSortField::Timestamp,
+            order: SortOrder::Asc,
+        };
+        let page = db
+            .query_history_op(&HistoryOpFilter::default(), sort, Some(1), Some(2))
+            .await
+            .expect("query");
+        assert_eq!(page.total, 5);
+        assert_eq!(recorders(&page), ["A-2", "A-3"]);
+    }
+
+    #[tokio::test]
+    async fn query_history_op_orders_equal_timestamps_by_id() {
+        let db = memory_db().await;
+        let mut ids = vec![];
+        for recorder in ["A-1", "A-2", "A-3", "A-4"] {
+            let op = insert_op(
+                &db,
+                recorder,
+                OperationType::Viewed,
+                OperationStatus::Success,
+                at(1, 0),
+                None,
+            )
+            .await;
+            ids.push(op.id);
+        }
+        ids.reverse();
+
+        let filter = HistoryOpFilter::default();
+        let mut paged = vec![];
+        for offset in [0, 2] {
+            let page = db
+                .query_history_op(&filter, HistoryOpSort::default(), Some(offset), Some(2))
+                .await
+                .expect("query");
+            paged.extend(page.items.into_iter().map(|op| op.id));
+        }
+        assert_eq!(paged, ids);
+    }
+
+    #[tokio::test]
+    async fn query_history_op_daily_buckets_by_offset() {
+        let db = memory_db().await;
+        let crawl = OperationType::CrawlRecord;
+        let (ok, failed) = (OperationStatus::Success, OperationStatus::Failed);
+        insert_op(&db, "A-1", crawl, ok, at(1, 10), None).await;
+        insert_op(&db, "A-2", crawl, ok, at(1, 20), None).await;
+        insert_op(&db, "A-3", crawl, failed, at(1, 20), Some("timeout")).await;
+
+        let daily = async |offset: i32| {
+            db.query_history_op_daily(&HistoryOpFilter::default(), offset)
+                .await
+                .expect("daily")
+                .into_iter()
+                .map(|c| (c.day, c.status, c.count))
+                .collect::<Vec<_>>()
+        };
+        let day = |d: &str| format!("2024-{d}");
+
+        // 同一天内按状态的存储值排序
+        assert_eq!(
+            daily(0).await,
+            [(day("01-01"), failed, 1), (day("01-01"), ok, 2)]
+        );
+        // UTC+8 时 20 点的操作属于次日
+        assert_eq!(
+            daily(8 * 60).await,
+            [
+                (day("01-02"), failed, 1),
+                (day("01-02"), ok, 1),
+                (day("01-01"), ok, 1),
+            ]
+        );
+        // UTC-11 时 10 点的操作属于前一天
+        assert_eq!(
+            daily(-11 * 60).await,
+            [
+                (day("01-01"), failed, 1),
+                (day("01-01"), ok, 1),
+                ("2023-12-31".to_owned(), ok, 1),
+            ]
+        );
+    }
 }
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use luneth::common::MagnetLink;
+use sea_orm::{
+    ConnectionTrait as _, DbBackend, EntityTrait as _, IdenStatic as _, QueryOrder as _,
+    QueryResult, QuerySelect as _, Statement,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Result;
+use super::impl_revision::RevisionSource;
+use crate::entities::{record_local::Column, record_local::Entity as entity};
+
+/// 检查 JSON 列时每次读取的记录数
+const JSON_SCAN_PAGE_SIZE: u64 = 500;
+
+/// 演员、类别等名称到链接的映射列
+const MAP_COLUMNS: [Column; 6] = [
+    Column::Director,
+    Column::Studio,
+    Column::Label,
+    Column::Series,
+    Column::Genre,
+    Column::Idols,
+];
+
+/// 记录的本地图片状态，包括回收站中的记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalImageState {
+    pub id: String,
+    pub local_image_count: i32,
+    pub is_cached_locally: bool,
+}
+
+/// 无法反序列化的 JSON 列
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvalidJsonColumn {
+    pub record_id: String,
+    pub column: String,
+    pub message: String,
+}
+
+impl super::DbOperator {
+    /// 全部记录的本地图片状态，按编号排序
+    pub async fn query_local_image_states(&self) -> Result<Vec<LocalImageState>> {
+        let states = entity::find()
+            .select_only()
+            .columns([Column::Id, Column::LocalImageCount, Column::IsCachedLocally])
+            .order_by_asc(Column::Id)
+            .into_tuple::<(String, i32, bool)>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(
+                |(id, local_image_count, is_cached_locally)| LocalImageState {
+                    id,
+                    local_image_count,
+                    is_cached_locally,
+                },
+            )
+            .collect();
+        Ok(states)
+    }
+
+    /// 修正记录的本地图片数与缓存标记，回收站中的记录同样适用
+    pub async fn set_local_image_state(
+        &self,
+        id: &str,
+        local_image_count: i32,
+        is_cached_locally: bool,
+        source: &RevisionSource,
+    ) -> Result<crate::record_local::Model> {
+        let record = entity::find_by_id(id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| crate::DbError::NotFound(format!("record_local {id}")))?;
+        self.update_record_local(
+            record.set_local_image_cached(local_image_count, is_cached_locally),
+            source,
+        )
+        .await
+    }
+
+    /// 找出无法反序列化为对应类型的 JSON 列
+    ///
+    /// 逐行以文本读取，不经过实体模型，单条损坏的记录不会导致整页读取失败。
+    pub async fn scan_invalid_json_local(&self) -> Result<Vec<InvalidJsonColumn>> {
+        let columns = MAP_COLUMNS
+            .iter()
+            .chain(&[Column::ShareMagnetLinks, Column::SampleImageLinks])
+            .map(|c| c.as_str())
+            .collect::<Vec<_>>();
+        let sql = format!(
+            "SELECT id, {} FROM record_local WHERE id > ? ORDER BY id LIMIT ?",
+            columns.join(", ")
+        );
+
+        let mut invalid = Vec::new();
+        let mut last_id = String::new();
+        loop {
+            let rows = self
+                .db
+                .query_all(Statement::from_sql_and_values(
+                    DbBackend::Sqlite,
+                    &sql,
+                    [last_id.clone().into(), JSON_SCAN_PAGE_SIZE.into()],
+                ))
+                .await?;
+            for row in &rows {
+                let id = row.try_get::<String>("", "id")?;
+                for column in &columns {
+                    if let Err(message) = check_json_column(row, column) {
+                        invalid.push(InvalidJsonColumn {
+                            record_id: id.clone(),
+                            column: (*column).to_owned(),
+                            message,
+                        });
+                    }
+                }
+                last_id = id;
+            }
+            if (rows.len() as u64) < JSON_SCAN_PAGE_SIZE {
+                break;
+            }
+        }
+        Ok(invalid)
+    }
+}
+
+/// 按列的约定类型解析 JSON 文本
+fn check_json_column(row: &QueryResult, column: &str) -> std::result::Result<(), String> {
+    let text = row
+        .try_get::<Option<String>>("", column)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "value is null".to_owned())?;
+    let parsed = if column == Column::ShareMagnetLinks.as_str() {
+        serde_json::from_str::<Vec<MagnetLink>>(&text).map(drop)
+    } else if column == Column::SampleImageLinks.as_str() {
+        serde_json::from_str::<Vec<String>>(&text).map(drop)
+    } else {
+        serde_json::from_str::<HashMap<String, String>>(&text).map(drop)
+    };
+    parsed.map_err(|e| e.to_string())
+}
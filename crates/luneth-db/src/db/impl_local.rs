@@ -1,45 +1,624 @@
 use std::str::FromStr;
 
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+
 use super::Result;
+use super::impl_revision::{RevisionSource, insert_revision};
+use super::impl_search::RANK_EXPR;
+use crate::DbError;
 use crate::entities::{
     record_local::ActiveModel as am, record_local::Entity as entity, record_local::Model as model,
 };
+use crate::types::AttributeKind;
 use sea_orm::{
-    ActiveModelTrait as _, ColumnTrait, Condition, DatabaseConnection, EntityTrait as _,
-    PaginatorTrait as _, QueryFilter as _, QuerySelect as _, sea_query::IntoCondition as _,
+    ActiveEnum as _, ActiveModelTrait as _, ColumnTrait, Condition, ConnectionTrait,
+    DatabaseConnection, DbErr, EntityTrait as _, FromQueryResult, IdenStatic as _, Order,
+    PaginatorTrait as _, QueryFilter as _, QueryOrder, QuerySelect as _, Select, TransactionTrait,
+    Value,
+    sea_query::{Expr, IntoCondition as _, OnConflict, SimpleExpr},
 };
+use serde::{Deserialize, Serialize};
+
+use crate::record_local::Column;
 
-#[derive(Debug)]
+/// 本地记录过滤条件
+///
+/// 以 serde 外部标签形式序列化：无参条件为字符串（如 `"viewed"`），
+/// 其余为单键对象（如 `{"not": "viewed"}`、`{"genre": ["剧情"]}`）。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum LocalFilterCondition {
     Viewed,
     Liked,
     Submit,
     Local,
+    /// 有私人备注
+    HasNote,
+    /// 条件取反
+    Not(Box<Self>),
+    /// 任一子条件满足；为空时不匹配任何记录
+    Any(Vec<Self>),
+    /// 全部子条件满足；为空时匹配所有记录
+    All(Vec<Self>),
+    /// 发行日期区间（`YYYY-MM-DD`，含端点）
+    ReleaseDate {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// 评分区间（含端点），0 表示未评分
+    Rating {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    /// 时长区间（分钟，含端点）
+    Length {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    /// 包含任一类别
+    Genre(Vec<String>),
+    /// 包含任一演员
+    Idol(Vec<String>),
+    /// 属于任一片商
+    Studio(Vec<String>),
+    /// 属于任一发行商
+    Label(Vec<String>),
+    /// 属于任一系列
+    Series(Vec<String>),
+    /// 属于任一导演
+    Director(Vec<String>),
+    /// 带有任一标签
+    Tag(Vec<String>),
+    /// 在任一列表中
+    Collection(Vec<i32>),
 }
 
 impl LocalFilterCondition {
     fn to_condition(&self) -> Condition {
         match self {
-            Self::Viewed => crate::record_local::Column::Viewed
-                .eq(true)
-                .into_condition(),
-            Self::Liked => crate::record_local::Column::IsLiked
-                .eq(true)
-                .into_condition(),
-            Self::Submit => crate::record_local::Column::IsSubmitted
-                .eq(true)
-                .into_condition(),
-            Self::Local => crate::record_local::Column::IsCachedLocally
-                .eq(true)
-                .into_condition(),
+            Self::Viewed => Column::Viewed.eq(true).into_condition(),
+            Self::Liked => Column::IsLiked.eq(true).into_condition(),
+            Self::Submit => Column::IsSubmitted.eq(true).into_condition(),
+            Self::Local => Column::IsCachedLocally.eq(true).into_condition(),
+            Self::HasNote => Column::Note.ne("").into_condition(),
+            Self::Not(inner) => inner.to_condition().not(),
+            Self::Any(filters) => filters
+                .iter()
+                .fold(Condition::any(), |acc, f| acc.add(f.to_condition())),
+            Self::All(filters) => filters
+                .iter()
+                .fold(Condition::all(), |acc, f| acc.add(f.to_condition())),
+            Self::ReleaseDate { from, to } => {
+                let mut cond = Condition::all();
+                if let Some(from) = from {
+                    cond = cond.add(Column::ReleaseDate.gte(from.as_str()));
+                }
+                if let Some(to) = to {
+                    cond = cond.add(Column::ReleaseDate.lte(to.as_str()));
+                }
+                cond
+            }
+            Self::Rating { min, max } => {
+                let mut cond = Condition::all();
+                if let Some(min) = min {
+                    cond = cond.add(Column::Rating.gte(*min));
+                }
+                if let Some(max) = max {
+                    cond = cond.add(Column::Rating.lte(*max));
+                }
+                cond
+            }
+            Self::Length { min, max } => {
+                // 时长以 "120分钟" 等形式存储，取其数字前缀
+                let length = || Expr::expr(Expr::cust("CAST(record_local.length AS INTEGER)"));
+                let mut cond = Condition::all();
+                if let Some(min) = min {
+                    cond = cond.add(length().gte(*min));
+                }
+                if let Some(max) = max {
+                    cond = cond.add(length().lte(*max));
+                }
+                cond
+            }
+            Self::Genre(names) => attribute_in(AttributeKind::Genre, names),
+            Self::Idol(names) => attribute_in(AttributeKind::Idol, names),
+            Self::Studio(names) => attribute_in(AttributeKind::Studio, names),
+            Self::Label(names) => attribute_in(AttributeKind::Label, names),
+            Self::Series(names) => attribute_in(AttributeKind::Series, names),
+            Self::Director(names) => attribute_in(AttributeKind::Director, names),
+            Self::Tag(names) => tag_in(names),
+            Self::Collection(ids) => collection_in(ids),
         }
     }
 }
 
-fn to_conditions(filters: Vec<LocalFilterCondition>) -> Condition {
-    filters
-        .into_iter()
-        .fold(Condition::all(), |acc, cond| acc.add(cond.to_condition()))
+/// 记录关联了该类别下任一给定名称的属性
+fn attribute_in(kind: AttributeKind, names: &[String]) -> Condition {
+    if names.is_empty() {
+        return Condition::any();
+    }
+    let placeholders = vec!["?"; names.len()].join(", ");
+    let values = std::iter::once(kind.to_value()).chain(names.iter().cloned());
+    let expr: SimpleExpr = Expr::cust_with_values(
+        format!(
+            "EXISTS (SELECT 1 FROM record_attribute \
+             JOIN attribute ON attribute.id = record_attribute.attribute_id \
+             WHERE record_attribute.record_id = record_local.id \
+             AND attribute.kind = ? AND attribute.name IN ({placeholders}))"
+        ),
+        values,
+    );
+    expr.into_condition()
+}
+
+/// 记录带有任一给定名称的标签
+fn tag_in(names: &[String]) -> Condition {
+    if names.is_empty() {
+        return Condition::any();
+    }
+    let placeholders = vec!["?"; names.len()].join(", ");
+    let expr: SimpleExpr = Expr::cust_with_values(
+        format!(
+            "EXISTS (SELECT 1 FROM record_tag \
+             JOIN tag ON tag.id = record_tag.tag_id \
+             WHERE record_tag.record_id = record_local.id \
+             AND tag.name IN ({placeholders}))"
+        ),
+        names.iter().cloned(),
+    );
+    expr.into_condition()
+}
+
+/// 记录在任一给定的列表中
+fn collection_in(ids: &[i32]) -> Condition {
+    if ids.is_empty() {
+        return Condition::any();
+    }
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let expr: SimpleExpr = Expr::cust_with_values(
+        format!(
+            "EXISTS (SELECT 1 FROM collection_record \
+             WHERE collection_record.record_id = record_local.id \
+             AND collection_record.collection_id IN ({placeholders}))"
+        ),
+        ids.iter().copied(),
+    );
+    expr.into_condition()
+}
+
+/// 组合过滤条件，回收站中的记录总是被排除
+pub(super) fn to_conditions(filters: Vec<LocalFilterCondition>) -> Condition {
+    Condition::all()
+        .add(Column::DeletedAt.is_null())
+        .add(LocalFilterCondition::All(filters).to_condition())
+}
+
+/// 在 `conn` 上执行 [`super::DbOperator::upsert_local`]，同时返回写入前的记录
+///
+/// `conn` 为事务时在保存点中执行，失败只回滚本条记录。记录在回收站中时不写入，
+/// 返回 [`DbError::RecordTrashed`]。
+pub(super) async fn upsert_local_in<C: ConnectionTrait + TransactionTrait>(
+    conn: &C,
+    local_record: am,
+    source: &RevisionSource,
+) -> Result<(Option<model>, model)> {
+    let id = match &local_record.id {
+        sea_orm::ActiveValue::Set(id) | sea_orm::ActiveValue::Unchanged(id) => id.clone(),
+        sea_orm::ActiveValue::NotSet => {
+            return Err(DbErr::Custom("record id is required for upsert".to_owned()).into());
+        }
+    };
+
+    let merge = upsert_merge();
+    let mut on_conflict = OnConflict::column(Column::Id);
+    let mut changed = Condition::any();
+    for (column, expr) in merge {
+        let name = column.as_str();
+        changed = changed.add(Expr::cust(format!("({expr}) IS NOT record_local.{name}")));
+        on_conflict.value(column, Expr::cust(expr));
+    }
+    on_conflict
+        .value(Column::UpdatedAt, Expr::cust("excluded.updated_at"))
+        .action_cond_where(changed);
+
+    let txn = conn.begin().await?;
+    let before = entity::find_by_id(id.as_str()).one(&txn).await?;
+    if before
+        .as_ref()
+        .is_some_and(|before| before.deleted_at.is_some())
+    {
+        return Err(DbError::RecordTrashed(id));
+    }
+    let saved = match entity::insert(local_record)
+        .on_conflict(on_conflict)
+        .exec_with_returning(&txn)
+        .await
+    {
+        Ok(model) => model,
+        // 没有变化时不会写入任何行
+        Err(DbErr::RecordNotInserted | DbErr::RecordNotFound(_)) => before
+            .clone()
+            .ok_or_else(|| DbErr::RecordNotFound(format!("record_local {id}")))?,
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(before) = &before {
+        insert_revision(&txn, before, &saved, source, None).await?;
+    }
+    txn.commit().await?;
+    Ok((before, saved))
+}
+
+/// 爬取结果写入已有记录时各列的合并表达式
+///
+/// - 已看、喜欢、已提交等用户状态与创建时间保持不变，不在此列出
+/// - 标题、演员等元数据取新值，新值为空时保留原值
+/// - 磁链按链接去重合并（新结果中重复的链接只取第一条），保留原有顺序
+/// - 本地图片数取较大值，任一方已缓存到本地即视为已缓存
+fn upsert_merge() -> Vec<(Column, String)> {
+    let mut merge = vec![];
+    for column in [
+        Column::Title,
+        Column::ReleaseDate,
+        Column::Length,
+        Column::Cover,
+    ] {
+        let name = column.as_str();
+        merge.push((
+            column,
+            format!("COALESCE(NULLIF(excluded.{name}, ''), record_local.{name})"),
+        ));
+    }
+    for column in [
+        Column::Director,
+        Column::Studio,
+        Column::Label,
+        Column::Series,
+        Column::Genre,
+        Column::Idols,
+        Column::SampleImageLinks,
+    ] {
+        let name = column.as_str();
+        merge.push((
+            column,
+            format!(
+                "CASE WHEN COALESCE(excluded.{name}, '') IN ('', 'null', '{{}}', '[]') \
+                 THEN record_local.{name} ELSE excluded.{name} END"
+            ),
+        ));
+    }
+    merge.push((
+        Column::ShareMagnetLinks,
+        "(SELECT json_group_array(json(link)) FROM ( \
+            SELECT value AS link FROM json_each(record_local.share_magnet_links) \
+            UNION ALL \
+            SELECT new.value FROM json_each(excluded.share_magnet_links) AS new \
+            WHERE NOT EXISTS ( \
+                SELECT 1 FROM json_each(record_local.share_magnet_links) AS old \
+                WHERE json_extract(old.value, '$.link') IS json_extract(new.value, '$.link')) \
+            AND NOT EXISTS ( \
+                SELECT 1 FROM json_each(excluded.share_magnet_links) AS dup \
+                WHERE dup.key < new.key \
+                AND json_extract(dup.value, '$.link') IS json_extract(new.value, '$.link')) \
+        ))"
+        .to_owned(),
+    ));
+    merge.push((
+        Column::LocalImageCount,
+        "MAX(record_local.local_image_count, excluded.local_image_count)".to_owned(),
+    ));
+    merge.push((
+        Column::IsCachedLocally,
+        "(record_local.is_cached_locally OR excluded.is_cached_locally)".to_owned(),
+    ));
+    merge
+}
+
+/// 排序字段
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    ReleaseDate,
+    CreatedAt,
+    #[default]
+    UpdatedAt,
+    Title,
+    Id,
+    LocalImageCount,
+    Rating,
+    ViewCount,
+}
+
+impl SortField {
+    fn column(self) -> Column {
+        match self {
+            Self::ReleaseDate => Column::ReleaseDate,
+            Self::CreatedAt => Column::CreatedAt,
+            Self::UpdatedAt => Column::UpdatedAt,
+            Self::Title => Column::Title,
+            Self::Id => Column::Id,
+            Self::LocalImageCount => Column::LocalImageCount,
+            Self::Rating => Column::Rating,
+            Self::ViewCount => Column::ViewCount,
+        }
+    }
+}
+
+/// 排序方向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    fn reversed(self) -> Self {
+        match self {
+            Self::Asc => Self::Desc,
+            Self::Desc => Self::Asc,
+        }
+    }
+}
+
+impl From<SortOrder> for Order {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Asc => Self::Asc,
+            SortOrder::Desc => Self::Desc,
+        }
+    }
+}
+
+/// 本地记录排序方式，默认按更新时间倒序
+///
+/// 排序值相同的记录再按编号同向排序，保证分页结果稳定。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortSpec {
+    #[serde(default)]
+    pub field: SortField,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+impl SortSpec {
+    pub(super) fn apply<Q: QueryOrder>(self, query: Q) -> Q {
+        let query = query.order_by(self.field.column(), self.order.into());
+        if self.field == SortField::Id {
+            query
+        } else {
+            query.order_by(Column::Id, self.order.into())
+        }
+    }
+}
+
+/// 分页起点
+///
+/// 以 serde 外部标签形式序列化：`"first"`、`{"offset": 60}` 或 `{"cursor": "..."}`。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageStart {
+    /// 第一页
+    #[default]
+    First,
+    /// 跳过指定条数，用于直接跳页
+    Offset(u64),
+    /// 上一次返回的 `next_cursor` 或 `prev_cursor`
+    Cursor(String),
+}
+
+/// 游标分页的一页记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordPage<T = model> {
+    pub records: Vec<T>,
+    /// 满足过滤条件的记录总数
+    pub total: u64,
+    /// 下一页游标，已是最后一页时为 `None`
+    pub next_cursor: Option<String>,
+    /// 上一页游标，已是第一页时为 `None`
+    pub prev_cursor: Option<String>,
+}
+
+/// 可以按游标分页的查询结果
+pub(super) trait PageItem: FromQueryResult + Send + Sync {
+    /// 作为游标锚点的记录
+    fn record(&self) -> &model;
+
+    /// 全文检索相关度，只在按相关度分页时使用
+    fn rank(&self) -> f64 {
+        0.0
+    }
+}
+
+impl PageItem for model {
+    fn record(&self) -> &model {
+        self
+    }
+}
+
+/// 游标分页的排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum PageOrder {
+    /// 按 [`SortSpec`] 排序
+    Sort(SortSpec),
+    /// 按删除时间倒序，用于回收站
+    Trashed,
+    /// 按全文检索相关度排序，越相关越靠前
+    Relevance,
+}
+
+impl PageOrder {
+    /// 主排序表达式与方向，按编号排序时没有主排序表达式
+    ///
+    /// 排序值相同的记录再按编号同向排序。
+    fn key(self) -> (Option<SimpleExpr>, SortOrder) {
+        match self {
+            Self::Sort(SortSpec {
+                field: SortField::Id,
+                order,
+            }) => (None, order),
+            Self::Sort(sort) => (
+                Some(Expr::col((entity, sort.field.column())).into()),
+                sort.order,
+            ),
+            Self::Trashed => (
+                Some(Expr::col((entity, Column::DeletedAt)).into()),
+                SortOrder::Desc,
+            ),
+            Self::Relevance => (Some(Expr::cust(RANK_EXPR)), SortOrder::Asc),
+        }
+    }
+
+    fn apply<Q: QueryOrder>(self, query: Q, reversed: bool) -> Q {
+        let (key, order) = self.key();
+        let order = if reversed { order.reversed() } else { order };
+        let query = match key {
+            Some(key) => query.order_by(key, order.into()),
+            None => query,
+        };
+        query.order_by(Expr::col((entity, Column::Id)), order.into())
+    }
+
+    fn value(self, item: &impl PageItem) -> CursorValue {
+        let anchor = item.record();
+        match self {
+            Self::Sort(sort) => match sort.field {
+                SortField::ReleaseDate => CursorValue::Text(anchor.release_date.clone()),
+                SortField::CreatedAt => CursorValue::Text(anchor.created_at.to_rfc3339()),
+                SortField::UpdatedAt => CursorValue::Text(anchor.updated_at.to_rfc3339()),
+                SortField::Title => CursorValue::Text(anchor.title.clone()),
+                SortField::Id => CursorValue::Text(anchor.id.clone()),
+                SortField::LocalImageCount => CursorValue::Int(anchor.local_image_count.into()),
+                SortField::Rating => CursorValue::Int(anchor.rating.into()),
+                SortField::ViewCount => CursorValue::Int(anchor.view_count.into()),
+            },
+            // 回收站中的记录总有删除时间
+            Self::Trashed => CursorValue::Text(
+                anchor
+                    .deleted_at
+                    .map(|time| time.to_rfc3339())
+                    .unwrap_or_default(),
+            ),
+            Self::Relevance => CursorValue::Real(item.rank()),
+        }
+    }
+
+    fn sort_value(self, value: &CursorValue) -> Result<Value> {
+        match (self, value) {
+            (Self::Trashed, CursorValue::Text(time)) => parse_cursor_time(time),
+            (Self::Sort(sort), CursorValue::Text(time))
+                if matches!(sort.field, SortField::CreatedAt | SortField::UpdatedAt) =>
+            {
+                parse_cursor_time(time)
+            }
+            (Self::Sort(sort), CursorValue::Int(value))
+                if matches!(
+                    sort.field,
+                    SortField::LocalImageCount | SortField::Rating | SortField::ViewCount
+                ) =>
+            {
+                Ok((*value).into())
+            }
+            (Self::Sort(sort), CursorValue::Text(text))
+                if matches!(
+                    sort.field,
+                    SortField::ReleaseDate | SortField::Title | SortField::Id
+                ) =>
+            {
+                Ok(text.clone().into())
+            }
+            (Self::Relevance, CursorValue::Real(rank)) => Ok((*rank).into()),
+            _ => Err(DbError::InvalidCursor(format!(
+                "value does not match page order {self:?}"
+            ))),
+        }
+    }
+}
+
+fn parse_cursor_time(time: &str) -> Result<Value> {
+    let time =
+        DateTime::parse_from_rfc3339(time).map_err(|e| DbError::InvalidCursor(e.to_string()))?;
+    Ok(time.with_timezone(&Utc).into())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CursorDirection {
+    After,
+    Before,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum CursorValue {
+    Int(i64),
+    Real(f64),
+    Text(String),
+}
+
+/// 游标内容：锚点记录的排序值与编号
+///
+/// 对外以 base64 编码的 JSON 传递，调用方不应解析其内容。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Cursor {
+    order: PageOrder,
+    direction: CursorDirection,
+    value: CursorValue,
+    id: String,
+}
+
+impl Cursor {
+    fn new(order: PageOrder, direction: CursorDirection, anchor: &impl PageItem) -> Self {
+        Self {
+            order,
+            direction,
+            value: order.value(anchor),
+            id: anchor.record().id.clone(),
+        }
+    }
+
+    fn encode(&self) -> String {
+        // 仅包含字符串与数值，序列化不会失败
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    fn decode(cursor: &str) -> Result<Self> {
+        let json = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .map_err(|e| DbError::InvalidCursor(e.to_string()))?;
+        serde_json::from_slice(&json).map_err(|e| DbError::InvalidCursor(e.to_string()))
+    }
+
+    /// 位于锚点之后（按游标方向）的记录
+    fn seek_condition(&self) -> Result<Condition> {
+        let (key, order) = self.order.key();
+        let ascending = (order == SortOrder::Asc) == (self.direction == CursorDirection::After);
+        let beyond = |expr: SimpleExpr, value: Value| {
+            if ascending {
+                Expr::expr(expr).gt(value)
+            } else {
+                Expr::expr(expr).lt(value)
+            }
+        };
+
+        let id_expr: SimpleExpr = Expr::col((entity, Column::Id)).into();
+        let id = Value::from(self.id.clone());
+        let Some(key) = key else {
+            return Ok(beyond(id_expr, id).into_condition());
+        };
+
+        let value = self.order.sort_value(&self.value)?;
+        Ok(Condition::any()
+            .add(beyond(key.clone(), value.clone()))
+            .add(
+                Condition::all()
+                    .add(Expr::expr(key).eq(value))
+                    .add(beyond(id_expr, id)),
+            ))
+    }
 }
 
 impl FromStr for LocalFilterCondition {
@@ -51,6 +630,7 @@ impl FromStr for LocalFilterCondition {
             "liked" => Ok(Self::Liked),
             "submit" => Ok(Self::Submit),
             "local" => Ok(Self::Local),
+            "has_note" => Ok(Self::HasNote),
             _ => Err(format!("Invalid filter condition: {s}")),
         }
     }
@@ -62,26 +642,22 @@ impl super::DbOperator {
         &self.db
     }
     pub async fn insert_local(&self, local_record: am) -> Result<model> {
-        let result = local_record.insert(&self.db).await?;
+        let id = local_record.id.clone().into_value();
+        let result = local_record
+            .insert(&self.db)
+            .await
+            .map_err(|e| super::with_id_key(e.into(), id))?;
         Ok(result)
     }
 
-    /// if entity exist do noting, return success insert count
-    pub async fn or_insert_local<AM>(&self, local_record: am) -> Result<model> {
-        // Try to insert, if it fails due to unique constraint, ignore and return a default model
-        match local_record.insert(&self.db).await {
-            Ok(model) => Ok(model),
-            Err(sea_orm::DbErr::Exec(_)) => {
-                // For now, we'll just try to insert again and if it fails, propagate the error
-                // This is a simplified implementation - a more robust solution would
-                // extract the primary key and query for the existing record
-                Err(sea_orm::DbErr::Custom(
-                    "Record already exists or constraint violation".to_owned(),
-                )
-                .into())
-            }
-            Err(e) => Err(e.into()),
-        }
+    /// 写入爬取到的记录，已存在时按列合并
+    ///
+    /// 合并方式见 [`upsert_merge`]。合并后没有任何变化时不更新记录（也不刷新
+    /// `updated_at`），返回合并后的记录。已有记录的元数据发生变化时写入修订历史。
+    /// 回收站中的记录不会被改写，返回 [`DbError::RecordTrashed`]。
+    pub async fn upsert_local(&self, local_record: am, source: &RevisionSource) -> Result<model> {
+        let (_, saved) = upsert_local_in(&self.db, local_record, source).await?;
+        Ok(saved)
     }
 
     pub async fn query_total_count(&self, filters: Vec<LocalFilterCondition>) -> Result<u64> {
@@ -92,20 +668,17 @@ impl super::DbOperator {
         Ok(count)
     }
 
-    pub async fn search_local(
+    /// 根据过滤条件查询记录，按 `sort` 排序
+    pub async fn query_local(
         &self,
-        name: String,
         offset: Option<u64>,
         limit: Option<u64>,
         filters: Vec<LocalFilterCondition>,
-    ) -> Result<(u64, Vec<model>)> {
-        let condition = to_conditions(filters).add(
-            crate::record_local::Column::Id
-                .like(format!("%{name}%"))
-                .into_condition(),
-        );
+        sort: SortSpec,
+    ) -> Result<Vec<model>> {
+        let condition = to_conditions(filters);
 
-        let mut query = entity::find().filter(condition);
+        let mut query = sort.apply(entity::find().filter(condition));
 
         if let Some(offset) = offset {
             query = query.offset(offset);
@@ -115,37 +688,116 @@ impl super::DbOperator {
             query = query.limit(limit);
         }
 
-        let results = query.clone().all(&self.db).await?;
-        let count = query.count(&self.db).await?;
-        Ok((count, results))
+        let results = query.all(&self.db).await?;
+        Ok(results)
     }
 
-    /// 根据过滤条件查询记录-
-    pub async fn query_local(
+    /// 按游标分页查询记录
+    ///
+    /// 与偏移分页不同，翻页期间插入新记录不会导致结果重复或遗漏。游标与生成它时的
+    /// `sort` 绑定，排序方式改变后需从第一页重新开始。
+    pub async fn query_local_page(
         &self,
-        offset: Option<u64>,
-        limit: Option<u64>,
         filters: Vec<LocalFilterCondition>,
-    ) -> Result<Vec<model>> {
-        let condition = to_conditions(filters);
+        sort: SortSpec,
+        start: PageStart,
+        limit: u64,
+    ) -> Result<RecordPage> {
+        let total = self.query_total_count(filters.clone()).await?;
+        let query = entity::find().filter(to_conditions(filters));
+        self.query_page(query, total, PageOrder::Sort(sort), start, limit)
+            .await
+    }
 
-        let mut query = entity::find().filter(condition);
+    /// 按 `order` 游标分页查询 `query` 的结果，`total` 为结果总数
+    pub(super) async fn query_page<T: PageItem>(
+        &self,
+        query: Select<entity>,
+        total: u64,
+        order: PageOrder,
+        start: PageStart,
+        limit: u64,
+    ) -> Result<RecordPage<T>> {
+        let (direction, query) = match &start {
+            PageStart::First => (CursorDirection::After, order.apply(query, false)),
+            PageStart::Offset(offset) => (
+                CursorDirection::After,
+                order.apply(query, false).offset(*offset),
+            ),
+            PageStart::Cursor(cursor) => {
+                let cursor = Cursor::decode(cursor)?;
+                if cursor.order != order {
+                    return Err(DbError::InvalidCursor(format!(
+                        "cursor was created for {:?}, not {order:?}",
+                        cursor.order
+                    )));
+                }
+                let query = query.filter(cursor.seek_condition()?);
+                match cursor.direction {
+                    CursorDirection::After => (CursorDirection::After, order.apply(query, false)),
+                    CursorDirection::Before => (CursorDirection::Before, order.apply(query, true)),
+                }
+            }
+        };
 
-        if let Some(offset) = offset {
-            query = query.offset(offset);
-        }
+        // 多取一条用于判断该方向上是否还有记录
+        let mut records = query
+            .limit(limit.saturating_add(1))
+            .into_model::<T>()
+            .all(&self.db)
+            .await?;
+        let page_len = usize::try_from(limit).unwrap_or(usize::MAX);
+        let has_more = records.len() > page_len;
+        records.truncate(page_len);
 
-        if let Some(limit) = limit {
-            query = query.limit(limit);
-        }
+        let has_before = match direction {
+            CursorDirection::After => match &start {
+                PageStart::First => false,
+                PageStart::Offset(offset) => *offset > 0,
+                PageStart::Cursor(_) => true,
+            },
+            CursorDirection::Before => {
+                records.reverse();
+                has_more
+            }
+        };
+        let has_after = direction == CursorDirection::Before || has_more;
 
-        let results = query.all(&self.db).await?;
-        Ok(results)
+        let next_cursor = records
+            .last()
+            .filter(|_| has_after)
+            .map(|last| Cursor::new(order, CursorDirection::After, last).encode());
+        let prev_cursor = records
+            .first()
+            .filter(|_| has_before)
+            .map(|first| Cursor::new(order, CursorDirection::Before, first).encode());
+
+        Ok(RecordPage {
+            records,
+            total,
+            next_cursor,
+            prev_cursor,
+        })
     }
 
-    /// 更新记录
-    pub async fn update_record_local(&self, local_record: am) -> Result<model> {
-        let result = local_record.update(&self.db).await?;
+    /// 更新记录，元数据发生变化时写入修订历史
+    pub async fn update_record_local(
+        &self,
+        local_record: am,
+        source: &RevisionSource,
+    ) -> Result<model> {
+        let txn = self.db.begin().await?;
+        let before = match &local_record.id {
+            sea_orm::ActiveValue::Set(id) | sea_orm::ActiveValue::Unchanged(id) => {
+                entity::find_by_id(id.as_str()).one(&txn).await?
+            }
+            sea_orm::ActiveValue::NotSet => None,
+        };
+        let result = local_record.update(&txn).await?;
+        if let Some(before) = &before {
+            insert_revision(&txn, before, &result, source, None).await?;
+        }
+        txn.commit().await?;
         Ok(result)
     }
 
@@ -155,19 +807,79 @@ impl super::DbOperator {
         Ok(())
     }
 
-    /// 根据ID查询单个记录
+    /// 将记录移入回收站，已在回收站中的记录保留原删除时间
+    ///
+    /// 不修改 `updated_at`，恢复后记录回到原来的排序位置。
+    pub async fn trash_record_local(&self, id: &str) -> Result<model> {
+        entity::update_many()
+            .col_expr(Column::DeletedAt, Expr::value(Utc::now()))
+            .filter(Column::Id.eq(id))
+            .filter(Column::DeletedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        self.find_record_local_by_id(id)
+            .await?
+            .ok_or_else(|| DbError::NotFound(format!("record_local {id}")))
+    }
+
+    /// 从回收站恢复记录
+    pub async fn restore_record_local(&self, id: &str) -> Result<model> {
+        entity::update_many()
+            .col_expr(
+                Column::DeletedAt,
+                Expr::value(Option::<DateTime<Utc>>::None),
+            )
+            .filter(Column::Id.eq(id))
+            .exec(&self.db)
+            .await?;
+        self.find_record_local_by_id(id)
+            .await?
+            .ok_or_else(|| DbError::NotFound(format!("record_local {id}")))
+    }
+
+    /// 彻底删除回收站中的记录，返回被删除的记录
+    ///
+    /// 未移入回收站的记录不会被删除。
+    pub async fn purge_record_local(&self, id: &str) -> Result<model> {
+        let txn = self.db.begin().await?;
+        let record = entity::find_by_id(id)
+            .one(&txn)
+            .await?
+            .filter(|record| record.deleted_at.is_some())
+            .ok_or_else(|| DbError::NotFound(format!("record_local {id} in trash")))?;
+        entity::delete_many()
+            .filter(Column::Id.eq(id))
+            .filter(Column::DeletedAt.is_not_null())
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+        Ok(record)
+    }
+
+    /// 按游标分页查询回收站中的记录，最近删除的在前
+    pub async fn query_trash_local(&self, start: PageStart, limit: u64) -> Result<RecordPage> {
+        let query = entity::find().filter(Column::DeletedAt.is_not_null());
+        let total = query.clone().count(&self.db).await?;
+        self.query_page(query, total, PageOrder::Trashed, start, limit)
+            .await
+    }
+
+    /// 根据ID查询单个记录（包括回收站中的记录）
     pub async fn find_record_local_by_id(&self, id: &str) -> Result<Option<model>> {
         let result = entity::find_by_id(id).one(&self.db).await?;
         Ok(result)
     }
 
     /// Query specified column values from an entity table
+    ///
+    /// Trashed records are excluded, matching the list queries.
     pub async fn query_specified_column<C, T>(&self, column: C) -> Result<Vec<T>>
     where
         C: ColumnTrait,
         T: sea_orm::TryGetable + Send,
     {
         let results: Vec<T> = entity::find()
+            .filter(Column::DeletedAt.is_null())
             .select_only()
             .column(column)
             .into_tuple()
@@ -177,3 +889,398 @@ impl super::DbOperator {
         Ok(results)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Set;
+
+    use super::*;
+    use crate::test_util::{attribute, memory_db, record};
+    use crate::types::OperationType;
+
+    fn sort(field: SortField, order: SortOrder) -> SortSpec {
+        SortSpec { field, order }
+    }
+
+    fn ids(records: &[model]) -> Vec<&str> {
+        records.iter().map(|r| r.id.as_str()).collect()
+    }
+
+    /// 五条记录：发行日期依次递增，评分、状态与类别各不相同
+    async fn seeded_db() -> super::super::DbOperator {
+        let db = memory_db().await;
+        let rows = [
+            ("A-1", 5, true, false, &["Drama"][..]),
+            ("A-2", 0, false, true, &["Comedy"][..]),
+            ("A-3", 3, true, true, &["Drama", "Comedy"][..]),
+            ("A-4", 1, false, false, &[][..]),
+            ("A-5", 4, true, false, &["Action"][..]),
+        ];
+        for (i, (id, rating, viewed, liked, genres)) in rows.into_iter().enumerate() {
+            let mut r = record(id);
+            r.release_date = Set(format!("2020-01-0{}", i + 1));
+            r.rating = Set(rating);
+            r.viewed = Set(viewed);
+            r.is_liked = Set(liked);
+            r.genre = Set(attribute(genres));
+            db.insert_local(r).await.expect("insert");
+        }
+        db
+    }
+
+    #[tokio::test]
+    async fn query_local_filters() {
+        let db = seeded_db().await;
+        let by_date = sort(SortField::ReleaseDate, SortOrder::Asc);
+        let cases = [
+            (vec![], vec!["A-1", "A-2", "A-3", "A-4", "A-5"]),
+            (
+                vec![LocalFilterCondition::Viewed],
+                vec!["A-1", "A-3", "A-5"],
+            ),
+            (
+                vec![LocalFilterCondition::Viewed, LocalFilterCondition::Liked],
+                vec!["A-3"],
+            ),
+            (
+                vec![LocalFilterCondition::Not(Box::new(
+                    LocalFilterCondition::Viewed,
+                ))],
+                vec!["A-2", "A-4"],
+            ),
+            (
+                vec![LocalFilterCondition::Any(vec![
+                    LocalFilterCondition::Liked,
+                    LocalFilterCondition::Rating {
+                        min: Some(5),
+                        max: None,
+                    },
+                ])],
+                vec!["A-1", "A-2", "A-3"],
+            ),
+            (vec![LocalFilterCondition::Any(vec![])], vec![]),
+            (
+                vec![LocalFilterCondition::ReleaseDate {
+                    from: Some("2020-01-02".to_owned()),
+                    to: Some("2020-01-04".to_owned()),
+                }],
+                vec!["A-2", "A-3", "A-4"],
+            ),
+            (
+                vec![LocalFilterCondition::Rating {
+                    min: Some(1),
+                    max: Some(4),
+                }],
+                vec!["A-3", "A-4", "A-5"],
+            ),
+            (
+                vec![LocalFilterCondition::Genre(vec![
+                    "Comedy".to_owned(),
+                    "Action".to_owned(),
+                ])],
+                vec!["A-2", "A-3", "A-5"],
+            ),
+        ];
+
+        for (filters, expected) in cases {
+            let records = db
+                .query_local(None, None, filters.clone(), by_date)
+                .await
+                .expect("query");
+            assert_eq!(ids(&records), expected, "filters: {filters:?}");
+            let total = db.query_total_count(filters.clone()).await.expect("count");
+            assert_eq!(total, expected.len() as u64, "filters: {filters:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn query_local_sorts_and_pages_by_offset() {
+        let db = seeded_db().await;
+        let by_rating = sort(SortField::Rating, SortOrder::Desc);
+
+        let first = db
+            .query_local(None, Some(2), vec![], by_rating)
+            .await
+            .expect("first page");
+        let second = db
+            .query_local(Some(2), Some(2), vec![], by_rating)
+            .await
+            .expect("second page");
+        let last = db
+            .query_local(Some(4), Some(2), vec![], by_rating)
+            .await
+            .expect("last page");
+        assert_eq!(ids(&first), ["A-1", "A-5"]);
+        assert_eq!(ids(&second), ["A-3", "A-4"]);
+        assert_eq!(ids(&last), ["A-2"]);
+    }
+
+    #[tokio::test]
+    async fn query_local_page_walks_duplicate_sort_keys() {
+        let db = memory_db().await;
+        // 评分只有两种取值，大量记录的排序值相同
+        for i in 1..=7 {
+            let mut r = record(&format!("B-{i}"));
+            r.rating = Set(if i % 2 == 0 { 4 } else { 2 });
+            db.insert_local(r).await.expect("insert");
+        }
+        let by_rating = sort(SortField::Rating, SortOrder::Desc);
+        let expected = ["B-6", "B-4", "B-2", "B-7", "B-5", "B-3", "B-1"];
+
+        // 向后翻页
+        let mut pages = vec![];
+        let mut start = PageStart::First;
+        loop {
+            let page = db
+                .query_local_page(vec![], by_rating, start, 3)
+                .await
+                .expect("page");
+            assert_eq!(page.total, 7);
+            assert_eq!(page.prev_cursor.is_some(), !pages.is_empty());
+            let next = page.next_cursor.clone();
+            pages.push(page);
+            match next {
+                Some(cursor) => start = PageStart::Cursor(cursor),
+                None => break,
+            }
+        }
+        let forward: Vec<_> = pages.iter().map(|page| ids(&page.records)).collect();
+        assert_eq!(
+            forward,
+            [&expected[0..3], &expected[3..6], &expected[6..7]],
+            "forward pages"
+        );
+
+        // 从最后一页向前翻页
+        let mut backward = vec![];
+        let mut cursor = pages.last().and_then(|page| page.prev_cursor.clone());
+        while let Some(prev) = cursor {
+            let page = db
+                .query_local_page(vec![], by_rating, PageStart::Cursor(prev), 3)
+                .await
+                .expect("page");
+            assert!(page.next_cursor.is_some(), "earlier pages have a next page");
+            backward.push(ids(&page.records).join(","));
+            cursor = page.prev_cursor;
+        }
+        assert_eq!(backward, ["B-7,B-5,B-3", "B-6,B-4,B-2"], "backward pages");
+
+        // 翻页期间在已读位置之前插入的记录不会导致下一页重复
+        let mut r = record("B-9");
+        r.rating = Set(4);
+        db.insert_local(r).await.expect("insert");
+        let next = pages[0].next_cursor.clone().expect("next cursor");
+        let page = db
+            .query_local_page(vec![], by_rating, PageStart::Cursor(next), 3)
+            .await
+            .expect("page");
+        assert_eq!(ids(&page.records), &expected[3..6]);
+    }
+
+    #[tokio::test]
+    async fn query_local_page_rejects_foreign_cursor() {
+        let db = seeded_db().await;
+        let by_rating = sort(SortField::Rating, SortOrder::Desc);
+        let page = db
+            .query_local_page(vec![], by_rating, PageStart::First, 2)
+            .await
+            .expect("page");
+        let cursor = page.next_cursor.expect("next cursor");
+
+        for other in [
+            sort(SortField::Rating, SortOrder::Asc),
+            sort(SortField::ReleaseDate, SortOrder::Desc),
+        ] {
+            let result = db
+                .query_local_page(vec![], other, PageStart::Cursor(cursor.clone()), 2)
+                .await;
+            assert!(
+                matches!(result, Err(DbError::InvalidCursor(_))),
+                "cursor should be rejected for {other:?}"
+            );
+        }
+        let result = db
+            .query_local_page(
+                vec![],
+                by_rating,
+                PageStart::Cursor("not a cursor".to_owned()),
+                2,
+            )
+            .await;
+        assert!(matches!(result, Err(DbError::InvalidCursor(_))));
+    }
+
+    #[tokio::test]
+    async fn query_local_excludes_trash() {
+        let db = seeded_db().await;
+        db.trash_record_local("A-3").await.expect("trash");
+
+        let records = db
+            .query_local(
+                None,
+                None,
+                vec![LocalFilterCondition::Viewed],
+                SortSpec::default(),
+            )
+            .await
+            .expect("query");
+        let mut found = ids(&records);
+        found.sort_unstable();
+        assert_eq!(found, ["A-1", "A-5"]);
+        assert_eq!(db.query_total_count(vec![]).await.expect("count"), 4);
+    }
+
+    #[tokio::test]
+    async fn query_trash_local_pages_by_deleted_time() {
+        let db = seeded_db().await;
+        for (day, id) in [(1, "A-2"), (3, "A-4"), (2, "A-1")] {
+            db.trash_record_local(id).await.expect("trash");
+            let deleted_at = format!("2024-01-0{day}T00:00:00Z")
+                .parse::<DateTime<Utc>>()
+                .expect("time");
+            entity::update_many()
+                .col_expr(Column::DeletedAt, Expr::value(deleted_at))
+                .filter(Column::Id.eq(id))
+                .exec(db.get_db())
+                .await
+                .expect("set deleted_at");
+        }
+
+        let first = db
+            .query_trash_local(PageStart::First, 2)
+            .await
+            .expect("first page");
+        assert_eq!(first.total, 3);
+        assert_eq!(ids(&first.records), ["A-4", "A-1"]);
+        assert!(first.prev_cursor.is_none());
+
+        let next = first.next_cursor.expect("next cursor");
+        let second = db
+            .query_trash_local(PageStart::Cursor(next), 2)
+            .await
+            .expect("second page");
+        assert_eq!(ids(&second.records), ["A-2"]);
+        assert!(second.next_cursor.is_none());
+
+        // 回收站的游标不能用于普通列表
+        let prev = second.prev_cursor.expect("prev cursor");
+        let result = db
+            .query_local_page(vec![], SortSpec::default(), PageStart::Cursor(prev), 2)
+            .await;
+        assert!(matches!(result, Err(DbError::InvalidCursor(_))));
+    }
+
+    #[tokio::test]
+    async fn purge_record_local_requires_trash() {
+        let db = seeded_db().await;
+
+        let result = db.purge_record_local("A-1").await;
+        assert!(matches!(result, Err(DbError::NotFound(_))));
+        assert!(
+            db.find_record_local_by_id("A-1")
+                .await
+                .expect("find")
+                .is_some()
+        );
+
+        db.trash_record_local("A-1").await.expect("trash");
+        let purged = db.purge_record_local("A-1").await.expect("purge");
+        assert_eq!(purged.id, "A-1");
+        assert!(
+            db.find_record_local_by_id("A-1")
+                .await
+                .expect("find")
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn upsert_local_inserts_then_merges() {
+        let db = memory_db().await;
+        let source = RevisionSource::new(OperationType::CrawlRecord);
+
+        let mut r = record("A-1");
+        r.genre = Set(attribute(&["Drama"]));
+        let inserted = db.upsert_local(r, &source).await.expect("insert");
+        assert_eq!(inserted.title, "Title A-1");
+
+        let mut r = record("A-1");
+        r.title = Set("New title".to_owned());
+        r.genre = Set(attribute(&[]));
+        let merged = db.upsert_local(r, &source).await.expect("merge");
+        assert_eq!(merged.title, "New title");
+        // 空的新值不覆盖原有属性
+        assert_eq!(merged.genre, attribute(&["Drama"]));
+        assert_eq!(db.query_total_count(vec![]).await.expect("count"), 1);
+    }
+
+    #[tokio::test]
+    async fn upsert_local_merges_magnet_links() {
+        let db = memory_db().await;
+        let source = RevisionSource::new(OperationType::CrawlRecord);
+
+        let mut r = record("A-1");
+        r.share_magnet_links = Set(serde_json::json!([{ "link": "a" }, { "name": "no link" }]));
+        db.upsert_local(r, &source).await.expect("insert");
+
+        // 原有的无链接条目不影响合并，新结果中的重复链接只保留第一条
+        let mut r = record("A-1");
+        r.share_magnet_links = Set(serde_json::json!([
+            { "link": "b" },
+            { "link": "a", "size": "1GB" },
+            { "link": "b", "size": "2GB" },
+            { "name": "no link" },
+        ]));
+        let merged = db.upsert_local(r, &source).await.expect("merge");
+        assert_eq!(
+            merged.share_magnet_links,
+            serde_json::json!([{ "link": "a" }, { "name": "no link" }, { "link": "b" }])
+        );
+    }
+
+    #[tokio::test]
+    async fn upsert_local_keeps_unchanged_record() {
+        let db = memory_db().await;
+        let source = RevisionSource::new(OperationType::CrawlRecord);
+
+        let mut r = record("A-1");
+        r.genre = Set(attribute(&["Drama"]));
+        let inserted = db.upsert_local(r, &source).await.expect("insert");
+
+        let mut r = record("A-1");
+        r.genre = Set(attribute(&["Drama"]));
+        let saved = db.upsert_local(r, &source).await.expect("upsert");
+        assert_eq!(saved, inserted, "unchanged record should not be rewritten");
+        let stored = db
+            .find_record_local_by_id("A-1")
+            .await
+            .expect("find")
+            .expect("record");
+        assert_eq!(stored.updated_at, inserted.updated_at);
+    }
+
+    #[tokio::test]
+    async fn upsert_local_skips_trashed_record() {
+        let db = memory_db().await;
+        let source = RevisionSource::new(OperationType::CrawlRecord);
+        db.upsert_local(record("A-1"), &source)
+            .await
+            .expect("insert");
+        let trashed = db.trash_record_local("A-1").await.expect("trash");
+
+        let mut r = record("A-1");
+        r.title = Set("New title".to_owned());
+        let result = db.upsert_local(r, &source).await;
+        assert!(
+            matches!(&result, Err(DbError::RecordTrashed(id)) if id == "A-1"),
+            "unexpected result: {result:?}"
+        );
+
+        let stored = db
+            .find_record_local_by_id("A-1")
+            .await
+            .expect("find")
+            .expect("record");
+        assert_eq!(stored, trashed, "trashed record should stay untouched");
+    }
+}
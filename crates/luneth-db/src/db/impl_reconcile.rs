@@ -0,0 +1,260 @@
+use std::collections::{HashMap, HashSet};
+
+use luneth::common::MagnetLink;
+use sea_orm::prelude::ChronoDateTimeUtc;
+use sea_orm::{
+    ColumnTrait as _, ConnectionTrait as _, DbBackend, EntityTrait as _, QueryFilter as _,
+    QuerySelect as _, Statement, TransactionTrait as _, sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Result;
+use crate::entities::{record_local, record_remote};
+
+/// 批量更新提交标记时每条语句包含的编号数
+const MARK_SUBMITTED_CHUNK_SIZE: usize = 500;
+
+/// 本地磁链多于远端的记录
+///
+/// 远端精简记录不含图片信息，图片数不参与判断，仅随结果返回供参考。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RicherRecord {
+    pub id: String,
+    /// 远端没有的磁链数
+    pub missing_links: usize,
+    pub local_image_count: i32,
+    pub is_cached_locally: bool,
+}
+
+/// 本地与远端记录的比对结果
+///
+/// 回收站中的记录不计入本地，远端也有的单独列出。是否比远端更完整只按磁链判断，
+/// 见 [`RicherRecord`]。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReconcileReport {
+    pub local_only: Vec<String>,
+    pub remote_only: Vec<String>,
+    pub both: Vec<String>,
+    pub richer_than_remote: Vec<RicherRecord>,
+    /// 本地已移入回收站、远端仍存在的记录
+    pub trashed_in_remote: Vec<String>,
+    /// 已在远端、本次标记为已提交的记录
+    pub marked_submitted: Vec<String>,
+    /// 待提交的记录：尚未提交的本地独有记录与比远端更完整的记录
+    pub to_submit: Vec<String>,
+}
+
+impl super::DbOperator {
+    /// 比对本地与远端记录，并将已在远端的本地记录标记为已提交
+    pub async fn reconcile_catalogs(&self) -> Result<ReconcileReport> {
+        use record_local::Column as Local;
+        use record_remote::Column as Remote;
+
+        let local = record_local::Entity::find()
+            .select_only()
+            .columns([
+                Local::Id,
+                Local::ShareMagnetLinks,
+                Local::LocalImageCount,
+                Local::IsCachedLocally,
+                Local::IsSubmitted,
+                Local::DeletedAt,
+            ])
+            .into_tuple::<(
+                String,
+                serde_json::Value,
+                i32,
+                bool,
+                bool,
+                Option<ChronoDateTimeUtc>,
+            )>()
+            .all(&self.db)
+            .await?;
+        let mut remote = record_remote::Entity::find()
+            .select_only()
+            .columns([Remote::Id, Remote::Links])
+            .into_tuple::<(String, serde_json::Value)>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        let mut report = ReconcileReport::default();
+        for (id, links, local_image_count, is_cached_locally, is_submitted, deleted_at) in local {
+            if deleted_at.is_some() {
+                if remote.remove(&id).is_some() {
+                    report.trashed_in_remote.push(id);
+                }
+                continue;
+            }
+            let Some(remote_links) = remote.remove(&id) else {
+                if !is_submitted {
+                    report.to_submit.push(id.clone());
+                }
+                report.local_only.push(id);
+                continue;
+            };
+
+            if !is_submitted {
+                report.marked_submitted.push(id.clone());
+            }
+            let missing_links = missing_links(links, remote_links);
+            if missing_links > 0 {
+                report.richer_than_remote.push(RicherRecord {
+                    id: id.clone(),
+                    missing_links,
+                    local_image_count,
+                    is_cached_locally,
+                });
+                report.to_submit.push(id.clone());
+            }
+            report.both.push(id);
+        }
+        report.remote_only = remote.into_keys().collect();
+
+        let txn = self.db.begin().await?;
+        for chunk in report.marked_submitted.chunks(MARK_SUBMITTED_CHUNK_SIZE) {
+            record_local::Entity::update_many()
+                .col_expr(Local::IsSubmitted, Expr::value(true))
+                .filter(Local::Id.is_in(chunk.iter().cloned()))
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+
+        for ids in [
+            &mut report.local_only,
+            &mut report.remote_only,
+            &mut report.both,
+            &mut report.trashed_in_remote,
+            &mut report.marked_submitted,
+            &mut report.to_submit,
+        ] {
+            ids.sort_unstable();
+        }
+        report
+            .richer_than_remote
+            .sort_unstable_by(|a, b| a.id.cmp(&b.id));
+
+        log::info!(
+            "Reconciled catalogs: {} local only, {} remote only, {} in both, {} richer locally, {} trashed locally, {} marked submitted",
+            report.local_only.len(),
+            report.remote_only.len(),
+            report.both.len(),
+            report.richer_than_remote.len(),
+            report.trashed_in_remote.len(),
+            report.marked_submitted.len()
+        );
+        Ok(report)
+    }
+
+    /// 本地与远端记录编号的并集
+    pub async fn query_all_record_ids(&self) -> Result<Vec<String>> {
+        let stmt = Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT id FROM record_local UNION SELECT id FROM record_remote",
+        );
+        let ids = self
+            .db
+            .query_all(stmt)
+            .await?
+            .iter()
+            .map(|row| row.try_get::<String>("", "id"))
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(ids)
+    }
+}
+
+/// 本地磁链中远端没有的链接数
+fn missing_links(local: serde_json::Value, remote: serde_json::Value) -> usize {
+    let local = serde_json::from_value::<Vec<MagnetLink>>(local).unwrap_or_default();
+    let remote = serde_json::from_value::<Vec<MagnetLink>>(remote).unwrap_or_default();
+    let remote = remote
+        .iter()
+        .map(|link| link.link.as_str())
+        .collect::<HashSet<_>>();
+    local
+        .iter()
+        .filter(|link| !remote.contains(link.link.as_str()))
+        .map(|link| link.link.as_str())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ActiveModelTrait as _, Set};
+    use serde_json::json;
+
+    use super::*;
+    use crate::DbOperator;
+    use crate::test_util::{memory_db, record};
+
+    async fn insert_remote(db: &DbOperator, id: &str, links: serde_json::Value) {
+        record_remote::ActiveModel {
+            id: Set(id.to_owned()),
+            title: Set(format!("Title {id}")),
+            date: Set("2020-01-01".to_owned()),
+            duration: Set(120),
+            director: Set(String::new()),
+            studio: Set(String::new()),
+            label: Set(String::new()),
+            series: Set(String::new()),
+            genres: Set(json!([])),
+            idols: Set(json!([])),
+            has_links: Set(links.as_array().is_some_and(|links| !links.is_empty())),
+            links: Set(links),
+            created_at: Set(chrono::Utc::now()),
+            updated_at: Set(chrono::Utc::now()),
+        }
+        .insert(&db.db)
+        .await
+        .expect("insert remote");
+    }
+
+    fn magnet(link: &str) -> serde_json::Value {
+        json!({ "name": "", "link": link, "size": "" })
+    }
+
+    #[tokio::test]
+    async fn reconcile_classifies_records() {
+        let db = memory_db().await;
+        db.insert_local(record("L-1")).await.expect("insert");
+        let mut r = record("B-1");
+        r.share_magnet_links = Set(json!([magnet("a"), magnet("b")]));
+        db.insert_local(r).await.expect("insert");
+        db.insert_local(record("T-1")).await.expect("insert");
+        db.insert_local(record("T-2")).await.expect("insert");
+        db.trash_record_local("T-1").await.expect("trash");
+        db.trash_record_local("T-2").await.expect("trash");
+
+        insert_remote(&db, "B-1", json!([magnet("a")])).await;
+        insert_remote(&db, "R-1", json!([])).await;
+        insert_remote(&db, "T-1", json!([])).await;
+
+        let report = db.reconcile_catalogs().await.expect("reconcile");
+        assert_eq!(report.local_only, ["L-1"]);
+        assert_eq!(report.remote_only, ["R-1"]);
+        assert_eq!(report.both, ["B-1"]);
+        // 回收站中的记录既不算远端独有，也不会被标记为已提交
+        assert_eq!(report.trashed_in_remote, ["T-1"]);
+        assert_eq!(report.marked_submitted, ["B-1"]);
+        assert_eq!(
+            report.richer_than_remote,
+            [RicherRecord {
+                id: "B-1".to_owned(),
+                missing_links: 1,
+                local_image_count: 0,
+                is_cached_locally: false,
+            }]
+        );
+        assert_eq!(report.to_submit, ["B-1", "L-1"]);
+
+        let trashed = db
+            .find_record_local_by_id("T-1")
+            .await
+            .expect("find")
+            .expect("record");
+        assert!(!trashed.is_submitted);
+    }
+}
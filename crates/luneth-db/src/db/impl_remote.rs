@@ -1,13 +1,303 @@
+use std::collections::HashSet;
+
 use super::Result;
 use crate::entities::record_remote;
-use sea_orm::ActiveModelTrait as _;
+use crate::record_remote::Column;
+use sea_orm::{
+    ActiveModelTrait as _, ActiveValue, ColumnTrait as _, Condition, EntityTrait as _,
+    IdenStatic as _, QueryFilter as _, QuerySelect as _, TransactionTrait as _,
+    sea_query::{Expr, OnConflict},
+};
+use serde::Serialize;
+
+/// 批量写入时每条语句包含的记录数
+pub const REMOTE_UPSERT_CHUNK_SIZE: usize = 500;
+
+/// 远端记录中由服务端提供、需要同步的列
+const SYNCED_COLUMNS: [Column; 11] = [
+    Column::Title,
+    Column::Date,
+    Column::Duration,
+    Column::Director,
+    Column::Studio,
+    Column::Label,
+    Column::Series,
+    Column::Genres,
+    Column::Idols,
+    Column::HasLinks,
+    Column::Links,
+];
+
+/// 批量写入远端记录的统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteUpsertSummary {
+    pub inserted: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+impl RemoteUpsertSummary {
+    /// 已处理的记录数
+    pub fn processed(&self) -> usize {
+        self.inserted + self.updated + self.unchanged
+    }
+}
 
 impl super::DbOperator {
     pub async fn insert_remote(
         &self,
         remote_am: record_remote::ActiveModel,
     ) -> Result<record_remote::Model> {
-        let model = remote_am.insert(&self.db).await?;
+        let id = remote_am.id.clone().into_value();
+        let model = remote_am
+            .insert(&self.db)
+            .await
+            .map_err(|e| super::with_id_key(e.into(), id))?;
         Ok(model)
     }
+
+    /// 在一个事务中分块写入远端记录，已存在的记录只在内容变化时更新
+    ///
+    /// 每写完一块以累计统计调用一次 `on_chunk`；任一块失败时整个事务回滚。
+    /// 同一批次中重复的编号只保留最后一条。
+    pub async fn upsert_remote_batch(
+        &self,
+        records: Vec<record_remote::ActiveModel>,
+        chunk_size: usize,
+        mut on_chunk: impl FnMut(&RemoteUpsertSummary) + Send,
+    ) -> Result<RemoteUpsertSummary> {
+        let records = dedup_by_id(records);
+        let mut summary = RemoteUpsertSummary::default();
+        if records.is_empty() {
+            return Ok(summary);
+        }
+
+        let mut changed = Condition::any();
+        for column in SYNCED_COLUMNS {
+            let name = column.as_str();
+            changed = changed.add(Expr::cust(format!(
+                "excluded.{name} IS NOT record_remote.{name}"
+            )));
+        }
+        let mut on_conflict = OnConflict::column(Column::Id);
+        on_conflict
+            .update_columns(SYNCED_COLUMNS)
+            .update_column(Column::UpdatedAt)
+            .action_cond_where(changed);
+
+        let txn = self.db.begin().await?;
+        for chunk in records.chunks(chunk_size.max(1)) {
+            let ids = chunk
+                .iter()
+                .filter_map(|record| match &record.id {
+                    ActiveValue::Set(id) | ActiveValue::Unchanged(id) => Some(id.clone()),
+                    ActiveValue::NotSet => None,
+                })
+                .collect::<Vec<_>>();
+            let existing = record_remote::Entity::find()
+                .select_only()
+                .column(Column::Id)
+                .filter(Column::Id.is_in(ids))
+                .into_tuple::<String>()
+                .all(&txn)
+                .await?
+                .into_iter()
+                .collect::<HashSet<_>>();
+
+            // 未变化的已有记录不计入影响行数
+            let affected = record_remote::Entity::insert_many(chunk.to_vec())
+                .on_conflict(on_conflict.clone())
+                .exec_without_returning(&txn)
+                .await?;
+            let affected = usize::try_from(affected).unwrap_or(usize::MAX);
+            let inserted = chunk.len() - existing.len();
+            let updated = affected.saturating_sub(inserted).min(existing.len());
+            summary.inserted += inserted;
+            summary.updated += updated;
+            summary.unchanged += existing.len() - updated;
+
+            on_chunk(&summary);
+        }
+        txn.commit().await?;
+
+        Ok(summary)
+    }
+}
+
+/// 按编号去重，保留最后出现的记录并维持原有顺序
+fn dedup_by_id(records: Vec<record_remote::ActiveModel>) -> Vec<record_remote::ActiveModel> {
+    let mut seen = HashSet::new();
+    let mut deduped = records
+        .into_iter()
+        .rev()
+        .filter(|record| match &record.id {
+            ActiveValue::Set(id) | ActiveValue::Unchanged(id) => seen.insert(id.clone()),
+            ActiveValue::NotSet => true,
+        })
+        .collect::<Vec<_>>();
+    deduped.reverse();
+    deduped
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ActiveModelBehavior as _, ConnectionTrait as _, PaginatorTrait as _, Set};
+
+    use super::*;
+    use crate::test_util::memory_db;
+
+    fn remote(id: &str, title: &str) -> record_remote::ActiveModel {
+        let mut record = record_remote::ActiveModel::new();
+        record.id = Set(id.to_owned());
+        record.title = Set(title.to_owned());
+        record.date = Set("2020-01-01".to_owned());
+        record.duration = Set(120);
+        record.director = Set(String::new());
+        record.studio = Set(String::new());
+        record.label = Set(String::new());
+        record.series = Set(String::new());
+        record
+    }
+
+    fn remotes(count: usize) -> Vec<record_remote::ActiveModel> {
+        (0..count)
+            .map(|i| remote(&format!("R-{i:04}"), "Title"))
+            .collect()
+    }
+
+    async fn remote_count(db: &super::super::DbOperator) -> u64 {
+        record_remote::Entity::find()
+            .count(db.get_db())
+            .await
+            .expect("count")
+    }
+
+    #[tokio::test]
+    async fn upsert_remote_batch_counts_changes() {
+        let db = memory_db().await;
+
+        let summary = db
+            .upsert_remote_batch(
+                vec![remote("A-1", "Title"), remote("A-2", "Title")],
+                REMOTE_UPSERT_CHUNK_SIZE,
+                |_| {},
+            )
+            .await
+            .expect("insert");
+        assert_eq!(
+            summary,
+            RemoteUpsertSummary {
+                inserted: 2,
+                updated: 0,
+                unchanged: 0,
+            }
+        );
+
+        let summary = db
+            .upsert_remote_batch(
+                vec![
+                    remote("A-1", "Title"),
+                    remote("A-2", "New title"),
+                    remote("A-3", "Title"),
+                ],
+                REMOTE_UPSERT_CHUNK_SIZE,
+                |_| {},
+            )
+            .await
+            .expect("upsert");
+        assert_eq!(
+            summary,
+            RemoteUpsertSummary {
+                inserted: 1,
+                updated: 1,
+                unchanged: 1,
+            }
+        );
+
+        let updated = record_remote::Entity::find_by_id("A-2")
+            .one(db.get_db())
+            .await
+            .expect("find")
+            .expect("record");
+        assert_eq!(updated.title, "New title");
+    }
+
+    #[tokio::test]
+    async fn upsert_remote_batch_keeps_last_duplicate() {
+        let db = memory_db().await;
+
+        let summary = db
+            .upsert_remote_batch(
+                vec![
+                    remote("A-1", "First"),
+                    remote("A-2", "Title"),
+                    remote("A-1", "Last"),
+                ],
+                REMOTE_UPSERT_CHUNK_SIZE,
+                |_| {},
+            )
+            .await
+            .expect("upsert");
+        assert_eq!(summary.inserted, 2);
+        assert_eq!(summary.processed(), 2);
+
+        let record = record_remote::Entity::find_by_id("A-1")
+            .one(db.get_db())
+            .await
+            .expect("find")
+            .expect("record");
+        assert_eq!(record.title, "Last");
+    }
+
+    #[tokio::test]
+    async fn upsert_remote_batch_reports_each_chunk() {
+        let db = memory_db().await;
+        let total = REMOTE_UPSERT_CHUNK_SIZE * 2 + 1;
+
+        let mut progress = vec![];
+        let summary = db
+            .upsert_remote_batch(remotes(total), REMOTE_UPSERT_CHUNK_SIZE, |summary| {
+                progress.push(summary.processed());
+            })
+            .await
+            .expect("upsert");
+        assert_eq!(summary.inserted, total);
+        assert_eq!(
+            progress,
+            [
+                REMOTE_UPSERT_CHUNK_SIZE,
+                REMOTE_UPSERT_CHUNK_SIZE * 2,
+                total
+            ]
+        );
+        assert_eq!(remote_count(&db).await, total as u64);
+    }
+
+    #[tokio::test]
+    async fn upsert_remote_batch_rolls_back_on_failed_chunk() {
+        let db = memory_db().await;
+        // 第二块中的记录写入失败
+        db.get_db()
+            .execute_unprepared(&format!(
+                "CREATE TRIGGER fail_remote BEFORE INSERT ON record_remote \
+                 WHEN new.id = 'R-{:04}' BEGIN SELECT RAISE(ABORT, 'rejected'); END",
+                REMOTE_UPSERT_CHUNK_SIZE + 1
+            ))
+            .await
+            .expect("create trigger");
+
+        let mut chunks = 0;
+        let result = db
+            .upsert_remote_batch(
+                remotes(REMOTE_UPSERT_CHUNK_SIZE + 10),
+                REMOTE_UPSERT_CHUNK_SIZE,
+                |_| chunks += 1,
+            )
+            .await;
+        assert!(result.is_err());
+        assert_eq!(chunks, 1);
+        // 已写入的第一块随事务回滚
+        assert_eq!(remote_count(&db).await, 0);
+    }
 }
@@ -0,0 +1,350 @@
+use sea_orm::{
+    ActiveModelTrait as _, ColumnTrait as _, ConnectionTrait, DbErr, EntityTrait as _,
+    IdenStatic as _, IntoActiveModel as _, ModelTrait as _, QueryFilter as _, QueryOrder as _, Set,
+    TransactionTrait as _,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::Result;
+use crate::DbError;
+use crate::record_local::{self, Column};
+use crate::record_revision;
+use crate::types::OperationType;
+
+/// 引起记录变更的来源
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevisionSource {
+    pub operation: OperationType,
+    /// 所属任务，手动操作为空
+    pub task_id: Option<String>,
+}
+
+impl RevisionSource {
+    pub fn new(operation: OperationType) -> Self {
+        Self {
+            operation,
+            task_id: None,
+        }
+    }
+
+    /// 关联到指定任务
+    #[must_use]
+    pub fn with_task_id(mut self, task_id: &str) -> Self {
+        self.task_id = Some(task_id.to_owned());
+        self
+    }
+}
+
+/// 计入修订历史的元数据列
+///
+/// 用户状态与时间戳不在此列；本地图片数与缓存状态反映磁盘上的图片，回滚元数据
+/// 不能改变它们，也不在此列。
+const REVISION_COLUMNS: [Column; 12] = [
+    Column::Title,
+    Column::ReleaseDate,
+    Column::Length,
+    Column::Director,
+    Column::Studio,
+    Column::Label,
+    Column::Series,
+    Column::Genre,
+    Column::Idols,
+    Column::ShareMagnetLinks,
+    Column::Cover,
+    Column::SampleImageLinks,
+];
+
+/// 以列名为键的元数据快照
+fn metadata_snapshot(record: &record_local::Model) -> Result<Map<String, Value>> {
+    let Value::Object(mut fields) = serde_json::to_value(record)? else {
+        return Ok(Map::new());
+    };
+    Ok(REVISION_COLUMNS
+        .iter()
+        .filter_map(|column| {
+            let name = column.as_str();
+            fields.remove(name).map(|value| (name.to_owned(), value))
+        })
+        .collect())
+}
+
+/// 元数据有变化时写入一条修订，返回写入的修订
+pub(super) async fn insert_revision<C: ConnectionTrait>(
+    conn: &C,
+    before: &record_local::Model,
+    after: &record_local::Model,
+    source: &RevisionSource,
+    rollback_of: Option<i32>,
+) -> Result<Option<record_revision::Model>> {
+    let old = metadata_snapshot(before)?;
+    let new = metadata_snapshot(after)?;
+    let diff = old
+        .iter()
+        .filter(|(name, value)| new.get(*name) != Some(*value))
+        .map(|(name, value)| {
+            let changed = json!({ "old": value, "new": new.get(name) });
+            (name.clone(), changed)
+        })
+        .collect::<Map<_, _>>();
+    if diff.is_empty() {
+        return Ok(None);
+    }
+
+    let revision = record_revision::ActiveModel {
+        record_id: Set(after.id.clone()),
+        operation: Set(source.operation),
+        task_id: Set(source.task_id.clone()),
+        rollback_of: Set(rollback_of),
+        snapshot: Set(Value::Object(old)),
+        diff: Set(Value::Object(diff)),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+    Ok(Some(revision))
+}
+
+impl super::DbOperator {
+    /// 查询记录的修订历史，最新的在前
+    pub async fn query_record_revisions(
+        &self,
+        record_id: &str,
+    ) -> Result<Vec<record_revision::Model>> {
+        let revisions = record_revision::Entity::find()
+            .filter(record_revision::Column::RecordId.eq(record_id))
+            .order_by_desc(record_revision::Column::Id)
+            .all(&self.db)
+            .await?;
+        Ok(revisions)
+    }
+
+    /// 将记录的元数据恢复为修订发生前的状态，返回恢复后的记录
+    ///
+    /// 回滚本身也会写入一条修订，`rollback_of` 指向被回滚的修订。回收站中的记录
+    /// 不能回滚，返回 [`DbError::RecordTrashed`]。
+    pub async fn rollback_record_revision(
+        &self,
+        revision_id: i32,
+        source: &RevisionSource,
+    ) -> Result<record_local::Model> {
+        let txn = self.db.begin().await?;
+
+        let revision = record_revision::Entity::find_by_id(revision_id)
+            .one(&txn)
+            .await?
+            .ok_or_else(|| DbErr::RecordNotFound(format!("record_revision {revision_id}")))?;
+        let current = record_local::Entity::find_by_id(revision.record_id.as_str())
+            .one(&txn)
+            .await?
+            .ok_or_else(|| DbErr::RecordNotFound(format!("record_local {}", revision.record_id)))?;
+        if current.deleted_at.is_some() {
+            return Err(DbError::RecordTrashed(current.id));
+        }
+
+        // 以当前记录为底套用快照，再逐列写回
+        let Value::Object(mut fields) = serde_json::to_value(&current)? else {
+            return Ok(current);
+        };
+        if let Value::Object(snapshot) = &revision.snapshot {
+            fields.extend(snapshot.clone());
+        }
+        let restored: record_local::Model = serde_json::from_value(Value::Object(fields))?;
+        let mut active = current.clone().into_active_model();
+        for column in REVISION_COLUMNS {
+            active.set(column, restored.get(column));
+        }
+
+        let updated = active.update(&txn).await?;
+        insert_revision(&txn, &current, &updated, source, Some(revision.id)).await?;
+        txn.commit().await?;
+        Ok(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Set;
+
+    use super::*;
+    use crate::test_util::{attribute, memory_db, record};
+
+    #[test]
+    fn snapshot_keys_are_column_names() {
+        let model = record_local::Model {
+            id: "A-1".to_owned(),
+            title: "Title".to_owned(),
+            release_date: "2020-01-01".to_owned(),
+            length: "120分钟".to_owned(),
+            director: attribute(&["Director"]),
+            studio: attribute(&[]),
+            label: attribute(&[]),
+            series: attribute(&[]),
+            genre: attribute(&["Drama"]),
+            idols: attribute(&[]),
+            share_magnet_links: json!([]),
+            local_image_count: 3,
+            cover: String::new(),
+            sample_image_links: json!([]),
+            viewed: false,
+            is_liked: false,
+            is_submitted: false,
+            is_cached_locally: true,
+            rating: 0,
+            rated_at: None,
+            note: String::new(),
+            note_updated_at: None,
+            view_count: 0,
+            last_viewed_at: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            deleted_at: None,
+        };
+
+        // 快照依赖序列化后的字段名与列名一致
+        let snapshot = metadata_snapshot(&model).expect("snapshot");
+        let mut keys = snapshot.keys().map(String::as_str).collect::<Vec<_>>();
+        let mut columns = REVISION_COLUMNS
+            .iter()
+            .map(|column| column.as_str())
+            .collect::<Vec<_>>();
+        keys.sort_unstable();
+        columns.sort_unstable();
+        assert_eq!(keys, columns);
+        assert_eq!(snapshot["release_date"], json!("2020-01-01"));
+        assert_eq!(snapshot["genre"], attribute(&["Drama"]));
+    }
+
+    #[tokio::test]
+    async fn upsert_writes_revision_on_metadata_change() {
+        let db = memory_db().await;
+        let source = RevisionSource::new(OperationType::CrawlRecord).with_task_id("task");
+
+        db.upsert_local(record("A-1"), &source)
+            .await
+            .expect("insert");
+        // 新增记录不写入修订
+        assert!(
+            db.query_record_revisions("A-1")
+                .await
+                .expect("revisions")
+                .is_empty()
+        );
+
+        let mut r = record("A-1");
+        r.title = Set("New title".to_owned());
+        db.upsert_local(r, &source).await.expect("update");
+
+        let revisions = db.query_record_revisions("A-1").await.expect("revisions");
+        assert_eq!(revisions.len(), 1);
+        let revision = &revisions[0];
+        assert_eq!(revision.operation, OperationType::CrawlRecord);
+        assert_eq!(revision.task_id.as_deref(), Some("task"));
+        assert_eq!(revision.rollback_of, None);
+        assert_eq!(revision.snapshot["title"], json!("Title A-1"));
+        assert_eq!(
+            revision.diff,
+            json!({ "title": { "old": "Title A-1", "new": "New title" } })
+        );
+    }
+
+    #[tokio::test]
+    async fn unchanged_metadata_writes_no_revision() {
+        let db = memory_db().await;
+        let source = RevisionSource::new(OperationType::CrawlRecord);
+
+        db.upsert_local(record("A-1"), &source)
+            .await
+            .expect("insert");
+        db.upsert_local(record("A-1"), &source)
+            .await
+            .expect("same record");
+
+        // 只缓存了图片，元数据没有变化
+        let mut r = record("A-1");
+        r.local_image_count = Set(3);
+        r.is_cached_locally = Set(true);
+        let cached = db.upsert_local(r, &source).await.expect("cache images");
+        assert!(cached.is_cached_locally);
+
+        assert!(
+            db.query_record_revisions("A-1")
+                .await
+                .expect("revisions")
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn rollback_restores_metadata_and_links_revision() {
+        let db = memory_db().await;
+        let crawl = RevisionSource::new(OperationType::CrawlRecord);
+
+        let mut r = record("A-1");
+        r.genre = Set(attribute(&["Drama"]));
+        db.upsert_local(r, &crawl).await.expect("insert");
+
+        let mut r = record("A-1");
+        r.title = Set("New title".to_owned());
+        r.genre = Set(attribute(&["Comedy"]));
+        r.local_image_count = Set(3);
+        r.is_cached_locally = Set(true);
+        db.upsert_local(r, &crawl).await.expect("update");
+        let revision = db.query_record_revisions("A-1").await.expect("revisions")[0].clone();
+
+        let source = RevisionSource::new(OperationType::Update);
+        let restored = db
+            .rollback_record_revision(revision.id, &source)
+            .await
+            .expect("rollback");
+        assert_eq!(restored.title, "Title A-1");
+        assert_eq!(restored.genre, attribute(&["Drama"]));
+        // 图片缓存状态不随元数据回滚
+        assert_eq!(restored.local_image_count, 3);
+        assert!(restored.is_cached_locally);
+
+        let revisions = db.query_record_revisions("A-1").await.expect("revisions");
+        assert_eq!(revisions.len(), 2);
+        let rollback = &revisions[0];
+        assert_eq!(rollback.rollback_of, Some(revision.id));
+        assert_eq!(rollback.operation, OperationType::Update);
+        assert_eq!(rollback.snapshot["title"], json!("New title"));
+        assert_eq!(
+            rollback.diff["title"],
+            json!({ "old": "New title", "new": "Title A-1" })
+        );
+    }
+
+    #[tokio::test]
+    async fn rollback_rejects_trashed_record() {
+        let db = memory_db().await;
+        let source = RevisionSource::new(OperationType::CrawlRecord);
+
+        db.upsert_local(record("A-1"), &source)
+            .await
+            .expect("insert");
+        let mut r = record("A-1");
+        r.title = Set("New title".to_owned());
+        db.upsert_local(r, &source).await.expect("update");
+        let revision = db.query_record_revisions("A-1").await.expect("revisions")[0].clone();
+
+        db.trash_record_local("A-1").await.expect("trash");
+        let result = db.rollback_record_revision(revision.id, &source).await;
+        assert!(matches!(result, Err(DbError::RecordTrashed(id)) if id == "A-1"));
+
+        let trashed = db
+            .find_record_local_by_id("A-1")
+            .await
+            .expect("find")
+            .expect("record");
+        assert_eq!(trashed.title, "New title");
+        assert_eq!(
+            db.query_record_revisions("A-1")
+                .await
+                .expect("revisions")
+                .len(),
+            1
+        );
+    }
+}
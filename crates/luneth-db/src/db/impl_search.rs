@@ -0,0 +1,403 @@
+use sea_orm::sea_query::{Alias, Expr, LikeExpr, SelectStatement};
+use sea_orm::{
+    DbErr, DeriveIden, EntityTrait as _, FromQueryResult, PaginatorTrait as _, QueryFilter as _,
+    QueryResult, QueryTrait, sea_query::Condition,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Result;
+use super::impl_local::{
+    LocalFilterCondition, PageItem, PageOrder, PageStart, RecordPage, SortField, SortOrder,
+    SortSpec, to_conditions,
+};
+use crate::entities::{
+    record_local::Column, record_local::Entity as entity, record_local::Model as model,
+};
+
+/// trigram 分词器只能匹配不少于三个字符的词
+const TRIGRAM_MIN_CHARS: usize = 3;
+
+/// 列权重依次为 id, title, idols, genre, studio, label, series, director
+pub(super) const RANK_EXPR: &str =
+    "bm25(record_local_fts, 10.0, 5.0, 3.0, 1.0, 1.0, 1.0, 1.0, 1.0)";
+
+const SNIPPET_EXPR: &str = "snippet(record_local_fts, -1, '<mark>', '</mark>', '…', 16)";
+
+#[derive(DeriveIden)]
+enum RecordLocalFts {
+    Table,
+    Id,
+    Title,
+    Idols,
+    Genre,
+    Studio,
+    Label,
+    Series,
+    Director,
+}
+
+/// 全文检索命中的记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub record: model,
+    /// bm25 相关度，越小越相关；未使用全文匹配时为 0
+    pub rank: f64,
+    /// 命中片段，匹配部分以 `<mark>` 包裹
+    pub snippet: Option<String>,
+}
+
+impl PageItem for SearchHit {
+    fn record(&self) -> &model {
+        &self.record
+    }
+
+    fn rank(&self) -> f64 {
+        self.rank
+    }
+}
+
+impl FromQueryResult for SearchHit {
+    fn from_query_result(res: &QueryResult, pre: &str) -> std::result::Result<Self, DbErr> {
+        Ok(Self {
+            record: model::from_query_result(res, pre)?,
+            rank: res.try_get(pre, "rank")?,
+            snippet: res.try_get(pre, "snippet")?,
+        })
+    }
+}
+
+/// 拆分后的检索词
+#[derive(Debug, Default)]
+pub(super) struct SearchTerms {
+    /// 可交给 FTS5 `MATCH` 的词
+    indexed: Vec<String>,
+    /// 过短、只能回退到 `LIKE` 的词
+    short: Vec<String>,
+}
+
+impl SearchTerms {
+    pub(super) fn parse(input: &str) -> Self {
+        let mut terms = Self::default();
+        for term in input.split_whitespace() {
+            if term.chars().count() >= TRIGRAM_MIN_CHARS {
+                terms.indexed.push(term.to_owned());
+            } else {
+                terms.short.push(term.to_owned());
+            }
+        }
+        terms
+    }
+
+    fn is_empty(&self) -> bool {
+        self.indexed.is_empty() && self.short.is_empty()
+    }
+
+    /// 每个词作为独立短语，词之间为 AND 关系
+    fn match_expr(&self) -> Option<String> {
+        if self.indexed.is_empty() {
+            return None;
+        }
+        let phrases = self
+            .indexed
+            .iter()
+            .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+            .collect::<Vec<_>>();
+        Some(phrases.join(" AND "))
+    }
+
+    /// 将检索条件加入针对 `record_local` 的查询
+    pub(super) fn apply(&self, stmt: &mut SelectStatement) {
+        if self.is_empty() {
+            return;
+        }
+        stmt.inner_join(
+            RecordLocalFts::Table,
+            Expr::col((RecordLocalFts::Table, RecordLocalFts::Id)).equals((entity, Column::Id)),
+        );
+        for cond in self.like_conditions() {
+            stmt.cond_where(cond);
+        }
+        if let Some(match_expr) = self.match_expr() {
+            stmt.and_where(Expr::cust_with_values(
+                "record_local_fts MATCH ?",
+                [match_expr],
+            ));
+        }
+    }
+
+    /// 短词在任意索引列中出现即可
+    fn like_conditions(&self) -> Vec<Condition> {
+        self.short
+            .iter()
+            .map(|term| {
+                let escaped = term
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_");
+                let pattern = format!("%{escaped}%");
+                [
+                    RecordLocalFts::Id,
+                    RecordLocalFts::Title,
+                    RecordLocalFts::Idols,
+                    RecordLocalFts::Genre,
+                    RecordLocalFts::Studio,
+                    RecordLocalFts::Label,
+                    RecordLocalFts::Series,
+                    RecordLocalFts::Director,
+                ]
+                .into_iter()
+                .fold(Condition::any(), |acc, col| {
+                    acc.add(
+                        Expr::col((RecordLocalFts::Table, col))
+                            .like(LikeExpr::new(pattern.clone()).escape('\\')),
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+impl super::DbOperator {
+    /// 全文检索本地记录
+    ///
+    /// 匹配编号、标题、演员、类别、片商、发行商、系列与导演。未指定 `sort` 时
+    /// 结果按相关度排序，相关度相同再按编号排序。
+    pub async fn search_local(
+        &self,
+        name: String,
+        filters: Vec<LocalFilterCondition>,
+        sort: Option<SortSpec>,
+        start: PageStart,
+        limit: u64,
+    ) -> Result<RecordPage<SearchHit>> {
+        let terms = SearchTerms::parse(&name);
+        let ranked = terms.match_expr().is_some();
+        let mut query = entity::find().filter(to_conditions(filters));
+
+        {
+            let stmt = QueryTrait::query(&mut query);
+            terms.apply(stmt);
+
+            if ranked {
+                stmt.expr_as(Expr::cust(RANK_EXPR), Alias::new("rank"))
+                    .expr_as(Expr::cust(SNIPPET_EXPR), Alias::new("snippet"));
+            } else {
+                stmt.expr_as(Expr::val(0.0_f64), Alias::new("rank"))
+                    .expr_as(Expr::value(Option::<String>::None), Alias::new("snippet"));
+            }
+        }
+
+        // 总数需在分页前统计
+        let total = query.clone().count(&self.db).await?;
+
+        let order = match sort {
+            Some(sort) => PageOrder::Sort(sort),
+            None if ranked => PageOrder::Relevance,
+            None => PageOrder::Sort(SortSpec {
+                field: SortField::Id,
+                order: SortOrder::Asc,
+            }),
+        };
+        self.query_page(query, total, order, start, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ConnectionTrait as _, IntoActiveModel as _, Set};
+
+    use super::{PageStart, SearchHit};
+    use crate::impl_revision::RevisionSource;
+    use crate::test_util::{attribute, memory_db, record};
+    use crate::types::OperationType;
+
+    async fn search_ids(db: &super::super::DbOperator, name: &str) -> Vec<String> {
+        let page = db
+            .search_local(name.to_owned(), vec![], None, PageStart::First, 100)
+            .await
+            .expect("search");
+        page.records.into_iter().map(|hit| hit.record.id).collect()
+    }
+
+    async fn search_hits(db: &super::super::DbOperator, name: &str) -> Vec<SearchHit> {
+        db.search_local(name.to_owned(), vec![], None, PageStart::First, 100)
+            .await
+            .expect("search")
+            .records
+    }
+
+    async fn insert_titles(db: &super::super::DbOperator, titles: &[(&str, &str)]) {
+        for (id, title) in titles {
+            let mut r = record(id);
+            r.title = Set((*title).to_owned());
+            db.insert_local(r).await.expect("insert");
+        }
+    }
+
+    #[tokio::test]
+    async fn search_matches_cjk_substrings() {
+        let db = memory_db().await;
+        insert_titles(&db, &[("C-1", "夏日的河流旅行"), ("C-2", "冬天的酒店")]).await;
+        let mut r = record("C-3");
+        r.idols = Set(attribute(&["山田花子"]));
+        db.insert_local(r).await.expect("insert");
+
+        // 不依赖分词，词中间的片段也能命中
+        assert_eq!(search_ids(&db, "河流旅").await, ["C-1"]);
+        assert_eq!(search_ids(&db, "田花子").await, ["C-3"]);
+        assert_eq!(search_ids(&db, "的酒店").await, ["C-2"]);
+        assert!(search_ids(&db, "河流店").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_falls_back_to_like_for_short_terms() {
+        let db = memory_db().await;
+        insert_titles(
+            &db,
+            &[
+                ("C-1", "夏日的河流旅行"),
+                ("C-2", "冬天的酒店"),
+                ("AB-1", "summer river"),
+            ],
+        )
+        .await;
+
+        assert_eq!(search_ids(&db, "河流").await, ["C-1"]);
+        assert_eq!(search_ids(&db, "ab").await, ["AB-1"]);
+        // `%` 与 `_` 按字面匹配
+        assert!(search_ids(&db, "%").await.is_empty());
+
+        // 只有短词时不计算相关度，也没有片段
+        let hits = search_hits(&db, "的").await;
+        assert_eq!(hits.len(), 2);
+        assert!(
+            hits.iter()
+                .all(|hit| hit.rank == 0.0 && hit.snippet.is_none())
+        );
+
+        // 长词走全文索引，短词再用 `LIKE` 收窄
+        assert_eq!(search_ids(&db, "的河流 旅").await, ["C-1"]);
+        assert!(search_ids(&db, "的河流 店").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_marks_snippet() {
+        let db = memory_db().await;
+        insert_titles(&db, &[("S-1", "summer river trip")]).await;
+
+        let hits = search_hits(&db, "river").await;
+        let snippet = hits[0].snippet.as_deref().expect("snippet");
+        assert!(snippet.contains("<mark>river</mark>"), "{snippet}");
+        assert!(hits[0].rank < 0.0);
+    }
+
+    #[tokio::test]
+    async fn search_orders_by_column_weight() {
+        let db = memory_db().await;
+        let mut r = record("G-1");
+        r.genre = Set(attribute(&["dance"]));
+        db.insert_local(r).await.expect("insert");
+        insert_titles(&db, &[("T-1", "dance night")]).await;
+
+        // 标题的权重高于类别
+        let hits = search_hits(&db, "dance").await;
+        let ids = hits
+            .iter()
+            .map(|hit| hit.record.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["T-1", "G-1"]);
+        assert!(hits[0].rank < hits[1].rank);
+    }
+
+    #[tokio::test]
+    async fn search_follows_update_and_delete() {
+        let db = memory_db().await;
+        for (id, title) in [
+            ("ABC-001", "summer river"),
+            ("ABC-002", "winter hotel"),
+            ("AB", "river island"),
+        ] {
+            let mut r = record(id);
+            r.title = Set(title.to_owned());
+            db.insert_local(r).await.expect("insert");
+        }
+        assert_eq!(search_ids(&db, "river").await, ["AB", "ABC-001"]);
+
+        // `VACUUM` 等操作可能改变记录的 rowid
+        db.get_db()
+            .execute_unprepared("UPDATE record_local SET rowid = rowid + 100")
+            .await
+            .expect("move rowids");
+
+        let source = RevisionSource::new(OperationType::Update);
+        for id in ["ABC-001", "AB"] {
+            let mut r = db
+                .find_record_local_by_id(id)
+                .await
+                .expect("find")
+                .expect("record exists")
+                .into_active_model();
+            r.title = Set("autumn garden".to_owned());
+            db.update_record_local(r, &source).await.expect("update");
+        }
+        assert!(search_ids(&db, "river").await.is_empty());
+        assert_eq!(search_ids(&db, "garden").await, ["AB", "ABC-001"]);
+
+        let r = db
+            .find_record_local_by_id("ABC-002")
+            .await
+            .expect("find")
+            .expect("record exists");
+        db.delete_record_local(r.into_active_model())
+            .await
+            .expect("delete");
+        assert!(search_ids(&db, "hotel").await.is_empty());
+
+        let count = db
+            .get_db()
+            .query_one(sea_orm::Statement::from_string(
+                sea_orm::DbBackend::Sqlite,
+                "SELECT COUNT(*) AS cnt FROM record_local_fts",
+            ))
+            .await
+            .expect("count")
+            .map(|row| row.try_get::<i64>("", "cnt").expect("cnt"));
+        assert_eq!(count, Some(2), "index should hold one row per record");
+    }
+
+    #[tokio::test]
+    async fn search_pages_by_relevance_cursor() {
+        let db = memory_db().await;
+        for (id, title) in [
+            ("R-1", "river"),
+            ("R-2", "river river"),
+            ("R-3", "river bank"),
+            ("R-4", "river river river"),
+            ("R-5", "river"),
+            ("R-6", "hotel"),
+        ] {
+            let mut r = record(id);
+            r.title = Set(title.to_owned());
+            db.insert_local(r).await.expect("insert");
+        }
+        let all = search_ids(&db, "river").await;
+        assert_eq!(all.len(), 5);
+
+        let mut walked = vec![];
+        let mut start = PageStart::First;
+        loop {
+            let page = db
+                .search_local("river".to_owned(), vec![], None, start, 2)
+                .await
+                .expect("page");
+            assert_eq!(page.total, 5);
+            walked.extend(page.records.into_iter().map(|hit| hit.record.id));
+            match page.next_cursor {
+                Some(cursor) => start = PageStart::Cursor(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(walked, all, "cursor pages follow relevance order");
+    }
+}
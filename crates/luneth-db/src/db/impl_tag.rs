@@ -0,0 +1,205 @@
+use std::collections::{HashMap, HashSet};
+
+use sea_orm::{
+    ActiveModelTrait as _, ColumnTrait as _, ConnectionTrait, EntityTrait as _,
+    IntoActiveModel as _, ModelTrait as _, QueryFilter as _, QueryOrder as _, QuerySelect as _,
+    RelationTrait as _, Set, TransactionTrait as _,
+    sea_query::{JoinType, OnConflict},
+};
+use serde::{Deserialize, Serialize};
+
+use super::Result;
+use crate::entities::{record_local, record_tag, tag};
+use crate::types::DbError;
+
+/// 批量关联时每条语句包含的记录数
+pub(super) const LINK_CHUNK_SIZE: usize = 500;
+
+/// 标签及其关联的记录数，不含回收站中的记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagSummary {
+    #[serde(flatten)]
+    pub tag: tag::Model,
+    pub record_count: u64,
+}
+
+impl super::DbOperator {
+    /// 全部标签，按名称排序
+    pub async fn query_tags(&self) -> Result<Vec<TagSummary>> {
+        let tags = tag::Entity::find()
+            .order_by_asc(tag::Column::Name)
+            .all(&self.db)
+            .await?;
+        let counts = record_tag::Entity::find()
+            .select_only()
+            .column(record_tag::Column::TagId)
+            .column_as(record_tag::Column::RecordId.count(), "count")
+            .join(JoinType::InnerJoin, record_tag::Relation::RecordLocal.def())
+            .filter(record_local::Column::DeletedAt.is_null())
+            .group_by(record_tag::Column::TagId)
+            .into_tuple::<(i32, i64)>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        let tags = tags
+            .into_iter()
+            .map(|tag| TagSummary {
+                record_count: counts.get(&tag.id).map_or(0, |&c| c.unsigned_abs()),
+                tag,
+            })
+            .collect();
+        Ok(tags)
+    }
+
+    /// 新建标签，名称去除首尾空白后不能为空，重名时返回唯一约束错误
+    pub async fn create_tag(&self, name: &str) -> Result<tag::Model> {
+        let tag = tag::ActiveModel {
+            name: Set(normalize_name(name)?),
+            created_at: Set(chrono::Utc::now()),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+        log::info!("Created tag {} ({})", tag.name, tag.id);
+        Ok(tag)
+    }
+
+    /// 重命名标签，重名时返回唯一约束错误
+    pub async fn rename_tag(&self, id: i32, name: &str) -> Result<tag::Model> {
+        let mut tag = find_tag(&self.db, id).await?.into_active_model();
+        tag.name = Set(normalize_name(name)?);
+        Ok(tag.update(&self.db).await?)
+    }
+
+    /// 删除标签及其全部关联
+    pub async fn delete_tag(&self, id: i32) -> Result<()> {
+        let tag = find_tag(&self.db, id).await?;
+        tag.delete(&self.db).await?;
+        log::info!("Deleted tag {id}");
+        Ok(())
+    }
+
+    /// 记录带有的全部标签，按名称排序
+    pub async fn find_record_tags(&self, record_id: &str) -> Result<Vec<tag::Model>> {
+        let tags = tag::Entity::find()
+            .inner_join(record_tag::Entity)
+            .filter(record_tag::Column::RecordId.eq(record_id))
+            .order_by_asc(tag::Column::Name)
+            .all(&self.db)
+            .await?;
+        Ok(tags)
+    }
+
+    /// 为多条记录批量添加多个标签，返回新增的关联数
+    ///
+    /// 已有的关联与不存在的记录被忽略，任一标签不存在时不做任何修改。
+    pub async fn tag_records(&self, tag_ids: &[i32], record_ids: &[String]) -> Result<u64> {
+        if tag_ids.is_empty() {
+            return Ok(0);
+        }
+        let txn = self.db.begin().await?;
+        for &id in tag_ids {
+            find_tag(&txn, id).await?;
+        }
+
+        let mut added = 0;
+        for chunk in record_ids.chunks(LINK_CHUNK_SIZE) {
+            let existing = existing_record_ids(&txn, chunk).await?;
+            if existing.is_empty() {
+                continue;
+            }
+            let links = tag_ids
+                .iter()
+                .flat_map(|&tag_id| {
+                    existing
+                        .iter()
+                        .map(move |record_id| record_tag::ActiveModel {
+                            record_id: Set(record_id.clone()),
+                            tag_id: Set(tag_id),
+                        })
+                })
+                .collect::<Vec<_>>();
+            for links in links.chunks(LINK_CHUNK_SIZE) {
+                added += record_tag::Entity::insert_many(links.iter().cloned())
+                    .on_conflict(
+                        OnConflict::columns([
+                            record_tag::Column::RecordId,
+                            record_tag::Column::TagId,
+                        ])
+                        .do_nothing()
+                        .to_owned(),
+                    )
+                    .exec_without_returning(&txn)
+                    .await?;
+            }
+        }
+        txn.commit().await?;
+
+        log::info!(
+            "Tagged {} records with tags {tag_ids:?}: {added} links added",
+            record_ids.len()
+        );
+        Ok(added)
+    }
+
+    /// 从多条记录批量移除多个标签，返回移除的关联数
+    pub async fn untag_records(&self, tag_ids: &[i32], record_ids: &[String]) -> Result<u64> {
+        let txn = self.db.begin().await?;
+        let mut removed = 0;
+        for chunk in record_ids.chunks(LINK_CHUNK_SIZE) {
+            removed += record_tag::Entity::delete_many()
+                .filter(record_tag::Column::TagId.is_in(tag_ids.iter().copied()))
+                .filter(record_tag::Column::RecordId.is_in(chunk.iter().cloned()))
+                .exec(&txn)
+                .await?
+                .rows_affected;
+        }
+        txn.commit().await?;
+
+        log::info!(
+            "Untagged {} records from tags {tag_ids:?}: {removed} links removed",
+            record_ids.len()
+        );
+        Ok(removed)
+    }
+}
+
+/// 去除首尾空白，不能为空
+pub(super) fn normalize_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(DbError::OperationFailed("name is empty".to_owned()));
+    }
+    Ok(name.to_owned())
+}
+
+/// 给定编号中存在于本地的记录，保持原顺序并去重
+pub(super) async fn existing_record_ids(
+    conn: &impl ConnectionTrait,
+    ids: &[String],
+) -> Result<Vec<String>> {
+    let found = record_local::Entity::find()
+        .select_only()
+        .column(record_local::Column::Id)
+        .filter(record_local::Column::Id.is_in(ids.iter().cloned()))
+        .into_tuple::<String>()
+        .all(conn)
+        .await?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let mut seen = HashSet::new();
+    Ok(ids
+        .iter()
+        .filter(|id| found.contains(*id) && seen.insert(id.as_str()))
+        .cloned()
+        .collect())
+}
+
+async fn find_tag(conn: &impl ConnectionTrait, id: i32) -> Result<tag::Model> {
+    tag::Entity::find_by_id(id)
+        .one(conn)
+        .await?
+        .ok_or_else(|| DbError::NotFound(format!("tag {id}")))
+}
@@ -0,0 +1,646 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use chrono::{DateTime, Utc};
+use luneth::record::RecordEntry;
+use sea_orm::{DatabaseTransaction, Set, TransactionTrait as _};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::Result;
+use super::impl_local::{LocalFilterCondition, PageStart, SortSpec, upsert_local_in};
+use super::impl_revision::RevisionSource;
+use crate::entities::record_local;
+use crate::types::DbError;
+
+/// 导出时每次查询的记录数
+pub const EXPORT_PAGE_SIZE: u64 = 500;
+
+/// CSV 中多个取值之间的分隔符
+const CSV_LIST_SEPARATOR: &str = "|";
+
+const CSV_HEADER: [&str; 26] = [
+    "id",
+    "title",
+    "release_date",
+    "length",
+    "director",
+    "studio",
+    "label",
+    "series",
+    "genre",
+    "idols",
+    "share_magnet_links",
+    "local_image_count",
+    "cover",
+    "sample_image_links",
+    "viewed",
+    "is_liked",
+    "is_submitted",
+    "is_cached_locally",
+    "rating",
+    "rated_at",
+    "note",
+    "note_updated_at",
+    "view_count",
+    "last_viewed_at",
+    "created_at",
+    "updated_at",
+];
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// 每行一条 [`RecordLine`]，可重新导入
+    Jsonl,
+    /// 展开为平铺的列，属性与链接以 `|` 连接
+    ///
+    /// 只保留属性名称与磁链地址，重新导入时属性链接与磁链的名称、大小为空。
+    Csv,
+}
+
+/// JSON Lines 中的一行记录
+///
+/// 元数据与 [`RecordEntry`] 的字段一致，其余为本地状态（含评分与备注）；导入时忽略 `updated_at`。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordLine {
+    #[serde(flatten)]
+    pub record: RecordEntry,
+    #[serde(default)]
+    pub cover: String,
+    #[serde(default)]
+    pub sample_image_links: Vec<String>,
+    #[serde(default)]
+    pub viewed: bool,
+    #[serde(default)]
+    pub is_liked: bool,
+    #[serde(default)]
+    pub is_submitted: bool,
+    #[serde(default)]
+    pub is_cached_locally: bool,
+    #[serde(default)]
+    pub rating: i32,
+    #[serde(default)]
+    pub rated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub note: String,
+    #[serde(default)]
+    pub note_updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub view_count: i32,
+    #[serde(default)]
+    pub last_viewed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<&record_local::Model> for RecordLine {
+    fn from(model: &record_local::Model) -> Self {
+        Self {
+            record: model.into_record(),
+            cover: model.cover.clone(),
+            sample_image_links: serde_json::from_value(model.sample_image_links.clone())
+                .unwrap_or_default(),
+            viewed: model.viewed,
+            is_liked: model.is_liked,
+            is_submitted: model.is_submitted,
+            is_cached_locally: model.is_cached_locally,
+            rating: model.rating,
+            rated_at: model.rated_at,
+            note: model.note.clone(),
+            note_updated_at: model.note_updated_at,
+            view_count: model.view_count,
+            last_viewed_at: model.last_viewed_at,
+            created_at: Some(model.created_at),
+            updated_at: Some(model.updated_at),
+        }
+    }
+}
+
+impl RecordLine {
+    /// 检查导入所需的字段
+    fn validate(&self) -> std::result::Result<(), String> {
+        if self.record.id.trim().is_empty() {
+            return Err("id is empty".to_owned());
+        }
+        if self.record.title.trim().is_empty() {
+            return Err("title is empty".to_owned());
+        }
+        if self.record.local_image_count < 0 {
+            return Err("local_image_count is negative".to_owned());
+        }
+        if self.view_count < 0 {
+            return Err("view_count is negative".to_owned());
+        }
+        if !(0..=record_local::MAX_RATING).contains(&self.rating) {
+            return Err(format!(
+                "rating is out of range 0-{}",
+                record_local::MAX_RATING
+            ));
+        }
+        Ok(())
+    }
+
+    fn into_active_model(self) -> record_local::ActiveModel {
+        let mut active_model = record_local::Model::from_record_entry(&self.record);
+        active_model.cover = Set(self.cover);
+        active_model.sample_image_links =
+            Set(serde_json::to_value(self.sample_image_links).unwrap_or_default());
+        active_model.viewed = Set(self.viewed);
+        active_model.is_liked = Set(self.is_liked);
+        active_model.is_submitted = Set(self.is_submitted);
+        active_model.is_cached_locally = Set(self.is_cached_locally);
+        active_model.rating = Set(self.rating);
+        active_model.rated_at = Set(self.rated_at);
+        active_model.note = Set(self.note);
+        active_model.note_updated_at = Set(self.note_updated_at);
+        // 旧版导出文件只有 viewed 标记，至少计为查看过一次
+        active_model.view_count = Set(if self.viewed {
+            self.view_count.max(1)
+        } else {
+            self.view_count
+        });
+        active_model.last_viewed_at = Set(self.last_viewed_at);
+        if let Some(created_at) = self.created_at {
+            active_model.created_at = Set(created_at);
+        }
+        active_model
+    }
+
+    fn csv_row(&self) -> [String; 26] {
+        let record = &self.record;
+        let links = record
+            .share_magnet_links
+            .iter()
+            .map(|link| link.link.as_str())
+            .collect::<Vec<_>>();
+        [
+            record.id.clone(),
+            record.title.clone(),
+            record.release_date.clone(),
+            record.length.clone(),
+            join_names(&record.director),
+            join_names(&record.studio),
+            join_names(&record.label),
+            join_names(&record.series),
+            join_names(&record.genre),
+            join_names(&record.idols),
+            links.join(CSV_LIST_SEPARATOR),
+            record.local_image_count.to_string(),
+            self.cover.clone(),
+            self.sample_image_links.join(CSV_LIST_SEPARATOR),
+            self.viewed.to_string(),
+            self.is_liked.to_string(),
+            self.is_submitted.to_string(),
+            self.is_cached_locally.to_string(),
+            self.rating.to_string(),
+            self.rated_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            self.note.clone(),
+            self.note_updated_at
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
+            self.view_count.to_string(),
+            self.last_viewed_at
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
+            self.created_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            self.updated_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+        ]
+    }
+
+    /// 由 [`Self::csv_row`] 导出的一行还原，按表头取列，缺少的列取默认值
+    fn from_csv(
+        headers: &csv::StringRecord,
+        row: &csv::StringRecord,
+    ) -> std::result::Result<Self, String> {
+        let field = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header == name)
+                .and_then(|index| row.get(index))
+                .unwrap_or_default()
+        };
+        let list = |name: &str| {
+            field(name)
+                .split(CSV_LIST_SEPARATOR)
+                .filter(|value| !value.is_empty())
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        };
+        let names = |name: &str| {
+            list(name)
+                .into_iter()
+                .map(|name| (name, Value::String(String::new())))
+                .collect::<serde_json::Map<_, _>>()
+        };
+        let parse = |name: &str| -> std::result::Result<Value, String> {
+            let value = field(name);
+            if value.is_empty() {
+                return Ok(Value::Null);
+            }
+            serde_json::from_str(value).map_err(|e| format!("invalid {name}: {e}"))
+        };
+        let text = |name: &str| match field(name) {
+            "" => Value::Null,
+            value => Value::String(value.to_owned()),
+        };
+
+        let links = list("share_magnet_links")
+            .into_iter()
+            .map(|link| json!({ "name": "", "link": link, "size": "" }))
+            .collect::<Vec<_>>();
+        let value = json!({
+            "id": field("id"),
+            "title": field("title"),
+            "release_date": field("release_date"),
+            "length": field("length"),
+            "director": names("director"),
+            "studio": names("studio"),
+            "label": names("label"),
+            "series": names("series"),
+            "genre": names("genre"),
+            "idols": names("idols"),
+            "share_magnet_links": links,
+            "local_image_count": parse("local_image_count")?,
+            "cover": field("cover"),
+            "sample_image_links": list("sample_image_links"),
+            "viewed": parse("viewed")?,
+            "is_liked": parse("is_liked")?,
+            "is_submitted": parse("is_submitted")?,
+            "is_cached_locally": parse("is_cached_locally")?,
+            "rating": parse("rating")?,
+            "rated_at": text("rated_at"),
+            "note": field("note"),
+            "note_updated_at": text("note_updated_at"),
+            "view_count": parse("view_count")?,
+            "last_viewed_at": text("last_viewed_at"),
+            "created_at": text("created_at"),
+            "updated_at": text("updated_at"),
+        });
+        serde_json::from_value(without_nulls(value)).map_err(|e| e.to_string())
+    }
+}
+
+/// 去掉取值为空的字段，使其按 `#[serde(default)]` 取默认值
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            Value::Object(map.into_iter().filter(|(_, v)| !v.is_null()).collect())
+        }
+        other => other,
+    }
+}
+
+/// 按名称排序后连接，保证导出结果稳定
+fn join_names(map: &HashMap<String, String>) -> String {
+    let mut names = map.keys().map(String::as_str).collect::<Vec<_>>();
+    names.sort_unstable();
+    names.join(CSV_LIST_SEPARATOR)
+}
+
+/// 导入结果，试运行时为将要产生的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    /// 非空行数
+    pub total: usize,
+    pub inserted: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub errors: Vec<ImportLineError>,
+}
+
+/// 无法导入的行
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportLineError {
+    /// 从 1 开始的行号
+    pub line: usize,
+    /// 能解析出编号时为该行记录的编号
+    pub id: Option<String>,
+    pub message: String,
+}
+
+impl super::DbOperator {
+    /// 按列表页的过滤条件与排序导出记录，返回导出的记录数
+    ///
+    /// 以游标分页逐页读取并写入 `writer`，不会一次载入全部记录。
+    pub async fn export_local(
+        &self,
+        filters: Vec<LocalFilterCondition>,
+        sort: SortSpec,
+        format: ExportFormat,
+        writer: impl Write,
+    ) -> Result<usize> {
+        let mut writer = match format {
+            ExportFormat::Jsonl => ExportWriter::Jsonl(writer),
+            ExportFormat::Csv => {
+                let mut csv = csv::Writer::from_writer(writer);
+                csv.write_record(CSV_HEADER).map_err(transfer_err)?;
+                ExportWriter::Csv(Box::new(csv))
+            }
+        };
+
+        let mut exported = 0;
+        let mut start = PageStart::First;
+        loop {
+            let page = self
+                .query_local_page(filters.clone(), sort, start, EXPORT_PAGE_SIZE)
+                .await?;
+            for model in &page.records {
+                writer.write(&RecordLine::from(model))?;
+            }
+            exported += page.records.len();
+            match page.next_cursor {
+                Some(cursor) => start = PageStart::Cursor(cursor),
+                None => break,
+            }
+        }
+        writer.flush()?;
+
+        log::info!("Exported {exported} local records as {format:?}");
+        Ok(exported)
+    }
+
+    /// 从 JSON Lines 或 CSV 导入记录，按 [`Self::upsert_local`] 的方式合并到已有记录
+    ///
+    /// 逐行校验，无法解析或校验失败的行记入报告并跳过，不影响其他行。新记录保留文件中的
+    /// 本地状态，已有记录只合并元数据。`dry_run` 时在事务中执行后回滚，报告内容与实际
+    /// 导入一致。
+    pub async fn import_local(
+        &self,
+        reader: impl BufRead,
+        format: ExportFormat,
+        dry_run: bool,
+        source: &RevisionSource,
+    ) -> Result<ImportReport> {
+        let mut report = ImportReport {
+            dry_run,
+            ..ImportReport::default()
+        };
+
+        let txn = self.db.begin().await?;
+        match format {
+            ExportFormat::Jsonl => {
+                for (index, line) in reader.lines().enumerate() {
+                    let line = line.map_err(transfer_err)?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let record = serde_json::from_str::<RecordLine>(&line)
+                        .map_err(|e| (line_id(&line), e.to_string()));
+                    import_record(&txn, &mut report, index + 1, record, source).await;
+                }
+            }
+            ExportFormat::Csv => {
+                let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+                let headers = reader.headers().map_err(transfer_err)?.clone();
+                for row in reader.records() {
+                    let (line, record) = match row {
+                        Ok(row) => (
+                            csv_line(row.position()),
+                            RecordLine::from_csv(&headers, &row)
+                                .map_err(|message| (csv_id(&headers, &row), message)),
+                        ),
+                        Err(e) if e.is_io_error() => return Err(transfer_err(e)),
+                        Err(e) => (csv_line(e.position()), Err((None, e.to_string()))),
+                    };
+                    import_record(&txn, &mut report, line, record, source).await;
+                }
+            }
+        }
+
+        if dry_run {
+            txn.rollback().await?;
+        } else {
+            txn.commit().await?;
+        }
+
+        log::info!(
+            "Imported local records (dry run: {dry_run}): {} inserted, {} updated, {} unchanged, {} failed",
+            report.inserted,
+            report.updated,
+            report.unchanged,
+            report.errors.len()
+        );
+        Ok(report)
+    }
+}
+
+enum ExportWriter<W: Write> {
+    Jsonl(W),
+    Csv(Box<csv::Writer<W>>),
+}
+
+impl<W: Write> ExportWriter<W> {
+    fn write(&mut self, line: &RecordLine) -> Result<()> {
+        match self {
+            Self::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, line)?;
+                writer.write_all(b"\n").map_err(transfer_err)
+            }
+            Self::Csv(writer) => writer.write_record(line.csv_row()).map_err(transfer_err),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self {
+            Self::Jsonl(writer) => writer.flush(),
+            Self::Csv(writer) => writer.flush(),
+        }
+        .map_err(transfer_err)
+    }
+}
+
+fn transfer_err(err: impl std::fmt::Display) -> DbError {
+    DbError::TransferFailed(err.to_string())
+}
+
+/// 尽量从无法导入的行中取出记录编号
+fn line_id(line: &str) -> Option<String> {
+    let value = serde_json::from_str::<serde_json::Value>(line).ok()?;
+    Some(value.get("id")?.as_str()?.to_owned())
+}
+
+/// 校验并写入一条记录，结果计入 `report`
+async fn import_record(
+    txn: &DatabaseTransaction,
+    report: &mut ImportReport,
+    line: usize,
+    record: std::result::Result<RecordLine, (Option<String>, String)>,
+    source: &RevisionSource,
+) {
+    report.total += 1;
+    let record = record.and_then(|record| match record.validate() {
+        Ok(()) => Ok(record),
+        Err(message) => Err((Some(record.record.id), message)),
+    });
+    let record = match record {
+        Ok(record) => record,
+        Err((id, message)) => {
+            report.errors.push(ImportLineError { line, id, message });
+            return;
+        }
+    };
+
+    let id = record.record.id.clone();
+    match upsert_local_in(txn, record.into_active_model(), source).await {
+        Ok((None, _)) => report.inserted += 1,
+        Ok((Some(before), saved)) if before == saved => report.unchanged += 1,
+        Ok((Some(_), _)) => report.updated += 1,
+        Err(e) => report.errors.push(ImportLineError {
+            line,
+            id: Some(id),
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// CSV 行在文件中的行号，表头为第 1 行
+fn csv_line(position: Option<&csv::Position>) -> usize {
+    position
+        .and_then(|position| usize::try_from(position.line()).ok())
+        .unwrap_or_default()
+}
+
+/// 尽量从无法导入的 CSV 行中取出记录编号
+fn csv_id(headers: &csv::StringRecord, row: &csv::StringRecord) -> Option<String> {
+    let index = headers.iter().position(|header| header == "id")?;
+    row.get(index)
+        .filter(|id| !id.is_empty())
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DbOperator;
+    use crate::test_util::{attribute, memory_db, record};
+    use crate::types::OperationType;
+
+    async fn seeded_db() -> DbOperator {
+        let db = memory_db().await;
+        let mut r = record("A-1");
+        r.genre = Set(attribute(&["Drama", "Comedy"]));
+        r.share_magnet_links = Set(json!([{ "name": "a", "link": "magnet:?xt=a", "size": "1GB" }]));
+        r.rating = Set(4);
+        r.rated_at = Set(Some(Utc::now()));
+        r.note = Set("first, \"quoted\"\nline".to_owned());
+        r.note_updated_at = Set(Some(Utc::now()));
+        r.viewed = Set(true);
+        r.view_count = Set(3);
+        db.insert_local(r).await.expect("insert");
+
+        let mut r = record("A-2");
+        r.is_liked = Set(true);
+        db.insert_local(r).await.expect("insert");
+        db
+    }
+
+    async fn import(
+        db: &DbOperator,
+        data: &[u8],
+        format: ExportFormat,
+        dry_run: bool,
+    ) -> ImportReport {
+        let source = RevisionSource::new(OperationType::Update);
+        db.import_local(data, format, dry_run, &source)
+            .await
+            .expect("import")
+    }
+
+    #[tokio::test]
+    async fn export_then_import_round_trips() {
+        let exported = seeded_db().await;
+
+        for format in [ExportFormat::Jsonl, ExportFormat::Csv] {
+            let mut data = Vec::new();
+            let count = exported
+                .export_local(vec![], SortSpec::default(), format, &mut data)
+                .await
+                .expect("export");
+            assert_eq!(count, 2, "{format:?}");
+
+            let db = memory_db().await;
+            let report = import(&db, &data, format, true).await;
+            assert_eq!(
+                (report.total, report.inserted, report.errors.len()),
+                (2, 2, 0),
+                "{format:?}: {report:?}"
+            );
+            assert_eq!(
+                db.query_total_count(vec![]).await.expect("count"),
+                0,
+                "{format:?}: dry run should not write"
+            );
+
+            let report = import(&db, &data, format, false).await;
+            assert_eq!(
+                (report.total, report.inserted, report.errors.len()),
+                (2, 2, 0),
+                "{format:?}: {report:?}"
+            );
+            for id in ["A-1", "A-2"] {
+                let before = exported
+                    .find_record_local_by_id(id)
+                    .await
+                    .expect("find")
+                    .expect("exported record");
+                let after = db
+                    .find_record_local_by_id(id)
+                    .await
+                    .expect("find")
+                    .expect("imported record");
+                assert_eq!(after.rating, before.rating, "{format:?} {id}");
+                assert_eq!(after.note, before.note, "{format:?} {id}");
+                assert_eq!(after.is_liked, before.is_liked, "{format:?} {id}");
+                assert_eq!(after.view_count, before.view_count, "{format:?} {id}");
+                assert_eq!(after.genre, before.genre, "{format:?} {id}");
+                assert_eq!(after.created_at, before.created_at, "{format:?} {id}");
+            }
+
+            // 再次导入时记录没有变化
+            let report = import(&db, &data, format, false).await;
+            assert_eq!(
+                (report.total, report.unchanged, report.errors.len()),
+                (2, 2, 0),
+                "{format:?}: {report:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn import_reports_invalid_lines() {
+        let db = memory_db().await;
+        let data = concat!(
+            "not json\n",
+            "\n",
+            r#"{"id": "A-1", "title": "", "rating": 3}"#,
+            "\n",
+        );
+        let report = import(&db, data.as_bytes(), ExportFormat::Jsonl, false).await;
+        assert_eq!(report.total, 2);
+        let lines = report
+            .errors
+            .iter()
+            .map(|error| (error.line, error.id.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [(1, None), (3, Some("A-1".to_owned()))]);
+
+        let data = "id,title,local_image_count,rating\nB-1,Title,0,9\nB-2,,0,3\n";
+        let report = import(&db, data.as_bytes(), ExportFormat::Csv, false).await;
+        assert_eq!(report.total, 2);
+        let lines = report
+            .errors
+            .iter()
+            .map(|error| (error.line, error.id.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [(2, Some("B-1".to_owned())), (3, Some("B-2".to_owned()))]
+        );
+        assert_eq!(db.query_total_count(vec![]).await.expect("count"), 0);
+    }
+}
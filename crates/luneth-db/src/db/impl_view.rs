@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use sea_orm::{
+    ActiveModelTrait as _, ColumnTrait as _, ConnectionTrait as _, DbBackend, EntityTrait as _,
+    IntoActiveModel as _, PaginatorTrait as _, QueryFilter as _, QueryOrder as _, QuerySelect as _,
+    Set, Statement, TransactionTrait as _,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Result;
+use super::impl_local::{PageOrder, PageStart, RecordPage, SortField, SortOrder, SortSpec};
+use crate::entities::{record_local, record_view};
+use crate::types::DbError;
+
+/// 查看事件的保留策略，未设置的项不限
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewRetention {
+    /// 保留最近若干天内的事件
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    /// 每条记录保留最近若干次事件
+    #[serde(default)]
+    pub max_per_record: Option<u32>,
+}
+
+/// 记录及其在统计区间内的查看次数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewStat {
+    #[serde(flatten)]
+    pub record: record_local::Model,
+    pub views: u64,
+}
+
+impl super::DbOperator {
+    /// 记录一次查看，返回查看事件，离开时以 [`Self::finish_view`] 补充停留时长
+    pub async fn record_view(&self, record_id: &str) -> Result<record_view::Model> {
+        let txn = self.db.begin().await?;
+        record_local::Entity::find_by_id(record_id)
+            .one(&txn)
+            .await?
+            .ok_or_else(|| DbError::NotFound(format!("record_local {record_id}")))?;
+        let view = record_view::ActiveModel {
+            record_id: Set(record_id.to_owned()),
+            viewed_at: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+        Ok(view)
+    }
+
+    /// 设置查看事件的停留时长（秒）
+    pub async fn finish_view(
+        &self,
+        view_id: i32,
+        duration_secs: i32,
+    ) -> Result<record_view::Model> {
+        let mut view = record_view::Entity::find_by_id(view_id)
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| DbError::NotFound(format!("record_view {view_id}")))?
+            .into_active_model();
+        view.duration_secs = Set(Some(duration_secs.max(0)));
+        Ok(view.update(&self.db).await?)
+    }
+
+    /// 记录的查看事件，最近的在前
+    pub async fn query_record_views(&self, record_id: &str) -> Result<Vec<record_view::Model>> {
+        let views = record_view::Entity::find()
+            .filter(record_view::Column::RecordId.eq(record_id))
+            .order_by_desc(record_view::Column::ViewedAt)
+            .order_by_desc(record_view::Column::Id)
+            .all(&self.db)
+            .await?;
+        Ok(views)
+    }
+
+    /// 最近查看的记录，按最近查看时间倒序，`since` 限定最早的查看时间
+    pub async fn query_recently_viewed(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: u64,
+    ) -> Result<Vec<record_local::Model>> {
+        use record_local::Column;
+
+        let mut query = record_local::Entity::find()
+            .filter(Column::DeletedAt.is_null())
+            .filter(Column::LastViewedAt.is_not_null());
+        if let Some(since) = since {
+            query = query.filter(Column::LastViewedAt.gte(since));
+        }
+        let records = query
+            .order_by_desc(Column::LastViewedAt)
+            .order_by_desc(Column::Id)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+        Ok(records)
+    }
+
+    /// 查看次数最多的记录
+    ///
+    /// 未指定 `since` 时按累计次数统计；指定时只统计该时间之后仍保留的查看事件。
+    pub async fn query_most_viewed(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: u64,
+    ) -> Result<Vec<ViewStat>> {
+        use record_local::Column;
+
+        let Some(since) = since else {
+            let stats = record_local::Entity::find()
+                .filter(Column::DeletedAt.is_null())
+                .filter(Column::ViewCount.gt(0))
+                .order_by_desc(Column::ViewCount)
+                .order_by_desc(Column::Id)
+                .limit(limit)
+                .all(&self.db)
+                .await?
+                .into_iter()
+                .map(|record| ViewStat {
+                    views: u64::try_from(record.view_count).unwrap_or_default(),
+                    record,
+                })
+                .collect();
+            return Ok(stats);
+        };
+
+        let counts = record_view::Entity::find()
+            .select_only()
+            .column(record_view::Column::RecordId)
+            .column_as(record_view::Column::Id.count(), "views")
+            .inner_join(record_local::Entity)
+            .filter(record_view::Column::ViewedAt.gte(since))
+            .filter(Column::DeletedAt.is_null())
+            .group_by(record_view::Column::RecordId)
+            .order_by_desc(record_view::Column::Id.count())
+            .order_by_desc(record_view::Column::RecordId)
+            .limit(limit)
+            .into_tuple::<(String, i64)>()
+            .all(&self.db)
+            .await?;
+        let mut records = record_local::Entity::find()
+            .filter(Column::Id.is_in(counts.iter().map(|(id, _)| id.clone())))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|record| (record.id.clone(), record))
+            .collect::<HashMap<_, _>>();
+
+        let stats = counts
+            .into_iter()
+            .filter_map(|(id, views)| {
+                records.remove(&id).map(|record| ViewStat {
+                    record,
+                    views: views.unsigned_abs(),
+                })
+            })
+            .collect();
+        Ok(stats)
+    }
+
+    /// 按游标分页查询从未查看过的记录，最新加入的在前
+    pub async fn query_never_viewed(&self, start: PageStart, limit: u64) -> Result<RecordPage> {
+        use record_local::Column;
+
+        let query = record_local::Entity::find()
+            .filter(Column::DeletedAt.is_null())
+            .filter(Column::Viewed.eq(false));
+        let total = query.clone().count(&self.db).await?;
+        let newest = SortSpec {
+            field: SortField::CreatedAt,
+            order: SortOrder::Desc,
+        };
+        self.query_page(query, total, PageOrder::Sort(newest), start, limit)
+            .await
+    }
+
+    /// 按保留策略清理查看事件，返回删除的事件数
+    ///
+    /// 累计查看次数与最近查看时间不受影响。
+    pub async fn prune_view_events(&self, retention: ViewRetention) -> Result<u64> {
+        let txn = self.db.begin().await?;
+        let mut removed = 0;
+
+        if let Some(days) = retention.max_age_days {
+            let cutoff = Utc::now() - TimeDelta::days(days.into());
+            removed += record_view::Entity::delete_many()
+                .filter(record_view::Column::ViewedAt.lt(cutoff))
+                .exec(&txn)
+                .await?
+                .rows_affected;
+        }
+
+        if let Some(max) = retention.max_per_record {
+            removed += txn
+                .execute(Statement::from_sql_and_values(
+                    DbBackend::Sqlite,
+                    "DELETE FROM record_view WHERE id IN ( \
+                        SELECT id FROM ( \
+                            SELECT id, ROW_NUMBER() OVER ( \
+                                PARTITION BY record_id ORDER BY viewed_at DESC, id DESC \
+                            ) AS rank FROM record_view \
+                        ) WHERE rank > ? \
+                    )",
+                    [max.into()],
+                ))
+                .await?
+                .rows_affected();
+        }
+
+        txn.commit().await?;
+        log::info!("Pruned {removed} view events with retention {retention:?}");
+        Ok(removed)
+    }
+}
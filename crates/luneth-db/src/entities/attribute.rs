@@ -0,0 +1,38 @@
+use crate::types::AttributeKind;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 记录属性：演员、类别、片商、发行商、系列与导演
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "attribute")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub kind: AttributeKind,
+    pub name: String,
+    pub link: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::record_attribute::Entity")]
+    RecordAttribute,
+}
+
+impl Related<super::record_attribute::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecordAttribute.def()
+    }
+}
+
+impl Related<super::record_local::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::record_attribute::Relation::RecordLocal.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(super::record_attribute::Relation::Attribute.def().rev())
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
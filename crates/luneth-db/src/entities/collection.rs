@@ -0,0 +1,40 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 用户手动维护的记录列表
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "collection")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub name: String,
+    pub description: String,
+    pub created_at: ChronoDateTimeUtc,
+    /// 名称、描述或成员变化时更新
+    pub updated_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::collection_record::Entity")]
+    CollectionRecord,
+}
+
+impl Related<super::collection_record::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CollectionRecord.def()
+    }
+}
+
+impl Related<super::record_local::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::collection_record::Relation::RecordLocal.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(super::collection_record::Relation::Collection.def().rev())
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
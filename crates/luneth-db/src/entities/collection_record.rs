@@ -0,0 +1,47 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 列表中的记录及其位置
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "collection_record")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub collection_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub record_id: String,
+    /// 列表内的顺序，从 0 开始
+    pub position: i32,
+    pub added_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::collection::Entity",
+        from = "Column::CollectionId",
+        to = "super::collection::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Collection,
+    #[sea_orm(
+        belongs_to = "super::record_local::Entity",
+        from = "Column::RecordId",
+        to = "super::record_local::Column::Id",
+        on_delete = "Cascade"
+    )]
+    RecordLocal,
+}
+
+impl Related<super::collection::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Collection.def()
+    }
+}
+
+impl Related<super::record_local::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecordLocal.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -9,17 +9,32 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub recorder_id: String,
-    pub operation: String,
+    pub operation: OperationType,
     pub timestamp: ChronoDateTimeUtc,
-    pub status: String,
+    pub status: OperationStatus,
     pub user: String,
     pub error_message: Option<String>,
+    /// 产生该操作的任务，手动操作为空
+    pub task_id: Option<String>,
     pub created_at: ChronoDateTimeUtc,
     pub updated_at: ChronoDateTimeUtc,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::history_task::Entity",
+        from = "Column::TaskId",
+        to = "super::history_task::Column::Id"
+    )]
+    HistoryTask,
+}
+
+impl Related<super::history_task::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::HistoryTask.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {
     fn new() -> Self {
@@ -54,6 +69,14 @@ impl ActiveModelBehavior for ActiveModel {
     }
 }
 
+impl ActiveModel {
+    /// 关联到指定任务
+    pub fn with_task_id(mut self, task_id: &str) -> Self {
+        self.task_id = Set(Some(task_id.to_owned()));
+        self
+    }
+}
+
 impl Model {
     /// 创建新的操作历史记录
     pub fn new_record(
@@ -66,21 +89,11 @@ impl Model {
         let mut active_model = ActiveModel::new();
 
         active_model.recorder_id = Set(recorder_id);
-        active_model.operation = Set(operation.to_string());
-        active_model.status = Set(status.to_string());
+        active_model.operation = Set(operation);
+        active_model.status = Set(status);
         active_model.user = Set(user);
         active_model.error_message = Set(error_message);
 
         active_model
     }
-
-    /// 获取操作类型枚举
-    pub fn get_operation_type(&self) -> Result<OperationType, String> {
-        self.operation.parse()
-    }
-
-    /// 获取操作状态枚举
-    pub fn get_operation_status(&self) -> Result<OperationStatus, String> {
-        self.status.parse()
-    }
 }
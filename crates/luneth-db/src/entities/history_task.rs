@@ -8,22 +8,38 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: String,
-    pub task_type: String,
+    pub task_type: TaskType,
     pub start_time: ChronoDateTimeUtc,
     pub end_time: Option<ChronoDateTimeUtc>,
-    pub status: String,
+    pub status: TaskStatus,
     #[sea_orm(column_type = "Json")]
     pub target_ids: Json,
     #[sea_orm(column_type = "Json")]
     pub failed_ids: Json,
     pub total_count: i32,
     pub failed_count: i32,
+    /// 任务中止的原因
+    pub error_message: Option<String>,
+    /// 重试时指向原任务
+    pub parent_id: Option<String>,
+    /// 重建任务所需的配置
+    #[sea_orm(column_type = "Json", nullable)]
+    pub config: Option<Json>,
     pub created_at: ChronoDateTimeUtc,
     pub updated_at: ChronoDateTimeUtc,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::history_op::Entity")]
+    HistoryOp,
+}
+
+impl Related<super::history_op::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::HistoryOp.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {
     fn new() -> Self {
@@ -63,13 +79,27 @@ impl ActiveModelBehavior for ActiveModel {
     }
 }
 
+impl ActiveModel {
+    /// 记录为对指定任务的重试
+    pub fn with_parent_id(mut self, parent_id: &str) -> Self {
+        self.parent_id = Set(Some(parent_id.to_owned()));
+        self
+    }
+
+    /// 保存重建任务所需的配置
+    pub fn with_config(mut self, config: Json) -> Self {
+        self.config = Set(Some(config));
+        self
+    }
+}
+
 impl Model {
     /// 创建新的任务记录
     pub fn new_task(task_type: TaskType, status: TaskStatus, target_ids: &[String]) -> ActiveModel {
         let mut active_model = ActiveModel::new();
 
-        active_model.task_type = Set(task_type.to_string());
-        active_model.status = Set(status.to_string());
+        active_model.task_type = Set(task_type);
+        active_model.status = Set(status);
         active_model.target_ids = Set(serde_json::to_value(target_ids).unwrap_or_default());
         active_model.total_count = Set(target_ids.len() as i32);
 
@@ -80,7 +110,7 @@ impl Model {
     pub fn update_status(&self, status: TaskStatus, failed_ids: &[String]) -> ActiveModel {
         let mut active_model: ActiveModel = self.clone().into();
 
-        active_model.status = Set(status.to_string());
+        active_model.status = Set(status);
         active_model.failed_ids = Set(serde_json::to_value(failed_ids).unwrap_or_default());
         active_model.failed_count = Set(failed_ids.len() as i32);
 
@@ -94,14 +124,22 @@ impl Model {
         active_model
     }
 
-    /// 获取任务类型枚举
-    pub fn get_task_type(&self) -> Result<TaskType, String> {
-        self.task_type.parse()
+    /// 追加运行中才确定的目标
+    pub fn add_targets(&self, target_ids: &[String]) -> ActiveModel {
+        let mut targets = self.get_target_ids().unwrap_or_default();
+        targets.extend_from_slice(target_ids);
+
+        let mut active_model: ActiveModel = self.clone().into();
+        active_model.total_count = Set(targets.len() as i32);
+        active_model.target_ids = Set(serde_json::to_value(targets).unwrap_or_default());
+        active_model
     }
 
-    /// 获取任务状态枚举
-    pub fn get_task_status(&self) -> Result<TaskStatus, String> {
-        self.status.parse()
+    /// 结束任务并记录中止原因
+    pub fn abort(&self, failed_ids: &[String], error_message: String) -> ActiveModel {
+        let mut active_model = self.update_status(TaskStatus::Aborted, failed_ids);
+        active_model.error_message = Set(Some(error_message));
+        active_model
     }
 
     /// 获取目标ID列表
@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 记录与属性的关联
+///
+/// 由 `record_local` 的触发器根据其 JSON 映射列维护，不应直接写入。
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "record_attribute")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub record_id: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub attribute_id: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::record_local::Entity",
+        from = "Column::RecordId",
+        to = "super::record_local::Column::Id",
+        on_delete = "Cascade"
+    )]
+    RecordLocal,
+    #[sea_orm(
+        belongs_to = "super::attribute::Entity",
+        from = "Column::AttributeId",
+        to = "super::attribute::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Attribute,
+}
+
+impl Related<super::record_local::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecordLocal.def()
+    }
+}
+
+impl Related<super::attribute::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Attribute.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
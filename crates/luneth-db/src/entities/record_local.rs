@@ -3,6 +3,11 @@ use sea_orm::entity::prelude::*;
 use sea_orm::{ActiveModelTrait, IntoActiveModel as _, Set};
 use serde::{Deserialize, Serialize};
 
+/// 本地记录
+///
+/// 演员、类别等 JSON 映射列是属性的权威数据，同时由触发器在写入时拆分到
+/// `attribute` 与 `record_attribute` 表中。关系表只用于关联、计数与过滤，
+/// 修改属性（包括改名）应改写 JSON 列，由触发器重建关联。
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "record_local")]
 pub struct Model {
@@ -33,17 +38,101 @@ pub struct Model {
     #[sea_orm(column_type = "Json")]
     pub sample_image_links: Json,
 
+    /// 是否查看过，由 `record_view` 的触发器维护
     pub viewed: bool,
     pub is_liked: bool,
     pub is_submitted: bool,
     pub is_cached_locally: bool,
 
+    /// 评分 1–5，0 表示未评分
+    pub rating: i32,
+    pub rated_at: Option<ChronoDateTimeUtc>,
+    /// Markdown 格式的私人备注，为空表示没有备注
+    pub note: String,
+    pub note_updated_at: Option<ChronoDateTimeUtc>,
+    /// 累计查看次数，清理查看事件不影响此值
+    pub view_count: i32,
+    pub last_viewed_at: Option<ChronoDateTimeUtc>,
+
     pub created_at: ChronoDateTimeUtc,
     pub updated_at: ChronoDateTimeUtc,
+    /// 移入回收站的时间，为空表示未删除
+    pub deleted_at: Option<ChronoDateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::record_attribute::Entity")]
+    RecordAttribute,
+    #[sea_orm(has_many = "super::record_revision::Entity")]
+    RecordRevision,
+    #[sea_orm(has_many = "super::record_tag::Entity")]
+    RecordTag,
+    #[sea_orm(has_many = "super::collection_record::Entity")]
+    CollectionRecord,
+    #[sea_orm(has_many = "super::record_view::Entity")]
+    RecordView,
+}
+
+impl Related<super::record_attribute::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecordAttribute.def()
+    }
+}
+
+impl Related<super::record_revision::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecordRevision.def()
+    }
+}
+
+impl Related<super::record_tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecordTag.def()
+    }
+}
+
+impl Related<super::collection_record::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CollectionRecord.def()
+    }
+}
+
+impl Related<super::record_view::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecordView.def()
+    }
+}
+
+impl Related<super::attribute::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::record_attribute::Relation::Attribute.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(super::record_attribute::Relation::RecordLocal.def().rev())
+    }
+}
+
+impl Related<super::tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::record_tag::Relation::Tag.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(super::record_tag::Relation::RecordLocal.def().rev())
+    }
+}
+
+impl Related<super::collection::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::collection_record::Relation::Collection.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(super::collection_record::Relation::RecordLocal.def().rev())
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {
     fn new() -> Self {
@@ -65,6 +154,9 @@ impl ActiveModelBehavior for ActiveModel {
             is_submitted: Set(false),
             is_cached_locally: Set(false),
             viewed: Set(false),
+            rating: Set(0),
+            note: Set(String::new()),
+            view_count: Set(0),
             ..ActiveModelTrait::default()
         }
     }
@@ -92,34 +184,42 @@ impl ActiveModelBehavior for ActiveModel {
     }
 }
 
+/// 评分上限
+pub const MAX_RATING: i32 = 5;
+
 impl Model {
     pub fn from_recorder(recorder: &Recorder) -> ActiveModel {
+        let mut active_model = Self::from_record_entry(&recorder.record);
+
+        active_model.cover = Set(recorder.cover.clone());
+        active_model.sample_image_links =
+            Set(serde_json::to_value(&recorder.sample_image_links).unwrap_or_default());
+
+        active_model
+    }
+
+    /// 由记录元数据构建，封面与样图链接为空
+    pub fn from_record_entry(record: &RecordEntry) -> ActiveModel {
         let mut active_model = ActiveModel::new();
 
-        active_model.id = Set(recorder.record.id.clone());
-        active_model.title = Set(recorder.record.title.clone());
-        active_model.release_date = Set(recorder.record.release_date.clone());
-        active_model.length = Set(recorder.record.length.clone());
-        active_model.local_image_count = Set(recorder.record.local_image_count);
+        active_model.id = Set(record.id.clone());
+        active_model.title = Set(record.title.clone());
+        active_model.release_date = Set(record.release_date.clone());
+        active_model.length = Set(record.length.clone());
+        active_model.local_image_count = Set(record.local_image_count);
+        active_model.cover = Set(String::new());
 
         // 转换 HashMap 为 JSON
-        active_model.director =
-            Set(serde_json::to_value(&recorder.record.director).unwrap_or_default());
-        active_model.studio =
-            Set(serde_json::to_value(&recorder.record.studio).unwrap_or_default());
-        active_model.label = Set(serde_json::to_value(&recorder.record.label).unwrap_or_default());
-        active_model.series =
-            Set(serde_json::to_value(&recorder.record.series).unwrap_or_default());
-        active_model.genre = Set(serde_json::to_value(&recorder.record.genre).unwrap_or_default());
-        active_model.idols = Set(serde_json::to_value(&recorder.record.idols).unwrap_or_default());
+        active_model.director = Set(serde_json::to_value(&record.director).unwrap_or_default());
+        active_model.studio = Set(serde_json::to_value(&record.studio).unwrap_or_default());
+        active_model.label = Set(serde_json::to_value(&record.label).unwrap_or_default());
+        active_model.series = Set(serde_json::to_value(&record.series).unwrap_or_default());
+        active_model.genre = Set(serde_json::to_value(&record.genre).unwrap_or_default());
+        active_model.idols = Set(serde_json::to_value(&record.idols).unwrap_or_default());
 
         // 转换数组为 JSON
         active_model.share_magnet_links =
-            Set(serde_json::to_value(&recorder.record.share_magnet_links).unwrap_or_default());
-
-        active_model.cover = Set(recorder.cover.clone());
-        active_model.sample_image_links =
-            Set(serde_json::to_value(&recorder.sample_image_links).unwrap_or_default());
+            Set(serde_json::to_value(&record.share_magnet_links).unwrap_or_default());
 
         active_model
     }
@@ -176,6 +276,23 @@ impl Model {
         active_model
     }
 
+    /// 设置评分并记录评分时间，0 表示清除评分
+    pub fn set_rating(self, rating: i32) -> ActiveModel {
+        let mut active_model = self.into_active_model();
+        active_model.rating = Set(rating);
+        active_model.rated_at = Set((rating > 0).then(chrono::Utc::now));
+        active_model
+    }
+
+    /// 设置备注并记录修改时间，空白内容表示清除备注
+    pub fn set_note(self, note: &str) -> ActiveModel {
+        let note = note.trim();
+        let mut active_model = self.into_active_model();
+        active_model.note = Set(note.to_owned());
+        active_model.note_updated_at = Set((!note.is_empty()).then(chrono::Utc::now));
+        active_model
+    }
+
     pub fn update_links(self, links: Vec<luneth::common::MagnetLink>) -> ActiveModel {
         let mut active_model = self.into_active_model();
         active_model.share_magnet_links = Set(serde_json::to_value(links).unwrap_or_default());
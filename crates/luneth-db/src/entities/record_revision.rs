@@ -0,0 +1,45 @@
+use crate::types::OperationType;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 本地记录元数据的一次变更
+///
+/// `snapshot` 保存变更前的元数据，回滚到该修订即恢复为此快照；`diff` 以列名为键
+/// 保存 `{ "old": ..., "new": ... }`。
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "record_revision")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub record_id: String,
+    /// 引起变更的操作
+    pub operation: OperationType,
+    /// 引起变更的任务，手动操作为空
+    pub task_id: Option<String>,
+    /// 由回滚产生时为被回滚的修订
+    pub rollback_of: Option<i32>,
+    #[sea_orm(column_type = "Json")]
+    pub snapshot: Json,
+    #[sea_orm(column_type = "Json")]
+    pub diff: Json,
+    pub created_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::record_local::Entity",
+        from = "Column::RecordId",
+        to = "super::record_local::Column::Id",
+        on_delete = "Cascade"
+    )]
+    RecordLocal,
+}
+
+impl Related<super::record_local::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecordLocal.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
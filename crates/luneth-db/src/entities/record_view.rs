@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 一次查看记录的事件
+///
+/// 插入后由触发器更新 `record_local` 的查看次数、最近查看时间与 `viewed` 标记。
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "record_view")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub record_id: String,
+    pub viewed_at: ChronoDateTimeUtc,
+    /// 停留时长（秒），离开详情页后补充
+    pub duration_secs: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::record_local::Entity",
+        from = "Column::RecordId",
+        to = "super::record_local::Column::Id",
+        on_delete = "Cascade"
+    )]
+    RecordLocal,
+}
+
+impl Related<super::record_local::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecordLocal.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
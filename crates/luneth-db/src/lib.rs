@@ -1,13 +1,25 @@
 pub mod entities {
+    pub mod attribute;
+    pub mod collection;
+    pub mod collection_record;
     pub mod history_op;
     pub mod history_task;
+    pub mod record_attribute;
     pub mod record_local;
     pub mod record_remote;
+    pub mod record_revision;
+    pub mod record_tag;
+    pub mod record_view;
+    pub mod tag;
 }
 
 mod db;
+mod migration;
 mod types;
 
+#[cfg(test)]
+mod test_util;
+
 // 重新导出主要类型
 pub use db::*;
 pub use types::*;
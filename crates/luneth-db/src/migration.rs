@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+mod m20261018_000001_init_tables;
+mod m20261018_000002_record_local_fts;
+mod m20261018_000003_record_attributes;
+mod m20261018_000004_task_run_tracking;
+mod m20261018_000005_task_retry;
+mod m20261018_000006_history_op_indexes;
+mod m20261018_000007_history_enum_values;
+mod m20261018_000008_record_attribute_upsert;
+mod m20261018_000009_record_local_soft_delete;
+mod m20261018_000010_record_revision;
+mod m20261018_000011_tags_collections;
+mod m20261018_000012_record_rating_note;
+mod m20261018_000013_record_view;
+mod m20261018_000014_record_local_indexes;
+mod m20261018_000015_record_local_fts_by_id;
+
+/// 数据库迁移器，按时间顺序登记所有迁移
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20261018_000001_init_tables::Migration),
+            Box::new(m20261018_000002_record_local_fts::Migration),
+            Box::new(m20261018_000003_record_attributes::Migration),
+            Box::new(m20261018_000004_task_run_tracking::Migration),
+            Box::new(m20261018_000005_task_retry::Migration),
+            Box::new(m20261018_000006_history_op_indexes::Migration),
+            Box::new(m20261018_000007_history_enum_values::Migration),
+            Box::new(m20261018_000008_record_attribute_upsert::Migration),
+            Box::new(m20261018_000009_record_local_soft_delete::Migration),
+            Box::new(m20261018_000010_record_revision::Migration),
+            Box::new(m20261018_000011_tags_collections::Migration),
+            Box::new(m20261018_000012_record_rating_note::Migration),
+            Box::new(m20261018_000013_record_view::Migration),
+            Box::new(m20261018_000014_record_local_indexes::Migration),
+            Box::new(m20261018_000015_record_local_fts_by_id::Migration),
+        ]
+    }
+}
@@ -0,0 +1,296 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 初始表结构
+///
+/// 旧版本通过 `CREATE TABLE IF NOT EXISTS` 建表，这里同样使用 `if_not_exists`，
+/// 使已有数据库可以直接纳入迁移管理。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        create_record_local(manager).await?;
+        create_record_remote(manager).await?;
+        create_history_op(manager).await?;
+        create_history_task(manager).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in [
+            HistoryTask::Table.into_iden(),
+            HistoryOp::Table.into_iden(),
+            RecordRemote::Table.into_iden(),
+            RecordLocal::Table.into_iden(),
+        ] {
+            manager
+                .drop_table(Table::drop().table(table).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+async fn create_record_local(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    manager
+        .create_table(
+            Table::create()
+                .table(RecordLocal::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(RecordLocal::Id)
+                        .string()
+                        .not_null()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(RecordLocal::Title).string().not_null())
+                .col(ColumnDef::new(RecordLocal::ReleaseDate).string().not_null())
+                .col(ColumnDef::new(RecordLocal::Length).string().not_null())
+                .col(ColumnDef::new(RecordLocal::Director).json().not_null())
+                .col(ColumnDef::new(RecordLocal::Studio).json().not_null())
+                .col(ColumnDef::new(RecordLocal::Label).json().not_null())
+                .col(ColumnDef::new(RecordLocal::Series).json().not_null())
+                .col(ColumnDef::new(RecordLocal::Genre).json().not_null())
+                .col(ColumnDef::new(RecordLocal::Idols).json().not_null())
+                .col(
+                    ColumnDef::new(RecordLocal::ShareMagnetLinks)
+                        .json()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(RecordLocal::LocalImageCount)
+                        .integer()
+                        .not_null(),
+                )
+                .col(ColumnDef::new(RecordLocal::Cover).string().not_null())
+                .col(
+                    ColumnDef::new(RecordLocal::SampleImageLinks)
+                        .json()
+                        .not_null(),
+                )
+                .col(ColumnDef::new(RecordLocal::Viewed).boolean().not_null())
+                .col(ColumnDef::new(RecordLocal::IsLiked).boolean().not_null())
+                .col(
+                    ColumnDef::new(RecordLocal::IsSubmitted)
+                        .boolean()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(RecordLocal::IsCachedLocally)
+                        .boolean()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(RecordLocal::CreatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(RecordLocal::UpdatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .to_owned(),
+        )
+        .await
+}
+
+async fn create_record_remote(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    manager
+        .create_table(
+            Table::create()
+                .table(RecordRemote::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(RecordRemote::Id)
+                        .string()
+                        .not_null()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(RecordRemote::Title).string().not_null())
+                .col(ColumnDef::new(RecordRemote::Date).string().not_null())
+                .col(ColumnDef::new(RecordRemote::Duration).integer().not_null())
+                .col(ColumnDef::new(RecordRemote::Director).string().not_null())
+                .col(ColumnDef::new(RecordRemote::Studio).string().not_null())
+                .col(ColumnDef::new(RecordRemote::Label).string().not_null())
+                .col(ColumnDef::new(RecordRemote::Series).string().not_null())
+                .col(ColumnDef::new(RecordRemote::Genres).json().not_null())
+                .col(ColumnDef::new(RecordRemote::Idols).json().not_null())
+                .col(ColumnDef::new(RecordRemote::HasLinks).boolean().not_null())
+                .col(ColumnDef::new(RecordRemote::Links).json().not_null())
+                .col(
+                    ColumnDef::new(RecordRemote::CreatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(RecordRemote::UpdatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .to_owned(),
+        )
+        .await
+}
+
+async fn create_history_op(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    manager
+        .create_table(
+            Table::create()
+                .table(HistoryOp::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(HistoryOp::Id)
+                        .integer()
+                        .not_null()
+                        .auto_increment()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(HistoryOp::RecorderId).string().not_null())
+                .col(ColumnDef::new(HistoryOp::Operation).string().not_null())
+                .col(
+                    ColumnDef::new(HistoryOp::Timestamp)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .col(ColumnDef::new(HistoryOp::Status).string().not_null())
+                .col(ColumnDef::new(HistoryOp::User).string().not_null())
+                .col(ColumnDef::new(HistoryOp::ErrorMessage).string().null())
+                .col(
+                    ColumnDef::new(HistoryOp::CreatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(HistoryOp::UpdatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .to_owned(),
+        )
+        .await
+}
+
+async fn create_history_task(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    manager
+        .create_table(
+            Table::create()
+                .table(HistoryTask::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(HistoryTask::Id)
+                        .string()
+                        .not_null()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(HistoryTask::TaskType).string().not_null())
+                .col(
+                    ColumnDef::new(HistoryTask::StartTime)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(HistoryTask::EndTime)
+                        .timestamp_with_time_zone()
+                        .null(),
+                )
+                .col(ColumnDef::new(HistoryTask::Status).string().not_null())
+                .col(ColumnDef::new(HistoryTask::TargetIds).json().not_null())
+                .col(ColumnDef::new(HistoryTask::FailedIds).json().not_null())
+                .col(ColumnDef::new(HistoryTask::TotalCount).integer().not_null())
+                .col(
+                    ColumnDef::new(HistoryTask::FailedCount)
+                        .integer()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(HistoryTask::CreatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(HistoryTask::UpdatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .to_owned(),
+        )
+        .await
+}
+
+#[derive(DeriveIden)]
+enum RecordLocal {
+    Table,
+    Id,
+    Title,
+    ReleaseDate,
+    Length,
+    Director,
+    Studio,
+    Label,
+    Series,
+    Genre,
+    Idols,
+    ShareMagnetLinks,
+    LocalImageCount,
+    Cover,
+    SampleImageLinks,
+    Viewed,
+    IsLiked,
+    IsSubmitted,
+    IsCachedLocally,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum RecordRemote {
+    Table,
+    Id,
+    Title,
+    Date,
+    Duration,
+    Director,
+    Studio,
+    Label,
+    Series,
+    Genres,
+    Idols,
+    HasLinks,
+    Links,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum HistoryOp {
+    Table,
+    Id,
+    RecorderId,
+    Operation,
+    Timestamp,
+    Status,
+    User,
+    ErrorMessage,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum HistoryTask {
+    Table,
+    Id,
+    TaskType,
+    StartTime,
+    EndTime,
+    Status,
+    TargetIds,
+    FailedIds,
+    TotalCount,
+    FailedCount,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,89 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// `record_local` 的全文索引
+///
+/// 使用 FTS5 trigram 分词，以支持中日韩文本与子串（含前缀）匹配。
+/// 由触发器在增删改时同步（触发器后来改为按 `id` 对应，见 `m20261018_000015`）。
+/// JSON 映射列（演员、类别、片商等）只索引其名称（键）。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const CREATE_FTS_TABLE: &str = "
+CREATE VIRTUAL TABLE IF NOT EXISTS record_local_fts USING fts5(
+    id, title, idols, genre, studio, label, series, director,
+    tokenize = 'trigram'
+)";
+
+const FTS_COLUMNS: &str = "rowid, id, title, idols, genre, studio, label, series, director";
+
+/// 从 `record_local` 的一行中取出索引内容，`{row}` 为行别名
+///
+/// 无法解析的 JSON 列不索引其名称，避免个别损坏的记录使整条语句失败。
+const FTS_VALUES: &str = "
+    {row}.rowid, {row}.id, {row}.title,
+    (SELECT group_concat(key, ' ') FROM json_each(CASE WHEN json_valid({row}.idols) THEN {row}.idols END)),
+    (SELECT group_concat(key, ' ') FROM json_each(CASE WHEN json_valid({row}.genre) THEN {row}.genre END)),
+    (SELECT group_concat(key, ' ') FROM json_each(CASE WHEN json_valid({row}.studio) THEN {row}.studio END)),
+    (SELECT group_concat(key, ' ') FROM json_each(CASE WHEN json_valid({row}.label) THEN {row}.label END)),
+    (SELECT group_concat(key, ' ') FROM json_each(CASE WHEN json_valid({row}.series) THEN {row}.series END)),
+    (SELECT group_concat(key, ' ') FROM json_each(CASE WHEN json_valid({row}.director) THEN {row}.director END))";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let new_values = FTS_VALUES.replace("{row}", "new");
+
+        db.execute_unprepared(CREATE_FTS_TABLE).await?;
+
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER IF NOT EXISTS record_local_fts_ai AFTER INSERT ON record_local BEGIN
+                INSERT INTO record_local_fts ({FTS_COLUMNS}) VALUES ({new_values});
+            END"
+        ))
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE TRIGGER IF NOT EXISTS record_local_fts_ad AFTER DELETE ON record_local BEGIN
+                DELETE FROM record_local_fts WHERE rowid = old.rowid;
+            END",
+        )
+        .await?;
+
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER IF NOT EXISTS record_local_fts_au
+            AFTER UPDATE OF id, title, idols, genre, studio, label, series, director ON record_local
+            BEGIN
+                DELETE FROM record_local_fts WHERE rowid = old.rowid;
+                INSERT INTO record_local_fts ({FTS_COLUMNS}) VALUES ({new_values});
+            END"
+        ))
+        .await?;
+
+        // 为已有记录建立索引
+        db.execute_unprepared(&format!(
+            "INSERT INTO record_local_fts ({FTS_COLUMNS}) SELECT {} FROM record_local",
+            FTS_VALUES.replace("{row}", "record_local")
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for trigger in [
+            "record_local_fts_ai",
+            "record_local_fts_ad",
+            "record_local_fts_au",
+        ] {
+            db.execute_unprepared(&format!("DROP TRIGGER IF EXISTS {trigger}"))
+                .await?;
+        }
+        db.execute_unprepared("DROP TABLE IF EXISTS record_local_fts")
+            .await?;
+        Ok(())
+    }
+}
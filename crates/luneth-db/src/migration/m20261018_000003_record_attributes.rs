@@ -0,0 +1,240 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::Statement;
+
+/// 将 `record_local` 的 JSON 映射列拆分为 `attribute` 与 `record_attribute` 表
+///
+/// 采用单向同步：JSON 列是属性的唯一写入入口，`record_attribute` 是由触发器随
+/// `record_local` 的增改重建的派生数据，不直接写入；删除记录时通过外键级联清理关联。
+/// 拆分已有记录时跳过无法解析的 JSON 列，并在日志中报告跳过的行数。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 属性类别及其在 `record_local` 中的 JSON 列
+const KINDS: [(&str, &str); 6] = [
+    ("IDOL", "idols"),
+    ("GENRE", "genre"),
+    ("STUDIO", "studio"),
+    ("LABEL", "label"),
+    ("SERIES", "series"),
+    ("DIRECTOR", "director"),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        create_attribute(manager).await?;
+        create_record_attribute(manager).await?;
+
+        let db = manager.get_connection();
+        let link_new = link_statements("new");
+
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER IF NOT EXISTS record_local_attribute_ai AFTER INSERT ON record_local
+            BEGIN
+                {link_new}
+            END"
+        ))
+        .await?;
+
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER IF NOT EXISTS record_local_attribute_au
+            AFTER UPDATE OF idols, genre, studio, label, series, director ON record_local
+            BEGIN
+                DELETE FROM record_attribute WHERE record_id = old.id;
+                {link_new}
+            END"
+        ))
+        .await?;
+
+        // 拆分已有记录
+        for (kind, column) in KINDS {
+            let skipped = count_invalid_json(db, column).await?;
+            if skipped > 0 {
+                log::warn!(
+                    "Skipped {skipped} record_local rows with malformed {column} JSON \
+                     while splitting attributes"
+                );
+            }
+
+            let valid = format!(
+                "CASE WHEN json_valid(record_local.{column}) THEN record_local.{column} END"
+            );
+            db.execute_unprepared(&format!(
+                "INSERT OR IGNORE INTO attribute (kind, name, link)
+                SELECT '{kind}', j.key, COALESCE(j.value, '')
+                FROM record_local, json_each({valid}) AS j"
+            ))
+            .await?;
+            db.execute_unprepared(&format!(
+                "INSERT OR IGNORE INTO record_attribute (record_id, attribute_id)
+                SELECT record_local.id, attribute.id
+                FROM record_local, json_each({valid}) AS j
+                JOIN attribute ON attribute.kind = '{kind}' AND attribute.name = j.key"
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for trigger in ["record_local_attribute_ai", "record_local_attribute_au"] {
+            db.execute_unprepared(&format!("DROP TRIGGER IF EXISTS {trigger}"))
+                .await?;
+        }
+        for table in [
+            RecordAttribute::Table.into_iden(),
+            Attribute::Table.into_iden(),
+        ] {
+            manager
+                .drop_table(Table::drop().table(table).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// `record_local` 中该列不是合法 JSON 的行数
+async fn count_invalid_json(db: &SchemaManagerConnection<'_>, column: &str) -> Result<u64, DbErr> {
+    let stmt = Statement::from_string(
+        db.get_database_backend(),
+        format!("SELECT COUNT(*) AS cnt FROM record_local WHERE NOT json_valid({column})"),
+    );
+    let count = db
+        .query_one(stmt)
+        .await?
+        .map(|row| row.try_get::<i64>("", "cnt"))
+        .transpose()?
+        .unwrap_or_default();
+    Ok(count.try_into().unwrap_or_default())
+}
+
+/// 为 `record_local` 的一行建立属性与关联，`row` 为行别名
+fn link_statements(row: &str) -> String {
+    KINDS
+        .iter()
+        .map(|(kind, column)| {
+            format!(
+                "INSERT OR IGNORE INTO attribute (kind, name, link)
+                    SELECT '{kind}', key, COALESCE(value, '') FROM json_each({row}.{column});
+                INSERT OR IGNORE INTO record_attribute (record_id, attribute_id)
+                    SELECT {row}.id, attribute.id FROM json_each({row}.{column})
+                    JOIN attribute ON attribute.kind = '{kind}' AND attribute.name = json_each.key;"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+async fn create_attribute(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    manager
+        .create_table(
+            Table::create()
+                .table(Attribute::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(Attribute::Id)
+                        .integer()
+                        .not_null()
+                        .auto_increment()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(Attribute::Kind).string().not_null())
+                .col(ColumnDef::new(Attribute::Name).string().not_null())
+                .col(
+                    ColumnDef::new(Attribute::Link)
+                        .string()
+                        .not_null()
+                        .default(""),
+                )
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .create_index(
+            Index::create()
+                .name("idx_attribute_kind_name")
+                .table(Attribute::Table)
+                .col(Attribute::Kind)
+                .col(Attribute::Name)
+                .unique()
+                .if_not_exists()
+                .to_owned(),
+        )
+        .await
+}
+
+async fn create_record_attribute(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    manager
+        .create_table(
+            Table::create()
+                .table(RecordAttribute::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(RecordAttribute::RecordId)
+                        .string()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(RecordAttribute::AttributeId)
+                        .integer()
+                        .not_null(),
+                )
+                .primary_key(
+                    Index::create()
+                        .col(RecordAttribute::RecordId)
+                        .col(RecordAttribute::AttributeId),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(RecordAttribute::Table, RecordAttribute::RecordId)
+                        .to(RecordLocal::Table, RecordLocal::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(RecordAttribute::Table, RecordAttribute::AttributeId)
+                        .to(Attribute::Table, Attribute::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .create_index(
+            Index::create()
+                .name("idx_record_attribute_attribute_id")
+                .table(RecordAttribute::Table)
+                .col(RecordAttribute::AttributeId)
+                .if_not_exists()
+                .to_owned(),
+        )
+        .await
+}
+
+#[derive(DeriveIden)]
+enum RecordLocal {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Attribute {
+    Table,
+    Id,
+    Kind,
+    Name,
+    Link,
+}
+
+#[derive(DeriveIden)]
+enum RecordAttribute {
+    Table,
+    RecordId,
+    AttributeId,
+}
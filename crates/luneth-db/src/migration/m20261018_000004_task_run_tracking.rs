@@ -0,0 +1,85 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 记录任务运行的逐项结果
+///
+/// `history_op.task_id` 关联产生该操作的任务（手动操作为空），
+/// `history_task.error_message` 保存任务中止的原因。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HistoryOp::Table)
+                    .add_column(ColumnDef::new(HistoryOp::TaskId).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_history_op_task_id")
+                    .table(HistoryOp::Table)
+                    .col(HistoryOp::TaskId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HistoryTask::Table)
+                    .add_column(ColumnDef::new(HistoryTask::ErrorMessage).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_history_op_task_id")
+                    .table(HistoryOp::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HistoryOp::Table)
+                    .drop_column(HistoryOp::TaskId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HistoryTask::Table)
+                    .drop_column(HistoryTask::ErrorMessage)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum HistoryOp {
+    Table,
+    TaskId,
+}
+
+#[derive(DeriveIden)]
+enum HistoryTask {
+    Table,
+    ErrorMessage,
+}
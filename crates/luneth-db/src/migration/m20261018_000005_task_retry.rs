@@ -0,0 +1,73 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 支持重试任务运行中失败的目标
+///
+/// `history_task.parent_id` 指向被重试的任务，`history_task.config` 保存重建任务所需的配置。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HistoryTask::Table)
+                    .add_column(ColumnDef::new(HistoryTask::ParentId).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HistoryTask::Table)
+                    .add_column(ColumnDef::new(HistoryTask::Config).json().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_history_task_parent_id")
+                    .table(HistoryTask::Table)
+                    .col(HistoryTask::ParentId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_history_task_parent_id")
+                    .table(HistoryTask::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [HistoryTask::ParentId, HistoryTask::Config] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(HistoryTask::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum HistoryTask {
+    Table,
+    ParentId,
+    Config,
+}
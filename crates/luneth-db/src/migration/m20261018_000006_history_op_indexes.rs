@@ -0,0 +1,47 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 为操作历史的分页、按编号过滤与按天统计建立索引
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const INDEXES: [(&str, HistoryOp); 2] = [
+    ("idx_history_op_timestamp", HistoryOp::Timestamp),
+    ("idx_history_op_recorder_id", HistoryOp::RecorderId),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (name, column) in INDEXES {
+            manager
+                .create_index(
+                    Index::create()
+                        .name(name)
+                        .table(HistoryOp::Table)
+                        .col(column)
+                        .if_not_exists()
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (name, _) in INDEXES {
+            manager
+                .drop_index(Index::drop().name(name).table(HistoryOp::Table).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, DeriveIden)]
+enum HistoryOp {
+    Table,
+    Timestamp,
+    RecorderId,
+}
@@ -0,0 +1,111 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 规范化操作历史与任务记录中的枚举列，并在写入时拒绝未知取值
+///
+/// 已有取值统一为去空白、大写、下划线分隔的形式（`crawlRecord`、`crawl-record`
+/// 均变为 `CRAWL_RECORD`）。仍无法识别的取值记为 `UNKNOWN`，不删除任何历史，
+/// 也不会把未知状态当作失败重试。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const OPERATIONS: &[&str] = &[
+    "CRAWL_RECORD",
+    "CRAWL_PAGE",
+    "CRAWL_IDOL",
+    "VIEWED",
+    "LIKED",
+    "UNLIKED",
+    "SUBMIT",
+    "CREATE",
+    "UPDATE",
+    "DELETE",
+    "UNKNOWN",
+];
+const OPERATION_STATUSES: &[&str] = &["SUCCESS", "FAILED", "UNKNOWN"];
+const TASK_TYPES: &[&str] = &[
+    "CRAWL", "AUTO", "SUBMIT", "UPDATE", "IDOL", "PULL", "UNKNOWN",
+];
+const TASK_STATUSES: &[&str] = &["SUCCESS", "ABORTED", "PENDING", "FAILED", "UNKNOWN"];
+
+/// 表、列与允许的取值
+const COLUMNS: [(&str, &str, &[&str]); 4] = [
+    ("history_op", "operation", OPERATIONS),
+    ("history_op", "status", OPERATION_STATUSES),
+    ("history_task", "task_type", TASK_TYPES),
+    ("history_task", "status", TASK_STATUSES),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        for (table, column, values) in COLUMNS {
+            let allowed = value_list(values);
+
+            db.execute_unprepared(&format!(
+                "UPDATE {table} SET {column} = {normalized} WHERE {column} NOT IN ({allowed})",
+                normalized = normalize_sql(column, values),
+            ))
+            .await?;
+
+            db.execute_unprepared(&format!(
+                "UPDATE {table} SET {column} = 'UNKNOWN' WHERE {column} NOT IN ({allowed})"
+            ))
+            .await?;
+
+            for (suffix, event) in [
+                ("bi", "INSERT".to_owned()),
+                ("bu", format!("UPDATE OF {column}")),
+            ] {
+                db.execute_unprepared(&format!(
+                    "CREATE TRIGGER IF NOT EXISTS {table}_{column}_{suffix}
+                    BEFORE {event} ON {table}
+                    WHEN new.{column} NOT IN ({allowed})
+                    BEGIN
+                        SELECT RAISE(ABORT, 'invalid {table}.{column}');
+                    END"
+                ))
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for (table, column, _) in COLUMNS {
+            for suffix in ["bi", "bu"] {
+                db.execute_unprepared(&format!("DROP TRIGGER IF EXISTS {table}_{column}_{suffix}"))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn value_list(values: &[&str]) -> String {
+    values
+        .iter()
+        .map(|value| format!("'{value}'"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 将列值规范化的 SQL 表达式
+///
+/// 去除空白并统一分隔符后比较不含下划线的大写形式，以同时识别
+/// `CRAWL_RECORD`、`CrawlRecord` 与 `crawl-record`。
+fn normalize_sql(column: &str, values: &[&str]) -> String {
+    let key =
+        format!("REPLACE(REPLACE(REPLACE(UPPER(TRIM({column})), '_', ''), '-', ''), ' ', '')");
+    let cases = values
+        .iter()
+        .map(|value| format!("WHEN '{}' THEN '{value}'", value.replace('_', "")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("CASE {key} {cases} ELSE {column} END")
+}
@@ -0,0 +1,117 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 重建 `record_local` 的属性同步触发器，使其可由 `INSERT ... ON CONFLICT DO UPDATE` 触发
+///
+/// 触发器内的 `INSERT OR IGNORE` 会被外层语句的冲突处理方式覆盖，由 upsert 触发时
+/// 遇到已存在的属性会直接报错，因此改为先判断是否存在再插入。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 属性类别及其在 `record_local` 中的 JSON 列
+const KINDS: [(&str, &str); 6] = [
+    ("IDOL", "idols"),
+    ("GENRE", "genre"),
+    ("STUDIO", "studio"),
+    ("LABEL", "label"),
+    ("SERIES", "series"),
+    ("DIRECTOR", "director"),
+];
+
+const TRIGGERS: [&str; 2] = ["record_local_attribute_ai", "record_local_attribute_au"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for trigger in TRIGGERS {
+            db.execute_unprepared(&format!("DROP TRIGGER IF EXISTS {trigger}"))
+                .await?;
+        }
+
+        let link_new = link_statements("new");
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER record_local_attribute_ai AFTER INSERT ON record_local
+            BEGIN
+                {link_new}
+            END"
+        ))
+        .await?;
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER record_local_attribute_au
+            AFTER UPDATE OF idols, genre, studio, label, series, director ON record_local
+            BEGIN
+                DELETE FROM record_attribute WHERE record_id = old.id;
+                {link_new}
+            END"
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for trigger in TRIGGERS {
+            db.execute_unprepared(&format!("DROP TRIGGER IF EXISTS {trigger}"))
+                .await?;
+        }
+
+        // 恢复为 `INSERT OR IGNORE` 形式
+        let link_new = KINDS
+            .iter()
+            .map(|(kind, column)| {
+                format!(
+                    "INSERT OR IGNORE INTO attribute (kind, name, link)
+                        SELECT '{kind}', key, COALESCE(value, '') FROM json_each(new.{column});
+                    INSERT OR IGNORE INTO record_attribute (record_id, attribute_id)
+                        SELECT new.id, attribute.id FROM json_each(new.{column})
+                        JOIN attribute ON attribute.kind = '{kind}' AND attribute.name = json_each.key;"
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER record_local_attribute_ai AFTER INSERT ON record_local
+            BEGIN
+                {link_new}
+            END"
+        ))
+        .await?;
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER record_local_attribute_au
+            AFTER UPDATE OF idols, genre, studio, label, series, director ON record_local
+            BEGIN
+                DELETE FROM record_attribute WHERE record_id = old.id;
+                {link_new}
+            END"
+        ))
+        .await?;
+        Ok(())
+    }
+}
+
+/// 为 `record_local` 的一行建立属性与关联，`row` 为行别名
+fn link_statements(row: &str) -> String {
+    KINDS
+        .iter()
+        .map(|(kind, column)| {
+            format!(
+                "INSERT INTO attribute (kind, name, link)
+                    SELECT '{kind}', j.key, COALESCE(j.value, '') FROM json_each({row}.{column}) AS j
+                    WHERE NOT EXISTS (
+                        SELECT 1 FROM attribute WHERE attribute.kind = '{kind}' AND attribute.name = j.key
+                    );
+                INSERT INTO record_attribute (record_id, attribute_id)
+                    SELECT {row}.id, attribute.id FROM json_each({row}.{column}) AS j
+                    JOIN attribute ON attribute.kind = '{kind}' AND attribute.name = j.key
+                    WHERE NOT EXISTS (
+                        SELECT 1 FROM record_attribute
+                        WHERE record_attribute.record_id = {row}.id
+                        AND record_attribute.attribute_id = attribute.id
+                    );"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,64 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 为 `record_local` 增加回收站
+///
+/// `deleted_at` 非空的记录视为已移入回收站，不出现在列表、搜索与计数中。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RecordLocal::Table)
+                    .add_column(
+                        ColumnDef::new(RecordLocal::DeletedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_record_local_deleted_at")
+                    .table(RecordLocal::Table)
+                    .col(RecordLocal::DeletedAt)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_record_local_deleted_at")
+                    .table(RecordLocal::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RecordLocal::Table)
+                    .drop_column(RecordLocal::DeletedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RecordLocal {
+    Table,
+    DeletedAt,
+}
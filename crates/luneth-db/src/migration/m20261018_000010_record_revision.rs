@@ -0,0 +1,85 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 记录本地记录元数据的变更历史
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecordRevision::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RecordRevision::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RecordRevision::RecordId).string().not_null())
+                    .col(
+                        ColumnDef::new(RecordRevision::Operation)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RecordRevision::TaskId).string().null())
+                    .col(ColumnDef::new(RecordRevision::RollbackOf).integer().null())
+                    .col(ColumnDef::new(RecordRevision::Snapshot).json().not_null())
+                    .col(ColumnDef::new(RecordRevision::Diff).json().not_null())
+                    .col(
+                        ColumnDef::new(RecordRevision::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RecordRevision::Table, RecordRevision::RecordId)
+                            .to(RecordLocal::Table, RecordLocal::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_record_revision_record_id")
+                    .table(RecordRevision::Table)
+                    .col(RecordRevision::RecordId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RecordRevision::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RecordRevision {
+    Table,
+    Id,
+    RecordId,
+    Operation,
+    TaskId,
+    RollbackOf,
+    Snapshot,
+    Diff,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum RecordLocal {
+    Table,
+    Id,
+}
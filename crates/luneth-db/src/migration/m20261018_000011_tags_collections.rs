@@ -0,0 +1,246 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 用户自定义标签与手动列表，均以关系表多对多关联 `record_local`
+///
+/// 删除记录、标签或列表时通过外键级联清理关联。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        create_tag(manager).await?;
+        create_collection(manager).await?;
+        create_collection_record(manager).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in [
+            CollectionRecord::Table.into_iden(),
+            Collection::Table.into_iden(),
+            RecordTag::Table.into_iden(),
+            Tag::Table.into_iden(),
+        ] {
+            manager
+                .drop_table(Table::drop().table(table).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+async fn create_tag(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    manager
+        .create_table(
+            Table::create()
+                .table(Tag::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(Tag::Id)
+                        .integer()
+                        .not_null()
+                        .auto_increment()
+                        .primary_key(),
+                )
+                .col(ColumnDef::new(Tag::Name).string().not_null().unique_key())
+                .col(
+                    ColumnDef::new(Tag::CreatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .create_table(
+            Table::create()
+                .table(RecordTag::Table)
+                .if_not_exists()
+                .col(ColumnDef::new(RecordTag::RecordId).string().not_null())
+                .col(ColumnDef::new(RecordTag::TagId).integer().not_null())
+                .primary_key(
+                    Index::create()
+                        .col(RecordTag::RecordId)
+                        .col(RecordTag::TagId),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(RecordTag::Table, RecordTag::RecordId)
+                        .to(RecordLocal::Table, RecordLocal::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(RecordTag::Table, RecordTag::TagId)
+                        .to(Tag::Table, Tag::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .create_index(
+            Index::create()
+                .name("idx_record_tag_tag_id")
+                .table(RecordTag::Table)
+                .col(RecordTag::TagId)
+                .if_not_exists()
+                .to_owned(),
+        )
+        .await
+}
+
+async fn create_collection(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    manager
+        .create_table(
+            Table::create()
+                .table(Collection::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(Collection::Id)
+                        .integer()
+                        .not_null()
+                        .auto_increment()
+                        .primary_key(),
+                )
+                .col(
+                    ColumnDef::new(Collection::Name)
+                        .string()
+                        .not_null()
+                        .unique_key(),
+                )
+                .col(
+                    ColumnDef::new(Collection::Description)
+                        .string()
+                        .not_null()
+                        .default(""),
+                )
+                .col(
+                    ColumnDef::new(Collection::CreatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(Collection::UpdatedAt)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .to_owned(),
+        )
+        .await
+}
+
+async fn create_collection_record(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    manager
+        .create_table(
+            Table::create()
+                .table(CollectionRecord::Table)
+                .if_not_exists()
+                .col(
+                    ColumnDef::new(CollectionRecord::CollectionId)
+                        .integer()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(CollectionRecord::RecordId)
+                        .string()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(CollectionRecord::Position)
+                        .integer()
+                        .not_null(),
+                )
+                .col(
+                    ColumnDef::new(CollectionRecord::AddedAt)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .primary_key(
+                    Index::create()
+                        .col(CollectionRecord::CollectionId)
+                        .col(CollectionRecord::RecordId),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(CollectionRecord::Table, CollectionRecord::CollectionId)
+                        .to(Collection::Table, Collection::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(CollectionRecord::Table, CollectionRecord::RecordId)
+                        .to(RecordLocal::Table, RecordLocal::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .create_index(
+            Index::create()
+                .name("idx_collection_record_position")
+                .table(CollectionRecord::Table)
+                .col(CollectionRecord::CollectionId)
+                .col(CollectionRecord::Position)
+                .if_not_exists()
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .create_index(
+            Index::create()
+                .name("idx_collection_record_record_id")
+                .table(CollectionRecord::Table)
+                .col(CollectionRecord::RecordId)
+                .if_not_exists()
+                .to_owned(),
+        )
+        .await
+}
+
+#[derive(DeriveIden)]
+enum RecordLocal {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Tag {
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum RecordTag {
+    Table,
+    RecordId,
+    TagId,
+}
+
+#[derive(DeriveIden)]
+enum Collection {
+    Table,
+    Id,
+    Name,
+    Description,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum CollectionRecord {
+    Table,
+    CollectionId,
+    RecordId,
+    Position,
+    AddedAt,
+}
@@ -0,0 +1,125 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 为 `record_local` 增加评分与私人备注，并允许在操作历史中记录这两种操作
+///
+/// 评分以 0 表示未评分，保持非空以便参与排序与游标分页。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 本迁移前允许的操作类型
+const OPERATIONS: &[&str] = &[
+    "CRAWL_RECORD",
+    "CRAWL_PAGE",
+    "CRAWL_IDOL",
+    "VIEWED",
+    "LIKED",
+    "UNLIKED",
+    "SUBMIT",
+    "CREATE",
+    "UPDATE",
+    "DELETE",
+    "UNKNOWN",
+];
+const NEW_OPERATIONS: &[&str] = &["RATED", "NOTED"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 每条 ALTER TABLE 只能增加一列
+        for column in [
+            ColumnDef::new(RecordLocal::Rating)
+                .integer()
+                .not_null()
+                .default(0)
+                .check(Expr::col(RecordLocal::Rating).between(0, 5))
+                .to_owned(),
+            ColumnDef::new(RecordLocal::RatedAt)
+                .timestamp_with_time_zone()
+                .null()
+                .to_owned(),
+            ColumnDef::new(RecordLocal::Note)
+                .text()
+                .not_null()
+                .default("")
+                .to_owned(),
+            ColumnDef::new(RecordLocal::NoteUpdatedAt)
+                .timestamp_with_time_zone()
+                .null()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(RecordLocal::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        let operations = [OPERATIONS, NEW_OPERATIONS].concat();
+        replace_operation_triggers(manager, &operations).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        replace_operation_triggers(manager, OPERATIONS).await?;
+
+        for column in [
+            RecordLocal::NoteUpdatedAt,
+            RecordLocal::Note,
+            RecordLocal::RatedAt,
+            RecordLocal::Rating,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(RecordLocal::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// 以新的取值列表重建 `history_op.operation` 的校验触发器
+async fn replace_operation_triggers(
+    manager: &SchemaManager<'_>,
+    operations: &[&str],
+) -> Result<(), DbErr> {
+    let db = manager.get_connection();
+    let allowed = operations
+        .iter()
+        .map(|value| format!("'{value}'"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    for (suffix, event) in [("bi", "INSERT"), ("bu", "UPDATE OF operation")] {
+        db.execute_unprepared(&format!(
+            "DROP TRIGGER IF EXISTS history_op_operation_{suffix}"
+        ))
+        .await?;
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER history_op_operation_{suffix}
+            BEFORE {event} ON history_op
+            WHEN new.operation NOT IN ({allowed})
+            BEGIN
+                SELECT RAISE(ABORT, 'invalid history_op.operation');
+            END"
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+#[derive(DeriveIden)]
+enum RecordLocal {
+    Table,
+    Rating,
+    RatedAt,
+    Note,
+    NoteUpdatedAt,
+}
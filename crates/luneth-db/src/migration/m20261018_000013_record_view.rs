@@ -0,0 +1,144 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 以查看事件记录每次查看，`record_local` 保留累计次数与最近查看时间
+///
+/// `viewed` 改为由触发器维护的派生标记；已标记为已查看的记录计为查看过一次，
+/// 查看时间未知。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecordView::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RecordView::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RecordView::RecordId).string().not_null())
+                    .col(
+                        ColumnDef::new(RecordView::ViewedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RecordView::DurationSecs).integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RecordView::Table, RecordView::RecordId)
+                            .to(RecordLocal::Table, RecordLocal::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_record_view_record_id")
+                    .table(RecordView::Table)
+                    .col(RecordView::RecordId)
+                    .col(RecordView::ViewedAt)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_record_view_viewed_at")
+                    .table(RecordView::Table)
+                    .col(RecordView::ViewedAt)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        // SQLite 每条 ALTER TABLE 只能增加一列
+        for column in [
+            ColumnDef::new(RecordLocal::ViewCount)
+                .integer()
+                .not_null()
+                .default(0)
+                .to_owned(),
+            ColumnDef::new(RecordLocal::LastViewedAt)
+                .timestamp_with_time_zone()
+                .null()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(RecordLocal::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        let db = manager.get_connection();
+        db.execute_unprepared("UPDATE record_local SET view_count = 1 WHERE viewed = 1")
+            .await?;
+        db.execute_unprepared(
+            "CREATE TRIGGER IF NOT EXISTS record_view_ai AFTER INSERT ON record_view
+            BEGIN
+                UPDATE record_local
+                SET viewed = 1,
+                    view_count = view_count + 1,
+                    last_viewed_at = new.viewed_at
+                WHERE id = new.record_id;
+            END",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DROP TRIGGER IF EXISTS record_view_ai")
+            .await?;
+
+        for column in [RecordLocal::LastViewedAt, RecordLocal::ViewCount] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(RecordLocal::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_table(Table::drop().table(RecordView::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RecordView {
+    Table,
+    Id,
+    RecordId,
+    ViewedAt,
+    DurationSecs,
+}
+
+#[derive(DeriveIden)]
+enum RecordLocal {
+    Table,
+    Id,
+    ViewCount,
+    LastViewedAt,
+}
@@ -0,0 +1,139 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 为 `record_local` 的过滤与排序列建立索引
+///
+/// 列表查询总是带有 `deleted_at IS NULL`：排序索引以其开头、以主键结尾，与分页排序的
+/// 次序一致，避免全表扫描与临时排序；布尔列索引附带 `deleted_at`，计数时无需回表。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const INDEXES: [(&str, &[RecordLocal]); 12] = [
+    (
+        "idx_record_local_viewed",
+        &[RecordLocal::Viewed, RecordLocal::DeletedAt],
+    ),
+    (
+        "idx_record_local_is_liked",
+        &[RecordLocal::IsLiked, RecordLocal::DeletedAt],
+    ),
+    (
+        "idx_record_local_is_submitted",
+        &[RecordLocal::IsSubmitted, RecordLocal::DeletedAt],
+    ),
+    (
+        "idx_record_local_is_cached_locally",
+        &[RecordLocal::IsCachedLocally, RecordLocal::DeletedAt],
+    ),
+    (
+        "idx_record_local_release_date",
+        &[
+            RecordLocal::DeletedAt,
+            RecordLocal::ReleaseDate,
+            RecordLocal::Id,
+        ],
+    ),
+    (
+        "idx_record_local_created_at",
+        &[
+            RecordLocal::DeletedAt,
+            RecordLocal::CreatedAt,
+            RecordLocal::Id,
+        ],
+    ),
+    (
+        "idx_record_local_updated_at",
+        &[
+            RecordLocal::DeletedAt,
+            RecordLocal::UpdatedAt,
+            RecordLocal::Id,
+        ],
+    ),
+    (
+        "idx_record_local_title",
+        &[RecordLocal::DeletedAt, RecordLocal::Title, RecordLocal::Id],
+    ),
+    (
+        "idx_record_local_local_image_count",
+        &[
+            RecordLocal::DeletedAt,
+            RecordLocal::LocalImageCount,
+            RecordLocal::Id,
+        ],
+    ),
+    (
+        "idx_record_local_rating",
+        &[RecordLocal::DeletedAt, RecordLocal::Rating, RecordLocal::Id],
+    ),
+    (
+        "idx_record_local_view_count",
+        &[
+            RecordLocal::DeletedAt,
+            RecordLocal::ViewCount,
+            RecordLocal::Id,
+        ],
+    ),
+    (
+        "idx_record_local_last_viewed_at",
+        &[
+            RecordLocal::DeletedAt,
+            RecordLocal::LastViewedAt,
+            RecordLocal::Id,
+        ],
+    ),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (name, columns) in INDEXES {
+            let mut index = Index::create();
+            index.name(name).table(RecordLocal::Table).if_not_exists();
+            for &column in columns {
+                index.col(column);
+            }
+            manager.create_index(index).await?;
+        }
+
+        // 刷新统计信息，让查询计划在布尔列与排序列的索引之间正确取舍
+        manager
+            .get_connection()
+            .execute_unprepared("ANALYZE record_local")
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (name, _) in INDEXES {
+            manager
+                .drop_index(
+                    Index::drop()
+                        .name(name)
+                        .table(RecordLocal::Table)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, DeriveIden)]
+enum RecordLocal {
+    Table,
+    Id,
+    Title,
+    ReleaseDate,
+    LocalImageCount,
+    Viewed,
+    IsLiked,
+    IsSubmitted,
+    IsCachedLocally,
+    Rating,
+    ViewCount,
+    LastViewedAt,
+    CreatedAt,
+    UpdatedAt,
+    DeletedAt,
+}
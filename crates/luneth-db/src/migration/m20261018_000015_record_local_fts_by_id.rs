@@ -0,0 +1,131 @@
+#![expect(elided_lifetimes_in_paths)]
+
+use sea_orm_migration::prelude::*;
+
+/// 全文索引改为按记录编号与 `record_local` 对应
+///
+/// `record_local` 以文本列为主键，`VACUUM` 可能重新分配其 `rowid`，此后按 `rowid`
+/// 同步的触发器会删错索引行。触发器改为按 `id` 删除旧索引行，并重建全部索引。
+/// 编号不少于三个字符时先经 trigram 索引定位，避免逐行扫描。
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const FTS_COLUMNS: &str = "id, title, idols, genre, studio, label, series, director";
+
+/// 从 `record_local` 的一行中取出索引内容，`{row}` 为行别名
+///
+/// 无法解析的 JSON 列不索引其名称，避免个别损坏的记录使整条语句失败。
+const FTS_VALUES: &str = "
+    {row}.id, {row}.title,
+    (SELECT group_concat(key, ' ') FROM json_each(CASE WHEN json_valid({row}.idols) THEN {row}.idols END)),
+    (SELECT group_concat(key, ' ') FROM json_each(CASE WHEN json_valid({row}.genre) THEN {row}.genre END)),
+    (SELECT group_concat(key, ' ') FROM json_each(CASE WHEN json_valid({row}.studio) THEN {row}.studio END)),
+    (SELECT group_concat(key, ' ') FROM json_each(CASE WHEN json_valid({row}.label) THEN {row}.label END)),
+    (SELECT group_concat(key, ' ') FROM json_each(CASE WHEN json_valid({row}.series) THEN {row}.series END)),
+    (SELECT group_concat(key, ' ') FROM json_each(CASE WHEN json_valid({row}.director) THEN {row}.director END))";
+
+/// 删除 `old.id` 对应的索引行
+///
+/// trigram 无法匹配不足三个字符的短语，此时回退为逐行比较。
+const DELETE_OLD: &str = "
+    DELETE FROM record_local_fts
+    WHERE length(old.id) >= 3
+        AND record_local_fts MATCH 'id:\"' || replace(old.id, '\"', '\"\"') || '\"'
+        AND id = old.id;
+    DELETE FROM record_local_fts WHERE length(old.id) < 3 AND id = old.id;";
+
+const TRIGGERS: [&str; 3] = [
+    "record_local_fts_ai",
+    "record_local_fts_ad",
+    "record_local_fts_au",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let new_values = FTS_VALUES.replace("{row}", "new");
+
+        for trigger in TRIGGERS {
+            db.execute_unprepared(&format!("DROP TRIGGER IF EXISTS {trigger}"))
+                .await?;
+        }
+
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER record_local_fts_ai AFTER INSERT ON record_local BEGIN
+                INSERT INTO record_local_fts ({FTS_COLUMNS}) VALUES ({new_values});
+            END"
+        ))
+        .await?;
+
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER record_local_fts_ad AFTER DELETE ON record_local BEGIN
+                {DELETE_OLD}
+            END"
+        ))
+        .await?;
+
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER record_local_fts_au
+            AFTER UPDATE OF id, title, idols, genre, studio, label, series, director ON record_local
+            BEGIN
+                {DELETE_OLD}
+                INSERT INTO record_local_fts ({FTS_COLUMNS}) VALUES ({new_values});
+            END"
+        ))
+        .await?;
+
+        // 之前按 rowid 同步的索引可能已与记录错位，全部重建
+        db.execute_unprepared("DELETE FROM record_local_fts")
+            .await?;
+        db.execute_unprepared(&format!(
+            "INSERT INTO record_local_fts ({FTS_COLUMNS}) SELECT {} FROM record_local",
+            FTS_VALUES.replace("{row}", "record_local")
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let new_values = FTS_VALUES.replace("{row}", "new");
+
+        for trigger in TRIGGERS {
+            db.execute_unprepared(&format!("DROP TRIGGER IF EXISTS {trigger}"))
+                .await?;
+        }
+
+        // 恢复按 rowid 同步的触发器与索引
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER record_local_fts_ai AFTER INSERT ON record_local BEGIN
+                INSERT INTO record_local_fts (rowid, {FTS_COLUMNS}) VALUES (new.rowid, {new_values});
+            END"
+        ))
+        .await?;
+        db.execute_unprepared(
+            "CREATE TRIGGER record_local_fts_ad AFTER DELETE ON record_local BEGIN
+                DELETE FROM record_local_fts WHERE rowid = old.rowid;
+            END",
+        )
+        .await?;
+        db.execute_unprepared(&format!(
+            "CREATE TRIGGER record_local_fts_au
+            AFTER UPDATE OF id, title, idols, genre, studio, label, series, director ON record_local
+            BEGIN
+                DELETE FROM record_local_fts WHERE rowid = old.rowid;
+                INSERT INTO record_local_fts (rowid, {FTS_COLUMNS}) VALUES (new.rowid, {new_values});
+            END"
+        ))
+        .await?;
+
+        db.execute_unprepared("DELETE FROM record_local_fts")
+            .await?;
+        db.execute_unprepared(&format!(
+            "INSERT INTO record_local_fts (rowid, {FTS_COLUMNS}) SELECT record_local.rowid, {} FROM record_local",
+            FTS_VALUES.replace("{row}", "record_local")
+        ))
+        .await?;
+        Ok(())
+    }
+}
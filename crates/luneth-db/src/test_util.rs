@@ -0,0 +1,65 @@
+//! 单元测试共用的辅助工具
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sea_orm::{ActiveModelBehavior as _, Set};
+
+use crate::DbOperator;
+use crate::entities::record_local;
+
+/// 打开已执行全部迁移的内存数据库
+pub(crate) async fn memory_db() -> DbOperator {
+    DbOperator::builder()
+        .open_in_memory()
+        .await
+        .expect("open in-memory db")
+}
+
+/// 构建一条只填写必填列的本地记录
+pub(crate) fn record(id: &str) -> record_local::ActiveModel {
+    let mut record = record_local::ActiveModel::new();
+    record.id = Set(id.to_owned());
+    record.title = Set(format!("Title {id}"));
+    record.release_date = Set("2020-01-01".to_owned());
+    record.length = Set("120分钟".to_owned());
+    record.cover = Set(String::new());
+    record
+}
+
+/// `{名称: 链接}` 形式的属性列
+pub(crate) fn attribute(names: &[&str]) -> serde_json::Value {
+    names
+        .iter()
+        .map(|name| ((*name).to_owned(), serde_json::Value::String(String::new())))
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// 测试结束时自动删除的临时目录
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "luneth-db-{name}-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).expect("create temp dir");
+        Self(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            eprintln!("Failed to remove {}: {e}", self.0.display());
+        }
+    }
+}
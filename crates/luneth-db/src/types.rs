@@ -1,4 +1,4 @@
-use sea_orm::DbErr;
+use sea_orm::{ActiveEnum as _, DbErr, DeriveActiveEnum, EnumIter, sea_query::StringLen};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, thiserror::Error)]
@@ -9,14 +9,117 @@ pub enum DbError {
     CreateFailed(String),
     #[error("Database operation failed: {0}")]
     OperationFailed(String),
+    #[error("Unique constraint violated on {target}: {}", key.as_deref().unwrap_or("unknown key"))]
+    UniqueViolation { target: String, key: Option<String> },
+    #[error("Foreign key constraint violated: {0}")]
+    ForeignKeyViolation(String),
+    #[error("Check constraint violated: {0}")]
+    CheckViolation(String),
+    #[error("Record not found: {0}")]
+    NotFound(String),
+    #[error("Database is busy: {0}")]
+    Busy(String),
+    #[error("Transaction serialization failure: {0}")]
+    SerializationFailure(String),
     #[error("Serialization error: {0}")]
     SerializationError(String),
+    #[error("Database migration failed: {0}")]
+    MigrationFailed(String),
+    #[error("Database schema is newer than this application, unknown migration: {0}")]
+    SchemaTooNew(String),
+    #[error("Database backup failed: {0}")]
+    BackupFailed(String),
+    #[error("Record transfer failed: {0}")]
+    TransferFailed(String),
+    #[error("Invalid page cursor: {0}")]
+    InvalidCursor(String),
+    #[error("Record is in the trash: {0}")]
+    RecordTrashed(String),
+    #[cfg(feature = "tauri")]
     #[error("Tauri error: {0}")]
     TauriError(String),
 }
 
+impl DbError {
+    /// 供前端识别的错误代码
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ConnectionFailed(_) => "DB_CONNECTION_FAILED",
+            Self::CreateFailed(_) => "DB_CREATE_FAILED",
+            Self::OperationFailed(_) => "DB_OPERATION_FAILED",
+            Self::UniqueViolation { .. } => "DB_UNIQUE_VIOLATION",
+            Self::ForeignKeyViolation(_) => "DB_FOREIGN_KEY_VIOLATION",
+            Self::CheckViolation(_) => "DB_CHECK_VIOLATION",
+            Self::NotFound(_) => "DB_NOT_FOUND",
+            Self::Busy(_) => "DB_BUSY",
+            Self::SerializationFailure(_) => "DB_SERIALIZATION_FAILURE",
+            Self::SerializationError(_) => "DB_SERIALIZATION_ERROR",
+            Self::MigrationFailed(_) => "DB_MIGRATION_FAILED",
+            Self::SchemaTooNew(_) => "DB_SCHEMA_TOO_NEW",
+            Self::BackupFailed(_) => "DB_BACKUP_FAILED",
+            Self::TransferFailed(_) => "DB_TRANSFER_FAILED",
+            Self::InvalidCursor(_) => "DB_INVALID_CURSOR",
+            Self::RecordTrashed(_) => "DB_RECORD_TRASHED",
+            #[cfg(feature = "tauri")]
+            Self::TauriError(_) => "DB_TAURI_ERROR",
+        }
+    }
+
+    /// 为唯一约束冲突附加冲突的键，其余错误原样返回
+    #[must_use]
+    pub fn with_key(self, key: impl Into<String>) -> Self {
+        match self {
+            Self::UniqueViolation { target, .. } => Self::UniqueViolation {
+                target,
+                key: Some(key.into()),
+            },
+            other => other,
+        }
+    }
+
+    /// 按数据库返回的扩展错误码分类
+    ///
+    /// 唯一约束的冲突目标取自错误信息（如 `UNIQUE constraint failed: record_remote.id`）。
+    fn from_sqlite_code(code: i32, message: &str) -> Self {
+        match code {
+            // SQLITE_CONSTRAINT_UNIQUE, SQLITE_CONSTRAINT_PRIMARYKEY
+            2067 | 1555 => Self::UniqueViolation {
+                target: message
+                    .split_once(": ")
+                    .map_or(message, |(_, target)| target)
+                    .to_owned(),
+                key: None,
+            },
+            // SQLITE_CONSTRAINT_FOREIGNKEY
+            787 => Self::ForeignKeyViolation(message.to_owned()),
+            // SQLITE_CONSTRAINT_CHECK
+            275 => Self::CheckViolation(message.to_owned()),
+            // SQLITE_BUSY_SNAPSHOT
+            517 => Self::SerializationFailure(message.to_owned()),
+            // SQLITE_BUSY, SQLITE_LOCKED 及其扩展码
+            code if matches!(code & 0xff, 5 | 6) => Self::Busy(message.to_owned()),
+            _ => Self::OperationFailed(message.to_owned()),
+        }
+    }
+}
+
 impl From<DbErr> for DbError {
     fn from(err: DbErr) -> Self {
+        use sea_orm::{RuntimeErr, sqlx};
+
+        match &err {
+            DbErr::RecordNotFound(message) => return Self::NotFound(message.clone()),
+            DbErr::RecordNotUpdated => return Self::NotFound(err.to_string()),
+            DbErr::Conn(RuntimeErr::SqlxError(sqlx::Error::Database(e)))
+            | DbErr::Exec(RuntimeErr::SqlxError(sqlx::Error::Database(e)))
+            | DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::Database(e))) => {
+                let code = e.code().and_then(|code| code.parse::<i32>().ok());
+                if let Some(code) = code {
+                    return Self::from_sqlite_code(code, e.message());
+                }
+            }
+            _ => {}
+        }
         Self::OperationFailed(err.to_string())
     }
 }
@@ -27,6 +130,7 @@ impl From<serde_json::Error> for DbError {
     }
 }
 
+#[cfg(feature = "tauri")]
 impl From<tauri::Error> for DbError {
     fn from(err: tauri::Error) -> Self {
         Self::TauriError(err.to_string())
@@ -34,137 +138,155 @@ impl From<tauri::Error> for DbError {
 }
 
 /// 操作类型枚举
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OperationType {
+    #[sea_orm(string_value = "CRAWL_RECORD")]
     CrawlRecord,
+    #[sea_orm(string_value = "CRAWL_PAGE")]
     CrawlPage,
+    #[sea_orm(string_value = "CRAWL_IDOL")]
+    CrawlIdol,
+    #[sea_orm(string_value = "VIEWED")]
     Viewed,
+    #[sea_orm(string_value = "LIKED")]
     Liked,
+    #[sea_orm(string_value = "UNLIKED")]
     Unliked,
+    #[sea_orm(string_value = "SUBMIT")]
     Submit,
+    #[sea_orm(string_value = "CREATE")]
     Create,
+    #[sea_orm(string_value = "UPDATE")]
     Update,
+    #[sea_orm(string_value = "DELETE")]
     Delete,
-}
-
-impl std::fmt::Display for OperationType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::CrawlRecord => write!(f, "CRAWL_RECORD"),
-            Self::CrawlPage => write!(f, "CRAWL_PAGE"),
-            Self::Viewed => write!(f, "VIEWED"),
-            Self::Liked => write!(f, "LIKED"),
-            Self::Unliked => write!(f, "UNLIKED"),
-            Self::Submit => write!(f, "SUBMIT"),
-            Self::Create => write!(f, "CREATE"),
-            Self::Update => write!(f, "UPDATE"),
-            Self::Delete => write!(f, "DELETE"),
-        }
-    }
-}
-
-impl std::str::FromStr for OperationType {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "CREATE" => Ok(Self::Create),
-            "UPDATE" => Ok(Self::Update),
-            "DELETE" => Ok(Self::Delete),
-            _ => Err(format!("Invalid operation type: {s}")),
-        }
-    }
+    #[sea_orm(string_value = "RATED")]
+    Rated,
+    #[sea_orm(string_value = "NOTED")]
+    Noted,
+    /// 迁移时无法识别的旧操作类型
+    #[sea_orm(string_value = "UNKNOWN")]
+    Unknown,
 }
 
 /// 操作状态枚举
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OperationStatus {
+    #[sea_orm(string_value = "SUCCESS")]
     Success,
+    #[sea_orm(string_value = "FAILED")]
     Failed,
-}
-
-impl std::fmt::Display for OperationStatus {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Success => write!(f, "SUCCESS"),
-            Self::Failed => write!(f, "FAILED"),
-        }
-    }
-}
-
-impl std::str::FromStr for OperationStatus {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "SUCCESS" => Ok(Self::Success),
-            "FAILED" => Ok(Self::Failed),
-            _ => Err(format!("Invalid operation status: {s}")),
-        }
-    }
+    /// 迁移时无法识别的旧状态
+    #[sea_orm(string_value = "UNKNOWN")]
+    Unknown,
 }
 
 /// 任务类型枚举
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum TaskType {
+    /// 按编号批量爬取
+    #[sea_orm(string_value = "CRAWL")]
     Crawl,
+    /// 从起始页自动翻页爬取
+    #[sea_orm(string_value = "AUTO")]
+    Auto,
+    #[sea_orm(string_value = "SUBMIT")]
     Submit,
+    #[sea_orm(string_value = "UPDATE")]
     Update,
-}
-
-impl std::fmt::Display for TaskType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Crawl => write!(f, "CRAWL"),
-            Self::Submit => write!(f, "SUBMIT"),
-            Self::Update => write!(f, "UPDATE"),
-        }
-    }
-}
-
-impl std::str::FromStr for TaskType {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "CRAWL" => Ok(Self::Crawl),
-            "SUBMIT" => Ok(Self::Submit),
-            "UPDATE" => Ok(Self::Update),
-            _ => Err(format!("Invalid task type: {s}")),
-        }
-    }
+    /// 爬取演员头像
+    #[sea_orm(string_value = "IDOL")]
+    Idol,
+    /// 拉取远程记录
+    #[sea_orm(string_value = "PULL")]
+    Pull,
+    /// 迁移时无法识别的旧任务类型
+    #[sea_orm(string_value = "UNKNOWN")]
+    Unknown,
 }
 
 /// 任务状态枚举
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum TaskStatus {
+    #[sea_orm(string_value = "SUCCESS")]
     Success,
+    #[sea_orm(string_value = "ABORTED")]
     Aborted,
+    #[sea_orm(string_value = "PENDING")]
     Pending,
+    #[sea_orm(string_value = "FAILED")]
     Failed,
+    /// 迁移时无法识别的旧状态
+    #[sea_orm(string_value = "UNKNOWN")]
+    Unknown,
 }
 
-impl std::fmt::Display for TaskStatus {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Success => write!(f, "SUCCESS"),
-            Self::Aborted => write!(f, "ABORTED"),
-            Self::Pending => write!(f, "PENDING"),
-            Self::Failed => write!(f, "FAILED"),
+/// 以数据库中的取值作为字符串形式，保证与 `FromStr` 互逆
+macro_rules! impl_string_value {
+    ($($ty:ident => $name:literal),* $(,)?) => {$(
+        impl std::fmt::Display for $ty {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.to_value())
+            }
         }
-    }
+
+        impl std::str::FromStr for $ty {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::try_from_value(&s.to_owned()).map_err(|_| format!("Invalid {}: {s}", $name))
+            }
+        }
+    )*};
+}
+
+impl_string_value! {
+    OperationType => "operation type",
+    OperationStatus => "operation status",
+    TaskType => "task type",
+    TaskStatus => "task status",
 }
 
-impl std::str::FromStr for TaskStatus {
-    type Err = String;
+/// 记录属性类别
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeKind {
+    #[sea_orm(string_value = "IDOL")]
+    Idol,
+    #[sea_orm(string_value = "GENRE")]
+    Genre,
+    #[sea_orm(string_value = "STUDIO")]
+    Studio,
+    #[sea_orm(string_value = "LABEL")]
+    Label,
+    #[sea_orm(string_value = "SERIES")]
+    Series,
+    #[sea_orm(string_value = "DIRECTOR")]
+    Director,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "SUCCESS" => Ok(Self::Success),
-            "ABORTED" => Ok(Self::Aborted),
-            "PENDING" => Ok(Self::Pending),
-            "FAILED" => Ok(Self::Failed),
-            _ => Err(format!("Invalid task status: {s}")),
+impl AttributeKind {
+    /// `record_local` 中对应的 JSON 映射列
+    pub fn record_column(self) -> crate::record_local::Column {
+        use crate::record_local::Column;
+        match self {
+            Self::Idol => Column::Idols,
+            Self::Genre => Column::Genre,
+            Self::Studio => Column::Studio,
+            Self::Label => Column::Label,
+            Self::Series => Column::Series,
+            Self::Director => Column::Director,
         }
     }
 }
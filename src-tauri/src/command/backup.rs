@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use luneth_db::impl_backup::SnapshotInfo;
+use tauri::State;
+
+use crate::common::{BackupSchedule, BACKUP_SCHEDULE};
+use crate::db::backup;
+use crate::{AppError, AppState};
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn create_backup_snapshot(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    with_images: bool,
+) -> Result<SnapshotInfo, AppError> {
+    // 手动快照沿用定时快照的保留个数
+    let retention = BACKUP_SCHEDULE
+        .lock()
+        .await
+        .as_ref()
+        .map_or(0, |schedule| schedule.retention);
+    backup::create_snapshot(&app, &state.db, with_images, retention).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_backup_snapshots(app: tauri::AppHandle) -> Result<Vec<SnapshotInfo>, AppError> {
+    backup::list_snapshots(&app)
+}
+
+/// 设置定时快照，传入空值时停止
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_backup_schedule(schedule: Option<BackupSchedule>) -> Result<(), AppError> {
+    log::info!("Setting backup schedule to: {schedule:?}");
+    *BACKUP_SCHEDULE.lock().await = schedule;
+    Ok(())
+}
+
+/// 校验并暂存快照，应用重启后换入
+#[tauri::command(rename_all = "snake_case")]
+pub async fn restore_backup_snapshot(app: tauri::AppHandle, name: String) -> Result<(), AppError> {
+    backup::stage_restore(&app, &name).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn cancel_backup_restore(app: tauri::AppHandle) -> Result<(), AppError> {
+    backup::cancel_restore(&app)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn has_pending_backup_restore(app: tauri::AppHandle) -> Result<bool, AppError> {
+    backup::has_pending_restore(&app)
+}
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::db::integrity::{self, IntegrityReport, RepairRequest, RepairSummary};
+use crate::{AppError, AppState};
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn scan_library_integrity(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<IntegrityReport, AppError> {
+    let db = Arc::clone(&state.db);
+    integrity::scan_integrity(&app, db.as_ref()).await
+}
+
+/// 执行修复，返回的 `redownload` 需交给更新任务重新下载图片
+#[tauri::command(rename_all = "snake_case")]
+pub async fn repair_library_integrity(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    request: RepairRequest,
+) -> Result<RepairSummary, AppError> {
+    let db = Arc::clone(&state.db);
+    integrity::repair_integrity(&app, db.as_ref(), request).await
+}
@@ -1,14 +1,31 @@
 #![expect(clippy::let_underscore_must_use)]
 
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
-use luneth_db::entities::record_local::Model as RecorderModel;
+use chrono::{DateTime, Utc};
+use luneth_db::impl_facet::FacetValue;
+use luneth_db::impl_history::{
+    HistoryOpDailyCount, HistoryOpFilter, HistoryOpPage, HistoryOpSort, TaskRun,
+};
+use luneth_db::impl_local::{LocalFilterCondition, PageStart, RecordPage, SortSpec};
+use luneth_db::impl_reconcile::ReconcileReport;
+use luneth_db::impl_search::SearchHit;
+use luneth_db::impl_transfer::{ExportFormat, ImportReport};
+use luneth_db::impl_view::{ViewRetention, ViewStat};
+use luneth_db::AttributeKind;
 use tauri::{Manager as _, State};
 
 use crate::{
     common::EXIST_IDS,
-    db::read::{get_local_records, get_op_history, get_records_count, search_local_records},
-    AppState,
+    db::read::{
+        export_local_records, get_local_records_page, get_most_viewed, get_never_viewed,
+        get_op_history, get_op_history_daily, get_recently_viewed, get_record_facets,
+        get_record_revisions, get_records_count, get_task_run, get_task_runs, get_trash_records,
+        search_local_records,
+    },
+    AppError, AppState,
 };
 
 #[tauri::command(rename_all = "snake_case")]
@@ -23,87 +40,335 @@ pub async fn get_all_exist_records(state: State<'_, Arc<AppState>>) -> Result<Ve
 #[tauri::command(rename_all = "snake_case")]
 pub async fn query_record_count(
     state: State<'_, Arc<AppState>>,
-    filters: Vec<String>,
-) -> Result<u64, String> {
+    filters: Vec<LocalFilterCondition>,
+) -> Result<u64, AppError> {
     let db = Arc::clone(&state.db);
-    let count = get_records_count(db.as_ref(), filters)
-        .await
-        .map_err(|e| e.to_string())?;
+    let count = get_records_count(db.as_ref(), filters).await?;
     log::info!("Total record count: {count}");
     Ok(count)
 }
 
+/// 每个类别默认返回的属性值个数
+const DEFAULT_FACET_LIMIT: u64 = 20;
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn query_record_facets(
+    state: State<'_, Arc<AppState>>,
+    filters: Vec<LocalFilterCondition>,
+    name: Option<String>,
+    limit: Option<u64>,
+) -> Result<HashMap<AttributeKind, Vec<FacetValue>>, AppError> {
+    let db = Arc::clone(&state.db);
+    let name = name.filter(|n| !n.trim().is_empty());
+    let facets = get_record_facets(
+        db.as_ref(),
+        filters,
+        name,
+        limit.unwrap_or(DEFAULT_FACET_LIMIT),
+    )
+    .await?;
+    log::info!("Retrieved facets for {} kinds", facets.len());
+    Ok(facets)
+}
+
 #[tauri::command(rename_all = "snake_case")]
 pub async fn search_records(
     state: State<'_, Arc<AppState>>,
     name: String,
-    offset: Option<u64>,
-    limit: Option<u64>,
-    filters: Vec<String>,
-) -> Result<(u64, Vec<RecorderModel>), String> {
+    start: Option<PageStart>,
+    limit: u64,
+    filters: Vec<LocalFilterCondition>,
+    sort: Option<SortSpec>,
+) -> Result<RecordPage<SearchHit>, AppError> {
     let db = Arc::clone(&state.db);
-    let (count, records) = search_local_records(db.as_ref(), name, offset, limit, filters)
-        .await
-        .map_err(|e| e.to_string())?;
-    log::info!("Retrieved {} records from database", records.len());
-    Ok((count, records))
+    let page = search_local_records(
+        db.as_ref(),
+        name,
+        start.unwrap_or_default(),
+        limit,
+        filters,
+        sort,
+    )
+    .await?;
+    log::info!("Retrieved {} records from database", page.records.len());
+    Ok(page)
 }
 
 #[tauri::command(rename_all = "snake_case")]
 pub async fn get_local_records_paginator(
     state: State<'_, Arc<AppState>>,
-    offset: u64,
+    start: Option<PageStart>,
     limit: u64,
-    filters: Vec<String>,
-) -> Result<Vec<RecorderModel>, String> {
-    log::debug!("Fetching all records from database ordered by updated_at");
+    filters: Vec<LocalFilterCondition>,
+    sort: Option<SortSpec>,
+) -> Result<RecordPage, AppError> {
+    let sort = sort.unwrap_or_default();
+    log::debug!("Fetching records from database ordered by {sort:?}");
     let db = Arc::clone(&state.db);
-    let records = get_local_records(db.as_ref(), Some(offset), Some(limit), filters)
-        .await
-        .map_err(|e| e.to_string())?;
-    log::info!("Retrieved {} records from database", records.len());
-    Ok(records)
+    let page = get_local_records_page(db.as_ref(), start.unwrap_or_default(), limit, filters, sort)
+        .await?;
+    log::info!("Retrieved {} records from database", page.records.len());
+    Ok(page)
 }
 
 #[tauri::command(rename_all = "snake_case")]
-pub async fn get_all_op_history(
+pub async fn query_op_history(
     state: State<'_, Arc<AppState>>,
-) -> Result<Vec<luneth_db::history_op::Model>, String> {
+    filter: Option<HistoryOpFilter>,
+    sort: Option<HistoryOpSort>,
+    offset: Option<u64>,
+    limit: Option<u64>,
+) -> Result<HistoryOpPage, AppError> {
     log::debug!("Fetching operation history from database");
     let db = Arc::clone(&state.db);
-    let history = get_op_history(db.as_ref())
-        .await
-        .map_err(|e| e.to_string())?;
-    log::info!("Retrieved {} operation history records", history.len());
+    let history = get_op_history(
+        db.as_ref(),
+        &filter.unwrap_or_default(),
+        sort.unwrap_or_default(),
+        offset,
+        limit,
+    )
+    .await?;
+    log::info!(
+        "Retrieved {} of {} operation history records",
+        history.items.len(),
+        history.total
+    );
     Ok(history)
 }
 
 #[tauri::command(rename_all = "snake_case")]
-pub async fn mark_record_viewed(app: tauri::AppHandle, code: &str) -> Result<(), String> {
+pub async fn query_op_history_daily(
+    state: State<'_, Arc<AppState>>,
+    filter: Option<HistoryOpFilter>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<Vec<HistoryOpDailyCount>, AppError> {
+    let db = Arc::clone(&state.db);
+    let counts = get_op_history_daily(
+        db.as_ref(),
+        &filter.unwrap_or_default(),
+        utc_offset_minutes.unwrap_or_default(),
+    )
+    .await?;
+    log::info!("Retrieved {} daily operation counts", counts.len());
+    Ok(counts)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_task_runs(
+    state: State<'_, Arc<AppState>>,
+    offset: Option<u64>,
+    limit: Option<u64>,
+) -> Result<Vec<luneth_db::history_task::Model>, AppError> {
+    let db = Arc::clone(&state.db);
+    let tasks = get_task_runs(db.as_ref(), offset, limit).await?;
+    log::info!("Retrieved {} task runs", tasks.len());
+    Ok(tasks)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn inspect_task_run(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<Option<TaskRun>, AppError> {
+    let db = Arc::clone(&state.db);
+    get_task_run(db.as_ref(), &id).await
+}
+
+/// 记录一次查看，返回查看事件编号，离开时以 `finish_record_view` 补充停留时长
+#[tauri::command(rename_all = "snake_case")]
+pub async fn mark_record_viewed(
+    app: tauri::AppHandle,
+    code: &str,
+) -> Result<Option<i32>, AppError> {
     let app_state = app.state::<std::sync::Arc<crate::AppState>>();
     let db = &app_state.db;
 
-    crate::db::write::mark_record_viewed(db.as_ref(), code)
-        .await
-        .map_err(|e| e.to_string())
+    crate::db::write::mark_record_viewed(db.as_ref(), code).await
 }
 
 #[tauri::command(rename_all = "snake_case")]
-pub async fn mark_record_liked(app: tauri::AppHandle, code: &str) -> Result<(), String> {
+pub async fn finish_record_view(
+    state: State<'_, Arc<AppState>>,
+    view_id: i32,
+    duration_secs: i32,
+) -> Result<(), AppError> {
+    let db = Arc::clone(&state.db);
+    crate::db::write::finish_record_view(db.as_ref(), view_id, duration_secs).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_recently_viewed(
+    state: State<'_, Arc<AppState>>,
+    since: Option<DateTime<Utc>>,
+    limit: u64,
+) -> Result<Vec<luneth_db::record_local::Model>, AppError> {
+    let db = Arc::clone(&state.db);
+    get_recently_viewed(db.as_ref(), since, limit).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_most_viewed(
+    state: State<'_, Arc<AppState>>,
+    since: Option<DateTime<Utc>>,
+    limit: u64,
+) -> Result<Vec<ViewStat>, AppError> {
+    let db = Arc::clone(&state.db);
+    get_most_viewed(db.as_ref(), since, limit).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_never_viewed(
+    state: State<'_, Arc<AppState>>,
+    start: Option<PageStart>,
+    limit: u64,
+) -> Result<RecordPage, AppError> {
+    let db = Arc::clone(&state.db);
+    get_never_viewed(db.as_ref(), start.unwrap_or_default(), limit).await
+}
+
+/// 按保留策略清理查看事件，返回删除的事件数，由前端在启动时调用
+#[tauri::command(rename_all = "snake_case")]
+pub async fn prune_view_events(
+    state: State<'_, Arc<AppState>>,
+    retention: ViewRetention,
+) -> Result<u64, AppError> {
+    log::info!("Pruning view events with retention: {retention:?}");
+    let db = Arc::clone(&state.db);
+    crate::db::write::prune_view_events(db.as_ref(), retention).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn mark_record_liked(app: tauri::AppHandle, code: &str) -> Result<(), AppError> {
     let app_state = app.state::<std::sync::Arc<crate::AppState>>();
     let db = &app_state.db;
 
-    crate::db::write::mark_record_liked(db.as_ref(), code)
-        .await
-        .map_err(|e| e.to_string())
+    crate::db::write::mark_record_liked(db.as_ref(), code).await
 }
 
 #[tauri::command(rename_all = "snake_case")]
-pub async fn mark_record_unliked(app: tauri::AppHandle, code: &str) -> Result<(), String> {
+pub async fn mark_record_unliked(app: tauri::AppHandle, code: &str) -> Result<(), AppError> {
     let app_state = app.state::<std::sync::Arc<crate::AppState>>();
     let db = &app_state.db;
 
-    crate::db::write::mark_record_unliked(db.as_ref(), code)
-        .await
-        .map_err(|e| e.to_string())
+    crate::db::write::mark_record_unliked(db.as_ref(), code).await
+}
+
+/// 设置评分（1–5），0 表示清除评分
+#[tauri::command(rename_all = "snake_case")]
+pub async fn rate_record(app: tauri::AppHandle, code: &str, rating: i32) -> Result<(), AppError> {
+    let app_state = app.state::<std::sync::Arc<crate::AppState>>();
+    let db = &app_state.db;
+
+    crate::db::write::rate_record(db.as_ref(), code, rating).await
+}
+
+/// 设置 Markdown 私人备注，空白内容表示清除备注
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_record_note(
+    app: tauri::AppHandle,
+    code: &str,
+    note: &str,
+) -> Result<(), AppError> {
+    let app_state = app.state::<std::sync::Arc<crate::AppState>>();
+    let db = &app_state.db;
+
+    crate::db::write::set_record_note(db.as_ref(), code, note).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn trash_record(app: tauri::AppHandle, code: &str) -> Result<(), AppError> {
+    let app_state = app.state::<std::sync::Arc<crate::AppState>>();
+    let db = &app_state.db;
+
+    crate::db::write::trash_record(db.as_ref(), code).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn restore_record(app: tauri::AppHandle, code: &str) -> Result<(), AppError> {
+    let app_state = app.state::<std::sync::Arc<crate::AppState>>();
+    let db = &app_state.db;
+
+    crate::db::write::restore_record(db.as_ref(), code).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn purge_record(app: tauri::AppHandle, code: &str) -> Result<(), AppError> {
+    let app_state = app.state::<std::sync::Arc<crate::AppState>>();
+    let db = &app_state.db;
+
+    crate::db::write::purge_record(&app, db.as_ref(), code).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_trash_records(
+    state: State<'_, Arc<AppState>>,
+    start: Option<PageStart>,
+    limit: u64,
+) -> Result<RecordPage, AppError> {
+    let db = Arc::clone(&state.db);
+    let page = get_trash_records(db.as_ref(), start.unwrap_or_default(), limit).await?;
+    log::info!(
+        "Retrieved {} of {} records in trash",
+        page.records.len(),
+        page.total
+    );
+    Ok(page)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_record_revisions(
+    state: State<'_, Arc<AppState>>,
+    code: String,
+) -> Result<Vec<luneth_db::record_revision::Model>, AppError> {
+    let db = Arc::clone(&state.db);
+    get_record_revisions(db.as_ref(), &code).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn rollback_record_revision(
+    state: State<'_, Arc<AppState>>,
+    revision_id: i32,
+) -> Result<luneth_db::record_local::Model, AppError> {
+    let db = Arc::clone(&state.db);
+    crate::db::write::rollback_record_revision(db.as_ref(), revision_id).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_records(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    format: ExportFormat,
+    filters: Vec<LocalFilterCondition>,
+    sort: Option<SortSpec>,
+) -> Result<usize, AppError> {
+    let db = Arc::clone(&state.db);
+    export_local_records(
+        db.as_ref(),
+        Path::new(&path),
+        format,
+        filters,
+        sort.unwrap_or_default(),
+    )
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn import_records(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    format: ExportFormat,
+    dry_run: bool,
+) -> Result<ImportReport, AppError> {
+    let db = Arc::clone(&state.db);
+    crate::db::write::import_local_records(db.as_ref(), Path::new(&path), format, dry_run).await
+}
+
+/// 比对本地与远端记录，返回的 `to_submit` 可直接交给 `launch_submit_task`
+#[tauri::command(rename_all = "snake_case")]
+pub async fn reconcile_catalogs(
+    state: State<'_, Arc<AppState>>,
+) -> Result<ReconcileReport, AppError> {
+    let db = Arc::clone(&state.db);
+    crate::db::write::reconcile_records(db.as_ref()).await
 }
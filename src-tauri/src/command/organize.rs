@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use luneth_db::impl_collection::CollectionSummary;
+use luneth_db::impl_tag::TagSummary;
+use luneth_db::record_local::Model as RecorderModel;
+use luneth_db::{attribute, collection, tag, AttributeKind};
+use tauri::State;
+
+use crate::db::{read, write};
+use crate::{AppError, AppState};
+
+// ############
+// # attribute
+// #############
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_attributes(
+    state: State<'_, Arc<AppState>>,
+    kind: AttributeKind,
+) -> Result<Vec<attribute::Model>, AppError> {
+    let db = Arc::clone(&state.db);
+    read::get_attributes(db.as_ref(), kind).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_record_attributes(
+    state: State<'_, Arc<AppState>>,
+    code: String,
+) -> Result<Vec<attribute::Model>, AppError> {
+    let db = Arc::clone(&state.db);
+    read::get_record_attributes(db.as_ref(), &code).await
+}
+
+/// 重命名属性，同类别下已有同名属性时合并到该属性
+#[tauri::command(rename_all = "snake_case")]
+pub async fn rename_attribute(
+    state: State<'_, Arc<AppState>>,
+    id: i32,
+    name: String,
+) -> Result<attribute::Model, AppError> {
+    let db = Arc::clone(&state.db);
+    write::rename_attribute(db.as_ref(), id, &name).await
+}
+
+// ############
+// # tag
+// #############
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_tags(state: State<'_, Arc<AppState>>) -> Result<Vec<TagSummary>, AppError> {
+    let db = Arc::clone(&state.db);
+    read::get_tags(db.as_ref()).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_record_tags(
+    state: State<'_, Arc<AppState>>,
+    code: String,
+) -> Result<Vec<tag::Model>, AppError> {
+    let db = Arc::clone(&state.db);
+    read::get_record_tags(db.as_ref(), &code).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn create_tag(
+    state: State<'_, Arc<AppState>>,
+    name: String,
+) -> Result<tag::Model, AppError> {
+    let db = Arc::clone(&state.db);
+    write::create_tag(db.as_ref(), &name).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn rename_tag(
+    state: State<'_, Arc<AppState>>,
+    id: i32,
+    name: String,
+) -> Result<tag::Model, AppError> {
+    let db = Arc::clone(&state.db);
+    write::rename_tag(db.as_ref(), id, &name).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn delete_tag(state: State<'_, Arc<AppState>>, id: i32) -> Result<(), AppError> {
+    let db = Arc::clone(&state.db);
+    write::delete_tag(db.as_ref(), id).await
+}
+
+/// 为多条记录批量添加多个标签，返回新增的关联数
+#[tauri::command(rename_all = "snake_case")]
+pub async fn tag_records(
+    state: State<'_, Arc<AppState>>,
+    tag_ids: Vec<i32>,
+    codes: Vec<String>,
+) -> Result<u64, AppError> {
+    let db = Arc::clone(&state.db);
+    write::tag_records(db.as_ref(), &tag_ids, &codes).await
+}
+
+/// 从多条记录批量移除多个标签，返回移除的关联数
+#[tauri::command(rename_all = "snake_case")]
+pub async fn untag_records(
+    state: State<'_, Arc<AppState>>,
+    tag_ids: Vec<i32>,
+    codes: Vec<String>,
+) -> Result<u64, AppError> {
+    let db = Arc::clone(&state.db);
+    write::untag_records(db.as_ref(), &tag_ids, &codes).await
+}
+
+// ############
+// # collection
+// #############
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_collections(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<CollectionSummary>, AppError> {
+    let db = Arc::clone(&state.db);
+    read::get_collections(db.as_ref()).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_record_collections(
+    state: State<'_, Arc<AppState>>,
+    code: String,
+) -> Result<Vec<collection::Model>, AppError> {
+    let db = Arc::clone(&state.db);
+    read::get_record_collections(db.as_ref(), &code).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_collection_records(
+    state: State<'_, Arc<AppState>>,
+    id: i32,
+) -> Result<Vec<RecorderModel>, AppError> {
+    let db = Arc::clone(&state.db);
+    read::get_collection_records(db.as_ref(), id).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn create_collection(
+    state: State<'_, Arc<AppState>>,
+    name: String,
+    description: Option<String>,
+) -> Result<collection::Model, AppError> {
+    let db = Arc::clone(&state.db);
+    write::create_collection(db.as_ref(), &name, description.unwrap_or_default()).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn update_collection(
+    state: State<'_, Arc<AppState>>,
+    id: i32,
+    name: String,
+    description: Option<String>,
+) -> Result<collection::Model, AppError> {
+    let db = Arc::clone(&state.db);
+    write::update_collection(db.as_ref(), id, &name, description.unwrap_or_default()).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn delete_collection(state: State<'_, Arc<AppState>>, id: i32) -> Result<(), AppError> {
+    let db = Arc::clone(&state.db);
+    write::delete_collection(db.as_ref(), id).await
+}
+
+/// 按给定顺序将记录追加到列表末尾，返回新加入的记录数
+#[tauri::command(rename_all = "snake_case")]
+pub async fn add_to_collection(
+    state: State<'_, Arc<AppState>>,
+    id: i32,
+    codes: Vec<String>,
+) -> Result<u64, AppError> {
+    let db = Arc::clone(&state.db);
+    write::add_to_collection(db.as_ref(), id, &codes).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn remove_from_collection(
+    state: State<'_, Arc<AppState>>,
+    id: i32,
+    codes: Vec<String>,
+) -> Result<u64, AppError> {
+    let db = Arc::clone(&state.db);
+    write::remove_from_collection(db.as_ref(), id, &codes).await
+}
+
+/// `codes` 中的记录按给定顺序排在最前，其余记录保持相对顺序
+#[tauri::command(rename_all = "snake_case")]
+pub async fn reorder_collection(
+    state: State<'_, Arc<AppState>>,
+    id: i32,
+    codes: Vec<String>,
+) -> Result<(), AppError> {
+    let db = Arc::clone(&state.db);
+    write::reorder_collection(db.as_ref(), id, &codes).await
+}
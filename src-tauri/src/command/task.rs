@@ -1,7 +1,7 @@
 #![expect(clippy::let_underscore_must_use)]
 
 use crate::handlers::Task;
-use crate::AppState;
+use crate::{AppError, AppState};
 use std::sync::Arc;
 use tauri::State;
 
@@ -20,14 +20,15 @@ pub async fn launch_auto_scrap_task(
     load_timeout: u64,
     request_delay: u64,
     webdriver_port: u16,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     log::debug!("Launching auto scraping task for URL: {start_url}");
     let db = Arc::clone(&state.db);
 
     // Use a blocking thread to handle non-Send types
     let handle = std::thread::spawn(move || {
         // Create a simple runtime for the async task
-        let rt = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+        let rt =
+            tokio::runtime::Runtime::new().map_err(|e| AppError::UnknownError(e.to_string()))?;
         rt.block_on(async move {
             let task = Task::new_auto(
                 app,
@@ -39,16 +40,18 @@ pub async fn launch_auto_scrap_task(
                 request_delay,
                 webdriver_port,
             )
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
             log::debug!("Auto scraping task created for URL: {start_url}");
-            task.exec().await.map_err(|e| e.to_string())?;
+            task.exec().await?;
             log::debug!("Auto scraping task completed successfully for URL: {start_url}");
-            Ok::<(), String>(())
+            Ok::<(), AppError>(())
         })
     });
 
-    match handle.join().map_err(|_e| "Thread panicked".to_owned())? {
+    match handle
+        .join()
+        .map_err(|_e| AppError::UnknownError("Thread panicked".to_owned()))?
+    {
         Ok(_) => {
             log::info!("Command Auto scraping task thread completed successfully");
             Ok(())
@@ -71,7 +74,7 @@ pub async fn launch_batch_scrap_task(
     load_timeout: u64,
     request_delay: u64,
     webdriver_port: u16,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     log::debug!("Launching manual scraping task for {} codes", batch.len());
     log::debug!("Codes to scrape: {batch:?}");
     let db = Arc::clone(&state.db);
@@ -79,7 +82,8 @@ pub async fn launch_batch_scrap_task(
     // Use a blocking thread to handle non-Send types
     let handle = std::thread::spawn(move || {
         // Create a simple runtime for the async task
-        let rt = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+        let rt =
+            tokio::runtime::Runtime::new().map_err(|e| AppError::UnknownError(e.to_string()))?;
         rt.block_on(async move {
             let task = Task::new_manual(
                 app,
@@ -91,19 +95,21 @@ pub async fn launch_batch_scrap_task(
                 request_delay,
                 webdriver_port,
             )
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
             log::debug!("Manual scraping task created for {} codes", batch.len());
-            task.exec().await.map_err(|e| e.to_string())?;
+            task.exec().await?;
             log::debug!(
                 "Command Manual scraping task completed successfully for {} codes",
                 batch.len()
             );
-            Ok::<(), String>(())
+            Ok::<(), AppError>(())
         })
     });
 
-    match handle.join().map_err(|_e| "Thread panicked".to_owned())? {
+    match handle
+        .join()
+        .map_err(|_e| AppError::UnknownError("Thread panicked".to_owned()))?
+    {
         Ok(_) => {
             log::info!("Manual scraping task thread completed successfully");
             Ok(())
@@ -119,20 +125,24 @@ pub async fn launch_batch_scrap_task(
 pub async fn launch_idol_scrap_task(
     app: tauri::AppHandle,
     state: State<'_, Arc<AppState>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = Arc::clone(&state.db);
     let handle = std::thread::spawn(move || {
-        let rt = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+        let rt =
+            tokio::runtime::Runtime::new().map_err(|e| AppError::UnknownError(e.to_string()))?;
         rt.block_on(async move {
             let task = Task::new_idol(app, db).await;
             log::debug!("Idol scraping task created");
             task.exec().await?;
             log::debug!("Idol scraping task completed successfully");
-            Ok::<(), String>(())
+            Ok::<(), AppError>(())
         })
     });
 
-    match handle.join().map_err(|_e| "Thread panicked".to_owned())? {
+    match handle
+        .join()
+        .map_err(|_e| AppError::UnknownError("Thread panicked".to_owned()))?
+    {
         Ok(_) => {
             log::info!("Idol scraping task thread completed successfully");
             Ok(())
@@ -148,20 +158,24 @@ pub async fn launch_idol_scrap_task(
 pub async fn launch_record_pull_task(
     app: tauri::AppHandle,
     state: State<'_, Arc<AppState>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let db = Arc::clone(&state.db);
     let handle = std::thread::spawn(move || {
-        let rt = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+        let rt =
+            tokio::runtime::Runtime::new().map_err(|e| AppError::UnknownError(e.to_string()))?;
         rt.block_on(async move {
             let task = Task::new_pull_record_slim(app, db).await;
             log::debug!("Record pull task created");
             task.exec().await?;
             log::debug!("Record pull task completed successfully");
-            Ok::<(), String>(())
+            Ok::<(), AppError>(())
         })
     });
 
-    match handle.join().map_err(|_e| "Thread panicked".to_owned())? {
+    match handle
+        .join()
+        .map_err(|_e| AppError::UnknownError("Thread panicked".to_owned()))?
+    {
         Ok(_) => {
             log::info!("Record pull task thread completed successfully");
             Ok(())
@@ -178,7 +192,7 @@ pub async fn launch_submit_task(
     app: tauri::AppHandle,
     state: State<'_, Arc<AppState>>,
     codes: Vec<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     log::debug!("Launching submit task for {} codes", codes.len());
     log::debug!("Codes to submit: {codes:?}");
     let db = Arc::clone(&state.db);
@@ -186,7 +200,8 @@ pub async fn launch_submit_task(
     // Use a blocking thread to handle non-Send types
     let handle = std::thread::spawn(move || {
         // Create a simple runtime for the async task
-        let rt = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+        let rt =
+            tokio::runtime::Runtime::new().map_err(|e| AppError::UnknownError(e.to_string()))?;
         rt.block_on(async move {
             let task = Task::new_submit(app, db, codes.clone()).await;
             log::debug!("Submit task created for {} codes", codes.len());
@@ -195,11 +210,14 @@ pub async fn launch_submit_task(
                 "Submit task completed successfully for {} codes",
                 codes.len()
             );
-            Ok::<(), String>(())
+            Ok::<(), AppError>(())
         })
     });
 
-    match handle.join().map_err(|_e| "Thread panicked".to_owned())? {
+    match handle
+        .join()
+        .map_err(|_e| AppError::UnknownError("Thread panicked".to_owned()))?
+    {
         Ok(_) => {
             log::info!("Submit task thread completed successfully");
             Ok(())
@@ -222,7 +240,7 @@ pub async fn launch_update_task(
     load_timeout: u64,
     request_delay: u64,
     webdriver_port: u16,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     log::debug!("Launching update task for {} codes", batch.len());
     log::debug!("Codes to update: {batch:?}");
     let db = Arc::clone(&state.db);
@@ -230,7 +248,8 @@ pub async fn launch_update_task(
     // Use a blocking thread to handle non-Send types
     let handle = std::thread::spawn(move || {
         // Create a simple runtime for the async task
-        let rt = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+        let rt =
+            tokio::runtime::Runtime::new().map_err(|e| AppError::UnknownError(e.to_string()))?;
         rt.block_on(async move {
             let config = crate::handlers::BatchCrawlConfig::new(
                 batch.clone(),
@@ -240,23 +259,23 @@ pub async fn launch_update_task(
                 request_delay,
                 webdriver_port,
             )
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
-            let task = Task::new_update(app, db, config)
-                .await
-                .map_err(|e| e.to_string())?;
+            let task = Task::new_update(app, db, config).await?;
             log::debug!("Update task created for {} codes", batch.len());
-            task.exec().await.map_err(|e| e.to_string())?;
+            task.exec().await?;
             log::debug!(
                 "Update task completed successfully for {} codes",
                 batch.len()
             );
-            Ok::<(), String>(())
+            Ok::<(), AppError>(())
         })
     });
 
-    match handle.join().map_err(|_e| "Thread panicked".to_owned())? {
+    match handle
+        .join()
+        .map_err(|_e| AppError::UnknownError("Thread panicked".to_owned()))?
+    {
         Ok(_) => {
             log::info!("Update task thread completed successfully");
             Ok(())
@@ -267,3 +286,41 @@ pub async fn launch_update_task(
         }
     }
 }
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn launch_retry_task(
+    app: tauri::AppHandle,
+    state: State<'_, Arc<AppState>>,
+    task_id: String,
+) -> Result<(), AppError> {
+    log::debug!("Launching retry task for task run {task_id}");
+    let db = Arc::clone(&state.db);
+
+    // Use a blocking thread to handle non-Send types
+    let handle = std::thread::spawn(move || {
+        // Create a simple runtime for the async task
+        let rt =
+            tokio::runtime::Runtime::new().map_err(|e| AppError::UnknownError(e.to_string()))?;
+        rt.block_on(async move {
+            let task = Task::new_retry(app, db, task_id.clone()).await?;
+            log::debug!("Retry task created for task run {task_id}");
+            task.exec().await?;
+            log::debug!("Retry task completed successfully for task run {task_id}");
+            Ok::<(), AppError>(())
+        })
+    });
+
+    match handle
+        .join()
+        .map_err(|_e| AppError::UnknownError("Thread panicked".to_owned()))?
+    {
+        Ok(_) => {
+            log::info!("Retry task thread completed successfully");
+            Ok(())
+        }
+        Err(e) => {
+            log::error!("Retry task failed: {e}");
+            Err(e)
+        }
+    }
+}
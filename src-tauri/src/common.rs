@@ -39,6 +39,21 @@ pub fn get_record_image_path(app_handle: &AppHandle) -> Result<PathBuf, AppError
     get_local_image_path(app_handle).map(|p| p.join("records"))
 }
 
+/// 数据库快照目录
+pub fn get_backup_path(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    match app_handle.path().app_local_data_dir() {
+        Ok(p) => Ok(p.join("backups")),
+        Err(e) => Err(AppError::FileSystemError(e.to_string())),
+    }
+}
+
+pub fn get_db_path(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    match app_handle.path().app_local_data_dir() {
+        Ok(p) => Ok(p.join(luneth_db::DB_FILE_NAME)),
+        Err(e) => Err(AppError::FileSystemError(e.to_string())),
+    }
+}
+
 #[expect(unused)]
 pub fn get_idol_image_path(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
     get_local_image_path(app_handle).map(|p| p.join("idols"))
@@ -65,6 +80,20 @@ pub async fn new_postman() -> Result<Postman, AppError> {
     Ok(client)
 }
 
+/// 定时快照配置，由前端在启动时下发
+pub static BACKUP_SCHEDULE: LazyLock<Mutex<Option<BackupSchedule>>> =
+    LazyLock::new(|| Mutex::new(None));
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct BackupSchedule {
+    /// 两次快照的间隔（小时）
+    pub interval_hours: u64,
+    /// 保留的快照个数，为 0 时不清理
+    pub retention: usize,
+    /// 是否一并归档图片
+    pub with_images: bool,
+}
+
 pub static TASK_BASE_URL: LazyLock<Mutex<Option<String>>> = LazyLock::new(|| {
     Mutex::new(None) // Default base URL
 });
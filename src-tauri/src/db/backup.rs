@@ -0,0 +1,120 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use luneth_db::impl_backup::{SnapshotInfo, SnapshotOptions};
+use luneth_db::DbOperator;
+use tauri::AppHandle;
+
+use crate::common::{
+    get_backup_path, get_db_path, get_local_image_path, BackupSchedule, BACKUP_SCHEDULE,
+};
+use crate::AppError;
+
+/// 检查是否需要定时快照的间隔
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+pub(crate) async fn create_snapshot(
+    app_handle: &AppHandle,
+    db: &DbOperator,
+    with_images: bool,
+    retention: usize,
+) -> Result<SnapshotInfo, AppError> {
+    let options = SnapshotOptions {
+        dir: get_backup_path(app_handle)?,
+        retention,
+        images_dir: with_images
+            .then(|| get_local_image_path(app_handle))
+            .transpose()?,
+    };
+    log::debug!("Creating database snapshot with options {options:?}");
+    let snapshot = db.create_snapshot(&options).await?;
+    log::info!("Database snapshot {} created", snapshot.name);
+    Ok(snapshot)
+}
+
+pub(crate) fn list_snapshots(app_handle: &AppHandle) -> Result<Vec<SnapshotInfo>, AppError> {
+    let snapshots = DbOperator::list_snapshots(&get_backup_path(app_handle)?)?;
+    Ok(snapshots)
+}
+
+/// 校验并暂存指定快照，重启后生效
+pub(crate) async fn stage_restore(app_handle: &AppHandle, name: &str) -> Result<(), AppError> {
+    let snapshot = list_snapshots(app_handle)?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| luneth_db::DbError::NotFound(format!("snapshot {name}")))?;
+    DbOperator::stage_restore(
+        &snapshot.path,
+        &get_db_path(app_handle)?,
+        Some(&get_local_image_path(app_handle)?),
+    )
+    .await?;
+    log::info!("Snapshot {name} staged, it will be restored on next start");
+    Ok(())
+}
+
+pub(crate) fn cancel_restore(app_handle: &AppHandle) -> Result<(), AppError> {
+    DbOperator::cancel_pending_restore(
+        &get_db_path(app_handle)?,
+        Some(&get_local_image_path(app_handle)?),
+    )?;
+    Ok(())
+}
+
+pub(crate) fn has_pending_restore(app_handle: &AppHandle) -> Result<bool, AppError> {
+    Ok(DbOperator::has_pending_restore(&get_db_path(app_handle)?))
+}
+
+/// 在打开数据库前换入上次暂存的快照
+pub(crate) fn apply_pending_restore(app_handle: &AppHandle) -> Result<bool, AppError> {
+    let restored = DbOperator::apply_pending_restore(
+        &get_db_path(app_handle)?,
+        Some(&get_local_image_path(app_handle)?),
+    )?;
+    Ok(restored)
+}
+
+/// 在后台线程中按 [`BACKUP_SCHEDULE`] 定时创建快照
+pub(crate) fn spawn_backup_scheduler(app_handle: AppHandle, db: Arc<DbOperator>) {
+    std::thread::spawn(move || {
+        let rt = match tokio::runtime::Runtime::new() {
+            Ok(rt) => rt,
+            Err(e) => {
+                log::error!("Failed to create runtime for backup scheduler: {e}");
+                return;
+            }
+        };
+        loop {
+            rt.block_on(async {
+                let schedule = BACKUP_SCHEDULE.lock().await.clone();
+                if let Some(schedule) = schedule {
+                    if let Err(e) = run_scheduled_snapshot(&app_handle, &db, &schedule).await {
+                        log::error!("Scheduled snapshot failed: {e}");
+                    }
+                }
+            });
+            std::thread::sleep(SCHEDULE_CHECK_INTERVAL);
+        }
+    });
+}
+
+/// 距最近一次快照已超过间隔时创建快照
+async fn run_scheduled_snapshot(
+    app_handle: &AppHandle,
+    db: &DbOperator,
+    schedule: &BackupSchedule,
+) -> Result<(), AppError> {
+    let Some(interval) = i64::try_from(schedule.interval_hours)
+        .ok()
+        .filter(|hours| *hours > 0)
+        .and_then(chrono::TimeDelta::try_hours)
+    else {
+        return Ok(());
+    };
+    let latest = list_snapshots(app_handle)?.first().map(|s| s.created_at);
+    if latest.is_some_and(|latest| chrono::Utc::now() - latest < interval) {
+        return Ok(());
+    }
+    create_snapshot(app_handle, db, schedule.with_images, schedule.retention).await?;
+    Ok(())
+}
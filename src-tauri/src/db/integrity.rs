@@ -0,0 +1,317 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read as _};
+use std::path::{Path, PathBuf};
+
+use luneth_db::impl_integrity::{InvalidJsonColumn, LocalImageState};
+use luneth_db::impl_revision::RevisionSource;
+use luneth_db::{DbOperator, OperationType};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::common::{get_local_image_path, get_record_image_path};
+use crate::AppError;
+
+/// 完整性检查结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IntegrityReport {
+    /// 检查的记录数，包括回收站中的记录
+    pub scanned: usize,
+    pub flag_mismatches: Vec<FlagMismatch>,
+    /// 没有对应记录的图片目录名
+    pub orphan_folders: Vec<String>,
+    pub broken_images: Vec<BrokenImage>,
+    pub invalid_json: Vec<InvalidJsonColumn>,
+}
+
+/// 缓存标记或图片数与磁盘不符的记录
+#[derive(Debug, Clone, Serialize)]
+pub struct FlagMismatch {
+    pub record_id: String,
+    pub is_cached_locally: bool,
+    pub local_image_count: i32,
+    /// 磁盘上可按顺序读取的图片数（封面加连续编号的样图）
+    pub usable_images: i32,
+}
+
+/// 无法读取或为空的图片文件
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenImage {
+    pub record_id: String,
+    pub file: String,
+    pub reason: String,
+}
+
+/// 修复操作，均以记录编号或目录名指定
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RepairRequest {
+    /// 按磁盘上的图片修正缓存标记与图片数
+    #[serde(default)]
+    pub fix_flags: Vec<String>,
+    /// 标记为未缓存，由更新任务重新下载图片
+    #[serde(default)]
+    pub redownload: Vec<String>,
+    /// 将无记录的图片目录移入隔离目录
+    #[serde(default)]
+    pub quarantine: Vec<String>,
+}
+
+/// 修复结果，`redownload` 为需要交给更新任务的记录编号
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RepairSummary {
+    pub fixed_flags: Vec<String>,
+    pub redownload: Vec<String>,
+    pub quarantined: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// 单个记录图片目录的检查结果
+#[derive(Debug, Default)]
+struct FolderScan {
+    usable: i32,
+    broken: Vec<BrokenImage>,
+}
+
+pub(crate) async fn scan_integrity(
+    app_handle: &AppHandle,
+    db: &DbOperator,
+) -> Result<IntegrityReport, AppError> {
+    log::debug!("Scanning library integrity");
+    let records_dir = get_record_image_path(app_handle)?;
+    let states = db.query_local_image_states().await?;
+    let invalid_json = db.scan_invalid_json_local().await?;
+
+    // 遍历图片目录为阻塞操作
+    let mut report =
+        tauri::async_runtime::spawn_blocking(move || scan_image_folders(&records_dir, &states))
+            .await
+            .map_err(|e| AppError::UnknownError(e.to_string()))??;
+    report.invalid_json = invalid_json;
+
+    log::info!(
+        "Integrity scan of {} records: {} flag mismatches, {} orphan folders, {} broken images, {} invalid JSON columns",
+        report.scanned,
+        report.flag_mismatches.len(),
+        report.orphan_folders.len(),
+        report.broken_images.len(),
+        report.invalid_json.len()
+    );
+    Ok(report)
+}
+
+/// 按请求修复，单项失败记入结果并继续处理其余项
+pub(crate) async fn repair_integrity(
+    app_handle: &AppHandle,
+    db: &DbOperator,
+    request: RepairRequest,
+) -> Result<RepairSummary, AppError> {
+    log::debug!("Repairing library integrity: {request:?}");
+    let records_dir = get_record_image_path(app_handle)?;
+    let states = db
+        .query_local_image_states()
+        .await?
+        .into_iter()
+        .map(|state| (state.id.clone(), state))
+        .collect::<HashMap<_, _>>();
+    let source = RevisionSource::new(OperationType::Update);
+    let mut summary = RepairSummary::default();
+
+    for id in request.fix_flags {
+        let Some(state) = states.get(&id) else {
+            summary.errors.push(format!("{id}: record not found"));
+            continue;
+        };
+        let usable = scan_record_folder(&records_dir.join(&id), &id).usable;
+        let (count, cached) = if usable > 0 {
+            (usable, true)
+        } else {
+            (state.local_image_count, false)
+        };
+        match db.set_local_image_state(&id, count, cached, &source).await {
+            Ok(_) => {
+                super::log::log_success_op(db, OperationType::Update, &id).await?;
+                summary.fixed_flags.push(id);
+            }
+            Err(e) => summary.errors.push(format!("{id}: {e}")),
+        }
+    }
+
+    for id in request.redownload {
+        let Some(state) = states.get(&id) else {
+            summary.errors.push(format!("{id}: record not found"));
+            continue;
+        };
+        match db
+            .set_local_image_state(&id, state.local_image_count, false, &source)
+            .await
+        {
+            Ok(_) => {
+                super::log::log_success_op(db, OperationType::Update, &id).await?;
+                summary.redownload.push(id);
+            }
+            Err(e) => summary.errors.push(format!("{id}: {e}")),
+        }
+    }
+
+    if !request.quarantine.is_empty() {
+        let known = states.keys().map(String::as_str).collect();
+        let orphans = list_orphan_folders(&records_dir, &known)?;
+        let quarantine_dir = get_local_image_path(app_handle)?.join("quarantine");
+        for name in request.quarantine {
+            if !orphans.contains(&name) {
+                summary
+                    .errors
+                    .push(format!("{name}: not an orphan image folder"));
+                continue;
+            }
+            match quarantine_folder(&records_dir.join(&name), &quarantine_dir, &name) {
+                Ok(target) => {
+                    log::info!("Quarantined image folder {name} to {}", target.display());
+                    summary.quarantined.push(name);
+                }
+                Err(e) => summary.errors.push(format!("{name}: {e}")),
+            }
+        }
+    }
+
+    log::info!(
+        "Integrity repair: {} flags fixed, {} queued for re-download, {} folders quarantined, {} errors",
+        summary.fixed_flags.len(),
+        summary.redownload.len(),
+        summary.quarantined.len(),
+        summary.errors.len()
+    );
+    Ok(summary)
+}
+
+fn scan_image_folders(
+    records_dir: &Path,
+    states: &[LocalImageState],
+) -> Result<IntegrityReport, AppError> {
+    let mut report = IntegrityReport {
+        scanned: states.len(),
+        ..IntegrityReport::default()
+    };
+
+    for state in states {
+        let scan = scan_record_folder(&records_dir.join(&state.id), &state.id);
+        let mismatched = if state.is_cached_locally {
+            scan.usable != state.local_image_count
+        } else {
+            scan.usable > 0
+        };
+        if mismatched {
+            report.flag_mismatches.push(FlagMismatch {
+                record_id: state.id.clone(),
+                is_cached_locally: state.is_cached_locally,
+                local_image_count: state.local_image_count,
+                usable_images: scan.usable,
+            });
+        }
+        report.broken_images.extend(scan.broken);
+    }
+
+    let known = states.iter().map(|state| state.id.as_str()).collect();
+    let mut orphans = list_orphan_folders(records_dir, &known)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    orphans.sort_unstable();
+    report.orphan_folders = orphans;
+
+    Ok(report)
+}
+
+/// 图片目录中没有对应记录的子目录
+fn list_orphan_folders(
+    records_dir: &Path,
+    known: &HashSet<&str>,
+) -> Result<HashSet<String>, AppError> {
+    let entries = match fs::read_dir(records_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(e) => return Err(AppError::FileSystemError(e.to_string())),
+    };
+    let orphans = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| !known.contains(name.as_str()))
+        .collect();
+    Ok(orphans)
+}
+
+/// 封面为 `<id>`，样图为 `<id>_0`、`<id>_1` …，可用图片数按连续编号计算
+fn scan_record_folder(dir: &Path, id: &str) -> FolderScan {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return FolderScan::default(),
+        Err(e) => {
+            return FolderScan {
+                usable: 0,
+                broken: vec![BrokenImage {
+                    record_id: id.to_owned(),
+                    file: dir.display().to_string(),
+                    reason: e.to_string(),
+                }],
+            }
+        }
+    };
+
+    let mut scan = FolderScan::default();
+    let mut valid = HashSet::new();
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        if !path.is_file() {
+            continue;
+        }
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        match check_image(&path) {
+            Ok(()) => {
+                valid.insert(stem.to_owned());
+            }
+            Err(reason) => scan.broken.push(BrokenImage {
+                record_id: id.to_owned(),
+                file: path.display().to_string(),
+                reason,
+            }),
+        }
+    }
+
+    if valid.contains(id) {
+        let samples = (0..)
+            .take_while(|i| valid.contains(&format!("{id}_{i}")))
+            .count();
+        scan.usable = i32::try_from(samples + 1).unwrap_or(i32::MAX);
+    }
+    scan
+}
+
+/// 图片文件可打开且非空
+fn check_image(path: &Path) -> Result<(), String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut buf = [0_u8; 1];
+    match file.read(&mut buf) {
+        Ok(0) => Err("zero-byte file".to_owned()),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 移入隔离目录，同名目录已存在时追加时间戳
+pub(crate) fn quarantine_folder(
+    dir: &Path,
+    quarantine_dir: &Path,
+    name: &str,
+) -> io::Result<PathBuf> {
+    fs::create_dir_all(quarantine_dir)?;
+    let mut target = quarantine_dir.join(name);
+    if target.exists() {
+        target = quarantine_dir.join(format!(
+            "{name}.{}",
+            chrono::Utc::now().format("%Y%m%d%H%M%S")
+        ));
+    }
+    fs::rename(dir, &target)?;
+    Ok(target)
+}
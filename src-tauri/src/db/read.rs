@@ -1,17 +1,30 @@
-use luneth_db::entities::record_local::Model as RecorderModel;
-use luneth_db::impl_local::LocalFilterCondition;
-use luneth_db::{history_op, DbOperator};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use luneth_db::impl_collection::CollectionSummary;
+use luneth_db::impl_facet::FacetValue;
+use luneth_db::impl_history::{
+    HistoryOpDailyCount, HistoryOpFilter, HistoryOpPage, HistoryOpSort, TaskRun,
+};
+use luneth_db::impl_local::{LocalFilterCondition, PageStart, RecordPage, SortSpec};
+use luneth_db::impl_search::SearchHit;
+use luneth_db::impl_tag::TagSummary;
+use luneth_db::impl_transfer::ExportFormat;
+use luneth_db::impl_view::ViewStat;
+use luneth_db::record_local::Model as RecorderModel;
+use luneth_db::{
+    attribute, collection, history_task, record_revision, tag, AttributeKind, DbOperator,
+};
 
 use crate::AppError;
 
 pub(crate) async fn get_records_count(
     db: &DbOperator,
-    filters: Vec<String>,
+    filters: Vec<LocalFilterCondition>,
 ) -> Result<u64, AppError> {
-    let filters = filters
-        .into_iter()
-        .filter_map(|f| f.parse().ok())
-        .collect::<Vec<LocalFilterCondition>>();
     log::debug!("Querying records count from database, filter by {filters:?}");
     let count = db.query_total_count(filters).await?;
     log::debug!("Successfully retrieved records count {count}");
@@ -19,77 +32,263 @@ pub(crate) async fn get_records_count(
     Ok(count)
 }
 
-pub(crate) async fn get_local_records(
+pub(crate) async fn get_record_facets(
     db: &DbOperator,
-    offset: Option<u64>,
-    limit: Option<u64>,
-    filters: Vec<String>,
-) -> Result<Vec<RecorderModel>, AppError> {
-    let filters = filters
-        .into_iter()
-        .filter_map(|f| f.parse().ok())
-        .collect::<Vec<LocalFilterCondition>>();
-    log::debug!("Querying records count from database, filter by {filters:?}");
-    let records = db.query_local(offset, limit, filters).await?;
-    log::debug!("Successfully retrieved {} records", records.len());
-    Ok(records)
+    filters: Vec<LocalFilterCondition>,
+    name: Option<String>,
+    limit: u64,
+) -> Result<HashMap<AttributeKind, Vec<FacetValue>>, AppError> {
+    log::debug!("Querying record facets from database, filter by {filters:?}, search {name:?}");
+    let facets = db.query_facets(filters, name, limit).await?;
+    log::debug!("Successfully retrieved facets for {} kinds", facets.len());
+    Ok(facets)
+}
+
+pub(crate) async fn get_local_records_page(
+    db: &DbOperator,
+    start: PageStart,
+    limit: u64,
+    filters: Vec<LocalFilterCondition>,
+    sort: SortSpec,
+) -> Result<RecordPage, AppError> {
+    log::debug!("Querying records page from database, filter by {filters:?}, sort by {sort:?}");
+    let page = db.query_local_page(filters, sort, start, limit).await?;
+    log::debug!(
+        "Successfully retrieved {} of {} records",
+        page.records.len(),
+        page.total
+    );
+    Ok(page)
 }
 
 pub(crate) async fn search_local_records(
     db: &DbOperator,
     name: String,
-    offset: Option<u64>,
-    limit: Option<u64>,
-    filters: Vec<String>,
-) -> Result<(u64, Vec<RecorderModel>), AppError> {
-    let filters = filters
-        .into_iter()
-        .filter_map(|f| f.parse().ok())
-        .collect::<Vec<LocalFilterCondition>>();
-    log::debug!("Querying records count from database, filter by {filters:?}");
-    let (count, records) = db.search_local(name, offset, limit, filters).await?;
-    log::debug!("Successfully retrieved {} records", records.len());
-    Ok((count, records))
+    start: PageStart,
+    limit: u64,
+    filters: Vec<LocalFilterCondition>,
+    sort: Option<SortSpec>,
+) -> Result<RecordPage<SearchHit>, AppError> {
+    log::debug!("Searching records from database, filter by {filters:?}, sort by {sort:?}");
+    let page = db.search_local(name, filters, sort, start, limit).await?;
+    log::debug!(
+        "Successfully retrieved {} of {} records",
+        page.records.len(),
+        page.total
+    );
+    Ok(page)
+}
+
+pub(crate) async fn get_trash_records(
+    db: &DbOperator,
+    start: PageStart,
+    limit: u64,
+) -> Result<RecordPage, AppError> {
+    log::debug!("Querying records in trash");
+    let page = db.query_trash_local(start, limit).await?;
+    log::debug!(
+        "Successfully retrieved {} of {} records in trash",
+        page.records.len(),
+        page.total
+    );
+    Ok(page)
+}
+
+/// 最近查看的记录，`since` 限定最早的查看时间
+pub(crate) async fn get_recently_viewed(
+    db: &DbOperator,
+    since: Option<DateTime<Utc>>,
+    limit: u64,
+) -> Result<Vec<RecorderModel>, AppError> {
+    log::debug!("Querying recently viewed records since {since:?}");
+    let records = db.query_recently_viewed(since, limit).await?;
+    log::debug!(
+        "Successfully retrieved {} recently viewed records",
+        records.len()
+    );
+    Ok(records)
+}
+
+/// 查看次数最多的记录，`since` 为空时按累计次数统计
+pub(crate) async fn get_most_viewed(
+    db: &DbOperator,
+    since: Option<DateTime<Utc>>,
+    limit: u64,
+) -> Result<Vec<ViewStat>, AppError> {
+    log::debug!("Querying most viewed records since {since:?}");
+    let stats = db.query_most_viewed(since, limit).await?;
+    log::debug!("Successfully retrieved {} most viewed records", stats.len());
+    Ok(stats)
+}
+
+pub(crate) async fn get_never_viewed(
+    db: &DbOperator,
+    start: PageStart,
+    limit: u64,
+) -> Result<RecordPage, AppError> {
+    log::debug!("Querying never viewed records");
+    let page = db.query_never_viewed(start, limit).await?;
+    log::debug!(
+        "Successfully retrieved {} of {} never viewed records",
+        page.records.len(),
+        page.total
+    );
+    Ok(page)
+}
+
+pub(crate) async fn get_record_revisions(
+    db: &DbOperator,
+    code: &str,
+) -> Result<Vec<record_revision::Model>, AppError> {
+    log::debug!("Querying revisions of record {code}");
+    let revisions = db.query_record_revisions(code).await?;
+    log::debug!("Successfully retrieved {} revisions", revisions.len());
+    Ok(revisions)
 }
 
 // TODO: Op errortype display
-pub(crate) async fn get_op_history(db: &DbOperator) -> Result<Vec<history_op::Model>, AppError> {
-    log::debug!("Querying operation history from database");
-    let history = db.query_history_op().await?;
+pub(crate) async fn get_op_history(
+    db: &DbOperator,
+    filter: &HistoryOpFilter,
+    sort: HistoryOpSort,
+    offset: Option<u64>,
+    limit: Option<u64>,
+) -> Result<HistoryOpPage, AppError> {
+    log::debug!("Querying operation history from database, filter by {filter:?}, sort by {sort:?}");
+    let history = db.query_history_op(filter, sort, offset, limit).await?;
     log::debug!(
-        "Successfully retrieved {} operation history entries",
-        history.len()
+        "Successfully retrieved {} of {} operation history entries",
+        history.items.len(),
+        history.total
     );
     Ok(history)
 }
 
-pub(crate) async fn get_remote_record_id(db: &DbOperator) -> Result<Vec<String>, AppError> {
-    log::debug!("Querying all remote record IDs from database");
-    let ids = db
-        .query_specified_column(luneth_db::record_remote::Column::Id)
+pub(crate) async fn get_op_history_daily(
+    db: &DbOperator,
+    filter: &HistoryOpFilter,
+    utc_offset_minutes: i32,
+) -> Result<Vec<HistoryOpDailyCount>, AppError> {
+    log::debug!("Querying daily operation counts from database, filter by {filter:?}");
+    let counts = db
+        .query_history_op_daily(filter, utc_offset_minutes)
         .await?;
-    log::debug!("Successfully retrieved {} remote record IDs", ids.len());
-    Ok(ids)
+    log::debug!(
+        "Successfully retrieved {} daily operation counts",
+        counts.len()
+    );
+    Ok(counts)
 }
 
-pub(crate) async fn get_local_record_id(db: &DbOperator) -> Result<Vec<String>, AppError> {
-    log::debug!("Querying all local record IDs from database");
-    let ids = db
-        .query_specified_column(luneth_db::record_local::Column::Id)
-        .await?;
-    log::debug!("Successfully retrieved {} local record IDs", ids.len());
-    Ok(ids)
+pub(crate) async fn get_task_runs(
+    db: &DbOperator,
+    offset: Option<u64>,
+    limit: Option<u64>,
+) -> Result<Vec<history_task::Model>, AppError> {
+    log::debug!("Querying task runs from database, offset {offset:?}, limit {limit:?}");
+    let tasks = db.query_history_task(offset, limit).await?;
+    log::debug!("Successfully retrieved {} task runs", tasks.len());
+    Ok(tasks)
+}
+
+pub(crate) async fn get_task_run(db: &DbOperator, id: &str) -> Result<Option<TaskRun>, AppError> {
+    log::debug!("Querying task run {id} from database");
+    let run = db.find_task_run(id).await?;
+    log::debug!(
+        "Task run {id} has {} operations",
+        run.as_ref().map_or(0, |run| run.operations.len())
+    );
+    Ok(run)
 }
 
 pub(crate) async fn get_exist_record_ids(db: &DbOperator) -> Result<Vec<String>, AppError> {
     log::debug!("Querying all existing record IDs from database");
-    let remote_ids = get_remote_record_id(db).await?;
-    let local_ids = get_local_record_id(db).await?;
-    let mut exist_ids: Vec<String> = remote_ids;
-    exist_ids.extend(local_ids);
+    let exist_ids = db.query_all_record_ids().await?;
     log::debug!(
         "Successfully retrieved {} existing record IDs",
         exist_ids.len()
     );
     Ok(exist_ids)
 }
+
+/// 按列表页的过滤条件与排序将记录导出到文件，返回导出的记录数
+pub(crate) async fn export_local_records(
+    db: &DbOperator,
+    path: &Path,
+    format: ExportFormat,
+    filters: Vec<LocalFilterCondition>,
+    sort: SortSpec,
+) -> Result<usize, AppError> {
+    log::debug!(
+        "Exporting local records as {format:?} to {}, filter by {filters:?}",
+        path.display()
+    );
+    let file = File::create(path).map_err(|e| AppError::FileSystemError(e.to_string()))?;
+    let count = db
+        .export_local(filters, sort, format, BufWriter::new(file))
+        .await?;
+    log::info!("Exported {count} local records to {}", path.display());
+    Ok(count)
+}
+
+pub(crate) async fn get_attributes(
+    db: &DbOperator,
+    kind: AttributeKind,
+) -> Result<Vec<attribute::Model>, AppError> {
+    log::debug!("Querying {kind:?} attributes from database");
+    let attributes = db.query_attributes(kind).await?;
+    log::debug!("Successfully retrieved {} attributes", attributes.len());
+    Ok(attributes)
+}
+
+pub(crate) async fn get_record_attributes(
+    db: &DbOperator,
+    code: &str,
+) -> Result<Vec<attribute::Model>, AppError> {
+    log::debug!("Querying attributes of record {code} from database");
+    Ok(db.find_record_attributes(code).await?)
+}
+
+pub(crate) async fn get_tags(db: &DbOperator) -> Result<Vec<TagSummary>, AppError> {
+    log::debug!("Querying tags from database");
+    let tags = db.query_tags().await?;
+    log::debug!("Successfully retrieved {} tags", tags.len());
+    Ok(tags)
+}
+
+pub(crate) async fn get_record_tags(
+    db: &DbOperator,
+    code: &str,
+) -> Result<Vec<tag::Model>, AppError> {
+    log::debug!("Querying tags of record {code} from database");
+    Ok(db.find_record_tags(code).await?)
+}
+
+pub(crate) async fn get_collections(db: &DbOperator) -> Result<Vec<CollectionSummary>, AppError> {
+    log::debug!("Querying collections from database");
+    let collections = db.query_collections().await?;
+    log::debug!("Successfully retrieved {} collections", collections.len());
+    Ok(collections)
+}
+
+pub(crate) async fn get_record_collections(
+    db: &DbOperator,
+    code: &str,
+) -> Result<Vec<collection::Model>, AppError> {
+    log::debug!("Querying collections of record {code} from database");
+    Ok(db.find_record_collections(code).await?)
+}
+
+/// 列表中的记录，按列表内顺序排列
+pub(crate) async fn get_collection_records(
+    db: &DbOperator,
+    id: i32,
+) -> Result<Vec<RecorderModel>, AppError> {
+    log::debug!("Querying records of collection {id} from database");
+    let records = db.query_collection_records(id).await?;
+    log::debug!(
+        "Successfully retrieved {} records of collection {id}",
+        records.len()
+    );
+    Ok(records)
+}
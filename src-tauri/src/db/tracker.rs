@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use luneth_db::{
+    history_op, history_task, impl_revision::RevisionSource, DbOperator, OperationStatus,
+    OperationType, TaskStatus, TaskType,
+};
+use tokio::sync::Mutex;
+
+use crate::AppError;
+
+/// 将一次任务运行写入 `history_task`，并把逐项操作关联到该任务
+pub(crate) struct TaskTracker {
+    db: Arc<DbOperator>,
+    task_id: String,
+    state: Mutex<TrackerState>,
+}
+
+struct TrackerState {
+    task: history_task::Model,
+    failed_ids: Vec<String>,
+    processed: usize,
+}
+
+impl TaskTracker {
+    /// 以等待状态创建任务记录
+    pub(crate) async fn start(
+        db: Arc<DbOperator>,
+        task_type: TaskType,
+        target_ids: &[String],
+        config: Option<serde_json::Value>,
+        parent_id: Option<&str>,
+    ) -> Result<Self, AppError> {
+        let mut active_model =
+            history_task::Model::new_task(task_type, TaskStatus::Pending, target_ids);
+        if let Some(config) = config {
+            active_model = active_model.with_config(config);
+        }
+        if let Some(parent_id) = parent_id {
+            active_model = active_model.with_parent_id(parent_id);
+        }
+        let task = db.insert_history_task(active_model).await?;
+        log::debug!("Started {task_type} task run {}", task.id);
+
+        Ok(Self {
+            db,
+            task_id: task.id.clone(),
+            state: Mutex::new(TrackerState {
+                task,
+                failed_ids: vec![],
+                processed: 0,
+            }),
+        })
+    }
+
+    /// 追加运行中才确定的目标，如自动爬取的页面与编号
+    pub(crate) async fn add_targets(&self, target_ids: &[String]) -> Result<(), AppError> {
+        let mut state = self.state.lock().await;
+        state.task = self
+            .db
+            .update_history_task(state.task.add_targets(target_ids))
+            .await?;
+        Ok(())
+    }
+
+    /// 以当前任务为来源记录元数据修订
+    pub(crate) fn revision_source(&self, op_type: OperationType) -> RevisionSource {
+        RevisionSource::new(op_type).with_task_id(&self.task_id)
+    }
+
+    /// 目标已存在等无需操作的情况
+    pub(crate) async fn skip(&self, code: &str) {
+        log::debug!("Task run {} skipped {code}", self.task_id);
+        self.state.lock().await.processed += 1;
+    }
+
+    pub(crate) async fn success(&self, op_type: OperationType, code: &str) -> Result<(), AppError> {
+        self.log_op(op_type, code, OperationStatus::Success, None)
+            .await?;
+        self.state.lock().await.processed += 1;
+        Ok(())
+    }
+
+    pub(crate) async fn failure(
+        &self,
+        op_type: OperationType,
+        code: &str,
+        err: String,
+    ) -> Result<(), AppError> {
+        log::error!(
+            "Task run {} failed {op_type} for {code}: {err}",
+            self.task_id
+        );
+        self.log_op(op_type, code, OperationStatus::Failed, Some(err))
+            .await?;
+
+        let mut state = self.state.lock().await;
+        state.processed += 1;
+        if !state.failed_ids.iter().any(|id| id == code) {
+            state.failed_ids.push(code.to_owned());
+        }
+        state.task = self
+            .db
+            .update_history_task(
+                state
+                    .task
+                    .update_status(TaskStatus::Pending, &state.failed_ids),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// 根据任务结果结束运行记录
+    ///
+    /// 正常结束时有失败项为 Failed，否则为 Success；出错时若所有目标均已处理为 Failed，
+    /// 否则为 Aborted。
+    pub(crate) async fn finish(self, result: &Result<(), AppError>) -> Result<(), AppError> {
+        let state = self.state.into_inner();
+        let total = usize::try_from(state.task.total_count).unwrap_or_default();
+        let finished = match result {
+            Ok(()) if state.failed_ids.is_empty() => state
+                .task
+                .update_status(TaskStatus::Success, &state.failed_ids),
+            Ok(()) => state
+                .task
+                .update_status(TaskStatus::Failed, &state.failed_ids),
+            Err(_) if total > 0 && state.processed >= total => state
+                .task
+                .update_status(TaskStatus::Failed, &state.failed_ids),
+            Err(e) => state.task.abort(&state.failed_ids, e.to_string()),
+        };
+
+        let task = self.db.update_history_task(finished).await?;
+        log::info!(
+            "Task run {} finished as {}, {} of {} failed",
+            task.id,
+            task.status,
+            task.failed_count,
+            task.total_count
+        );
+        Ok(())
+    }
+
+    async fn log_op(
+        &self,
+        op_type: OperationType,
+        code: &str,
+        status: OperationStatus,
+        err: Option<String>,
+    ) -> Result<(), AppError> {
+        let op_history_entry = history_op::Model::new_record(
+            code.to_owned(),
+            op_type,
+            status,
+            "crawl".to_owned(),
+            err,
+        )
+        .with_task_id(&self.task_id);
+        self.db.insert_history_op(op_history_entry).await?;
+        Ok(())
+    }
+}
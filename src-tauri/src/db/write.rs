@@ -1,58 +1,127 @@
-use luneth_db::{DbOperator, OperationType};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
 
-use crate::AppError;
+use luneth::record::Recorder;
+use luneth_db::entities::record_local::Model as RecorderModel;
+use luneth_db::impl_reconcile::ReconcileReport;
+use luneth_db::impl_remote::{RemoteUpsertSummary, REMOTE_UPSERT_CHUNK_SIZE};
+use luneth_db::impl_revision::RevisionSource;
+use luneth_db::impl_transfer::{ExportFormat, ImportReport};
+use luneth_db::impl_view::ViewRetention;
+use luneth_db::{attribute, collection, tag, DbError, DbOperator, OperationType};
+use tauri::AppHandle;
+
+use crate::{
+    common::{get_local_image_path, get_record_image_path},
+    db::integrity::quarantine_folder,
+    AppError,
+};
+
+// ############
+// # crawl
+// #############
+
+/// 保存爬取到的记录，已存在时与本地记录合并，返回合并后的记录
+pub(crate) async fn save_crawled_record(
+    db: &DbOperator,
+    recorder: &Recorder,
+    images_cached: bool,
+    source: &RevisionSource,
+) -> Result<RecorderModel, AppError> {
+    log::debug!("Saving crawled record {}", recorder.record.id);
+    let record_model = if images_cached {
+        RecorderModel::from_recorder_with_image_local(recorder)
+    } else {
+        RecorderModel::from_recorder(recorder)
+    };
+    let saved = db.upsert_local(record_model, source).await?;
+    Ok(saved)
+}
 
 // ############
 // # client
 // #############
+/// 在一个事务中分块写入拉取到的远端记录，每写完一块调用一次 `on_chunk`
 pub(crate) async fn save_remote_records(
     db: &DbOperator,
     records: Vec<luneth::common::RecordSlimDto>,
-) -> Result<usize, AppError> {
+    on_chunk: impl FnMut(&RemoteUpsertSummary) + Send,
+) -> Result<RemoteUpsertSummary, AppError> {
     log::debug!("Saving {} remote records to local database", records.len());
-    let mut success_cnt: usize = 0;
-    for record in records {
-        let id = record.id.clone();
-        let active_model = luneth_db::entities::record_remote::ActiveModel::from(record);
-        match db.insert_remote(active_model).await {
-            Ok(_) => success_cnt += 1,
-            Err(e) => {
-                if e.to_string().contains("UNIQUE constraint") {
-                    log::debug!("Record already exists, skipping: {id}");
-                } else {
-                    log::error!("Failed to save record {id}: {e}");
-                    return Err(e.into());
-                }
-            }
-        }
+    let active_models = records
+        .into_iter()
+        .map(luneth_db::entities::record_remote::ActiveModel::from)
+        .collect();
+    let summary = db
+        .upsert_remote_batch(active_models, REMOTE_UPSERT_CHUNK_SIZE, on_chunk)
+        .await?;
+    log::info!(
+        "Saved remote records: {} inserted, {} updated, {} unchanged",
+        summary.inserted,
+        summary.updated,
+        summary.unchanged
+    );
+    Ok(summary)
+}
+
+/// 比对本地与远端记录，修正已在远端记录的提交标记
+pub(crate) async fn reconcile_records(db: &DbOperator) -> Result<ReconcileReport, AppError> {
+    log::debug!("Reconciling local and remote records");
+    let report = db.reconcile_catalogs().await?;
+    for code in &report.marked_submitted {
+        log::debug!("Marked record {code} as submitted, it already exists on the server");
     }
-    log::info!("Successfully saved remote records to local database");
-    Ok(success_cnt)
+    Ok(report)
 }
 
-pub(crate) async fn mark_record_viewed(db: &DbOperator, code: &str) -> Result<(), AppError> {
+/// 记录一次查看，返回查看事件编号，记录不存在时为 `None`
+pub(crate) async fn mark_record_viewed(
+    db: &DbOperator,
+    code: &str,
+) -> Result<Option<i32>, AppError> {
     log::debug!("Marking record as viewed: {code}");
 
-    let record_local = db.find_record_local_by_id(code).await?;
-
-    if let Some(record) = record_local {
-        let viewed_am = record.set_viewd(true);
-        db.update_record_local(viewed_am).await?;
-        super::log::log_success_op(db, OperationType::Viewed, code).await?;
-    } else {
-        log::error!("Record with code {code} not found in local database");
-        super::log::log_failed_op(
-            db,
-            OperationType::Viewed,
-            code,
-            "Record not found".to_owned(),
-        )
-        .await?;
+    match db.record_view(code).await {
+        Ok(view) => {
+            super::log::log_success_op(db, OperationType::Viewed, code).await?;
+            Ok(Some(view.id))
+        }
+        Err(DbError::NotFound(_)) => {
+            log::error!("Record with code {code} not found in local database");
+            super::log::log_failed_op(
+                db,
+                OperationType::Viewed,
+                code,
+                "Record not found".to_owned(),
+            )
+            .await?;
+            Ok(None)
+        }
+        Err(e) => Err(e.into()),
     }
+}
 
+/// 补充查看事件的停留时长（秒）
+pub(crate) async fn finish_record_view(
+    db: &DbOperator,
+    view_id: i32,
+    duration_secs: i32,
+) -> Result<(), AppError> {
+    log::debug!("Finishing view {view_id} after {duration_secs}s");
+    db.finish_view(view_id, duration_secs).await?;
     Ok(())
 }
 
+/// 按保留策略清理查看事件
+pub(crate) async fn prune_view_events(
+    db: &DbOperator,
+    retention: ViewRetention,
+) -> Result<u64, AppError> {
+    log::debug!("Pruning view events with retention {retention:?}");
+    Ok(db.prune_view_events(retention).await?)
+}
+
 pub(crate) async fn mark_record_liked(db: &DbOperator, code: &str) -> Result<(), AppError> {
     log::debug!("Marking record as liked: {code}");
 
@@ -60,7 +129,8 @@ pub(crate) async fn mark_record_liked(db: &DbOperator, code: &str) -> Result<(),
 
     if let Some(record) = record_local {
         let liked_am = record.set_liked(true);
-        db.update_record_local(liked_am).await?;
+        db.update_record_local(liked_am, &RevisionSource::new(OperationType::Liked))
+            .await?;
         super::log::log_success_op(db, OperationType::Liked, code).await?;
     } else {
         log::error!("Record with code {code} not found in local database");
@@ -83,7 +153,8 @@ pub(crate) async fn mark_record_unliked(db: &DbOperator, code: &str) -> Result<(
 
     if let Some(record) = record_local {
         let unliked_am = record.set_liked(false);
-        db.update_record_local(unliked_am).await?;
+        db.update_record_local(unliked_am, &RevisionSource::new(OperationType::Unliked))
+            .await?;
         super::log::log_success_op(db, OperationType::Unliked, code).await?;
     } else {
         log::error!("Record with code {code} not found in local database");
@@ -106,7 +177,8 @@ pub(crate) async fn mark_record_submitted(db: &DbOperator, code: &str) -> Result
 
     if let Some(record) = record_local {
         let submitted_am = record.set_submitted(true);
-        db.update_record_local(submitted_am).await?;
+        db.update_record_local(submitted_am, &RevisionSource::new(OperationType::Submit))
+            .await?;
         super::log::log_success_op(db, OperationType::Submit, code).await?;
     } else {
         log::error!("Record with code {code} not found in local database");
@@ -121,3 +193,288 @@ pub(crate) async fn mark_record_submitted(db: &DbOperator, code: &str) -> Result
 
     Ok(())
 }
+
+/// 设置评分（1–5），0 表示清除评分；超出范围时记录失败并返回错误
+pub(crate) async fn rate_record(db: &DbOperator, code: &str, rating: i32) -> Result<(), AppError> {
+    log::debug!("Rating record {code}: {rating}");
+    update_user_state(db, code, OperationType::Rated, |record| {
+        record.set_rating(rating)
+    })
+    .await
+}
+
+/// 设置 Markdown 私人备注，空白内容表示清除备注
+pub(crate) async fn set_record_note(
+    db: &DbOperator,
+    code: &str,
+    note: &str,
+) -> Result<(), AppError> {
+    log::debug!("Setting note of record {code}");
+    update_user_state(db, code, OperationType::Noted, |record| {
+        record.set_note(note)
+    })
+    .await
+}
+
+/// 更新记录的用户状态，成功与失败均写入操作历史
+async fn update_user_state(
+    db: &DbOperator,
+    code: &str,
+    op_type: OperationType,
+    update: impl FnOnce(RecorderModel) -> luneth_db::record_local::ActiveModel,
+) -> Result<(), AppError> {
+    let Some(record) = db.find_record_local_by_id(code).await? else {
+        log::error!("Record with code {code} not found in local database");
+        super::log::log_failed_op(db, op_type, code, "Record not found".to_owned()).await?;
+        return Ok(());
+    };
+
+    match db
+        .update_record_local(update(record), &RevisionSource::new(op_type))
+        .await
+    {
+        Ok(_) => super::log::log_success_op(db, op_type, code).await,
+        Err(e) => {
+            log::error!("Failed to update record {code}: {e}");
+            super::log::log_failed_op(db, op_type, code, e.to_string()).await?;
+            Err(e.into())
+        }
+    }
+}
+
+// ############
+// # trash
+// #############
+
+pub(crate) async fn trash_record(db: &DbOperator, code: &str) -> Result<(), AppError> {
+    log::debug!("Moving record to trash: {code}");
+    db.trash_record_local(code).await?;
+    Ok(())
+}
+
+pub(crate) async fn restore_record(db: &DbOperator, code: &str) -> Result<(), AppError> {
+    log::debug!("Restoring record from trash: {code}");
+    db.restore_record_local(code).await?;
+    Ok(())
+}
+
+/// 彻底删除回收站中的记录及其图片目录，并记录删除操作
+pub(crate) async fn purge_record(
+    app_handle: &AppHandle,
+    db: &DbOperator,
+    code: &str,
+) -> Result<(), AppError> {
+    log::debug!("Purging record: {code}");
+
+    // 先把图片目录移入隔离目录，删除记录失败时再移回，避免留下没有图片的记录
+    let image_dir = get_record_image_path(app_handle)?.join(code);
+    let quarantined = if image_dir.exists() {
+        let quarantine_dir = get_local_image_path(app_handle)?.join("quarantine");
+        match quarantine_folder(&image_dir, &quarantine_dir, code) {
+            Ok(target) => Some(target),
+            Err(e) => {
+                let err = format!(
+                    "Failed to quarantine image directory {}: {e}",
+                    image_dir.display()
+                );
+                super::log::log_failed_op(db, OperationType::Delete, code, err.clone()).await?;
+                return Err(AppError::FileSystemError(err));
+            }
+        }
+    } else {
+        None
+    };
+
+    if let Err(e) = db.purge_record_local(code).await {
+        if let Some(target) = &quarantined {
+            if let Err(e) = std::fs::rename(target, &image_dir) {
+                log::error!(
+                    "Failed to restore image directory {} from {}: {e}",
+                    image_dir.display(),
+                    target.display()
+                );
+            }
+        }
+        super::log::log_failed_op(db, OperationType::Delete, code, e.to_string()).await?;
+        return Err(e.into());
+    }
+
+    // 记录已删除，清理失败时图片留在隔离目录中
+    if let Some(target) = quarantined {
+        match std::fs::remove_dir_all(&target) {
+            Ok(()) => log::debug!("Removed image directory {}", target.display()),
+            Err(e) => log::warn!("Failed to remove image directory {}: {e}", target.display()),
+        }
+    }
+
+    super::log::log_success_op(db, OperationType::Delete, code).await?;
+    Ok(())
+}
+
+// ############
+// # revision
+// #############
+
+/// 将记录回滚到修订发生前的状态
+pub(crate) async fn rollback_record_revision(
+    db: &DbOperator,
+    revision_id: i32,
+) -> Result<RecorderModel, AppError> {
+    log::debug!("Rolling back record revision {revision_id}");
+    let source = RevisionSource::new(OperationType::Update);
+    match db.rollback_record_revision(revision_id, &source).await {
+        Ok(record) => {
+            super::log::log_success_op(db, OperationType::Update, &record.id).await?;
+            Ok(record)
+        }
+        Err(e) => {
+            log::error!("Failed to roll back revision {revision_id}: {e}");
+            Err(e.into())
+        }
+    }
+}
+
+// ############
+// # transfer
+// #############
+
+/// 从 JSON Lines 或 CSV 文件导入记录，`dry_run` 时只返回报告不写入
+pub(crate) async fn import_local_records(
+    db: &DbOperator,
+    path: &Path,
+    format: ExportFormat,
+    dry_run: bool,
+) -> Result<ImportReport, AppError> {
+    log::debug!(
+        "Importing local records from {} as {format:?} (dry run: {dry_run})",
+        path.display()
+    );
+    let file = File::open(path).map_err(|e| AppError::FileSystemError(e.to_string()))?;
+    let source = RevisionSource::new(OperationType::Update);
+    let report = db
+        .import_local(BufReader::new(file), format, dry_run, &source)
+        .await?;
+    for error in &report.errors {
+        log::warn!(
+            "Skipped line {} of {}: {}",
+            error.line,
+            path.display(),
+            error.message
+        );
+    }
+    Ok(report)
+}
+
+// ############
+// # attribute
+// #############
+
+/// 重命名属性，同类别下已有同名属性时合并到该属性
+pub(crate) async fn rename_attribute(
+    db: &DbOperator,
+    id: i32,
+    name: &str,
+) -> Result<attribute::Model, AppError> {
+    log::debug!("Renaming attribute {id} to {name}");
+    Ok(db.rename_attribute(id, name.to_owned()).await?)
+}
+
+// ############
+// # tag
+// #############
+
+pub(crate) async fn create_tag(db: &DbOperator, name: &str) -> Result<tag::Model, AppError> {
+    log::debug!("Creating tag {name}");
+    Ok(db.create_tag(name).await?)
+}
+
+pub(crate) async fn rename_tag(
+    db: &DbOperator,
+    id: i32,
+    name: &str,
+) -> Result<tag::Model, AppError> {
+    log::debug!("Renaming tag {id} to {name}");
+    Ok(db.rename_tag(id, name).await?)
+}
+
+pub(crate) async fn delete_tag(db: &DbOperator, id: i32) -> Result<(), AppError> {
+    log::debug!("Deleting tag {id}");
+    Ok(db.delete_tag(id).await?)
+}
+
+/// 为多条记录批量添加多个标签，返回新增的关联数
+pub(crate) async fn tag_records(
+    db: &DbOperator,
+    tag_ids: &[i32],
+    codes: &[String],
+) -> Result<u64, AppError> {
+    log::debug!("Tagging {} records with tags {tag_ids:?}", codes.len());
+    Ok(db.tag_records(tag_ids, codes).await?)
+}
+
+/// 从多条记录批量移除多个标签，返回移除的关联数
+pub(crate) async fn untag_records(
+    db: &DbOperator,
+    tag_ids: &[i32],
+    codes: &[String],
+) -> Result<u64, AppError> {
+    log::debug!("Untagging {} records from tags {tag_ids:?}", codes.len());
+    Ok(db.untag_records(tag_ids, codes).await?)
+}
+
+// ############
+// # collection
+// #############
+
+pub(crate) async fn create_collection(
+    db: &DbOperator,
+    name: &str,
+    description: String,
+) -> Result<collection::Model, AppError> {
+    log::debug!("Creating collection {name}");
+    Ok(db.create_collection(name, description).await?)
+}
+
+pub(crate) async fn update_collection(
+    db: &DbOperator,
+    id: i32,
+    name: &str,
+    description: String,
+) -> Result<collection::Model, AppError> {
+    log::debug!("Updating collection {id}");
+    Ok(db.update_collection(id, name, description).await?)
+}
+
+pub(crate) async fn delete_collection(db: &DbOperator, id: i32) -> Result<(), AppError> {
+    log::debug!("Deleting collection {id}");
+    Ok(db.delete_collection(id).await?)
+}
+
+/// 将记录追加到列表末尾，返回新加入的记录数
+pub(crate) async fn add_to_collection(
+    db: &DbOperator,
+    id: i32,
+    codes: &[String],
+) -> Result<u64, AppError> {
+    log::debug!("Adding {} records to collection {id}", codes.len());
+    Ok(db.add_to_collection(id, codes).await?)
+}
+
+pub(crate) async fn remove_from_collection(
+    db: &DbOperator,
+    id: i32,
+    codes: &[String],
+) -> Result<u64, AppError> {
+    log::debug!("Removing {} records from collection {id}", codes.len());
+    Ok(db.remove_from_collection(id, codes).await?)
+}
+
+/// 给定记录按顺序排在最前，其余记录保持相对顺序
+pub(crate) async fn reorder_collection(
+    db: &DbOperator,
+    id: i32,
+    codes: &[String],
+) -> Result<(), AppError> {
+    log::debug!("Reordering collection {id}");
+    Ok(db.reorder_collection(id, codes).await?)
+}
@@ -2,15 +2,17 @@ use std::{sync::Arc, time::Duration};
 
 use luneth::crawl::CrawlConfig;
 use luneth_db::DbOperator;
+use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 
-use crate::{common::get_task_base_url, AppError};
+use crate::{common::get_task_base_url, db::tracker::TaskTracker, AppError};
 
 mod auto;
 mod batch;
 mod idol;
 mod images;
 mod record;
+mod retry;
 mod submit;
 mod update;
 
@@ -78,6 +80,51 @@ impl BatchCrawlConfig {
     }
 }
 
+/// 保存在任务运行记录中的爬取配置，重试时据此重建任务
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredCrawlConfig {
+    base_url: String,
+    with_image: bool,
+    headless: bool,
+    load_timeout: u64,
+    request_delay: u64,
+    webdriver_port: u16,
+}
+
+impl From<&BatchCrawlConfig> for StoredCrawlConfig {
+    fn from(config: &BatchCrawlConfig) -> Self {
+        Self {
+            base_url: config.crawl_config.base_url.clone(),
+            with_image: config.with_image,
+            headless: config.crawl_config.headless,
+            load_timeout: config.crawl_config.page_load_timeout,
+            request_delay: config.crawl_config.request_delay.as_secs(),
+            webdriver_port: config.crawl_config.webdriver_port,
+        }
+    }
+}
+
+impl BatchCrawlConfig {
+    fn from_stored(batch: Vec<String>, stored: StoredCrawlConfig) -> Self {
+        Self {
+            batch,
+            with_image: stored.with_image,
+            crawl_config: CrawlConfig {
+                base_url: stored.base_url,
+                page_load_timeout: stored.load_timeout,
+                magnet_timeout: stored.load_timeout,
+                headless: stored.headless,
+                request_delay: Duration::from_secs(stored.request_delay),
+                webdriver_port: stored.webdriver_port,
+            },
+        }
+    }
+
+    fn to_stored(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(StoredCrawlConfig::from(self)).ok()
+    }
+}
+
 #[derive(Debug)]
 pub enum TaskType {
     // Start URL, and with image
@@ -101,20 +148,55 @@ pub struct Task {
     app_handle: AppHandle,
     db: Arc<DbOperator>,
     task_type: TaskType,
+    // 重试时指向原任务运行
+    parent_id: Option<String>,
+}
+
+impl TaskType {
+    /// 任务运行记录中的类型与初始目标
+    fn history(&self) -> (luneth_db::TaskType, &[String]) {
+        match self {
+            Self::Auto(_) => (luneth_db::TaskType::Auto, &[]),
+            Self::Batch(config) => (luneth_db::TaskType::Crawl, &config.batch),
+            Self::PullRemote => (luneth_db::TaskType::Pull, &[]),
+            Self::Idol => (luneth_db::TaskType::Idol, &[]),
+            Self::Submit(codes) => (luneth_db::TaskType::Submit, codes),
+            Self::Update(config) => (luneth_db::TaskType::Update, &config.batch),
+        }
+    }
+
+    /// 需要随任务运行记录保存的配置
+    fn stored_config(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::Batch(config) | Self::Update(config) => config.to_stored(),
+            Self::Auto(_) | Self::PullRemote | Self::Idol | Self::Submit(_) => None,
+        }
+    }
 }
 
 impl Task {
     pub async fn exec(self) -> Result<(), AppError> {
         log::debug!("Starting task execution");
+
+        let (history_type, target_ids) = self.task_type.history();
+        let tracker = TaskTracker::start(
+            Arc::clone(&self.db),
+            history_type,
+            target_ids,
+            self.task_type.stored_config(),
+            self.parent_id.as_deref(),
+        )
+        .await?;
+
         log::debug!("Starting web crawler");
 
         let result = match &self.task_type {
-            TaskType::Auto(config) => self.crawl_auto(config).await,
-            TaskType::Batch(config) => self.crawl_batch(config).await,
-            TaskType::Idol => self.crawl_idol().await,
+            TaskType::Auto(config) => self.crawl_auto(config, &tracker).await,
+            TaskType::Batch(config) => self.crawl_batch(config, &tracker).await,
+            TaskType::Idol => self.crawl_idol(&tracker).await,
             TaskType::PullRemote => self.pull_record_slim().await,
-            TaskType::Submit(codes) => self.submit_codes(codes).await,
-            TaskType::Update(config) => self.update_codes(config).await,
+            TaskType::Submit(codes) => self.submit_codes(codes, &tracker).await,
+            TaskType::Update(config) => self.update_codes(config, &tracker).await,
         };
 
         match &result {
@@ -122,6 +204,10 @@ impl Task {
             Err(e) => log::error!("Task execution failed: {e}"),
         }
 
+        if let Err(e) = tracker.finish(&result).await {
+            log::error!("Failed to finish task run history: {e}");
+        }
+
         result
     }
 }
@@ -5,7 +5,7 @@ use tauri::{AppHandle, Emitter as _};
 
 use crate::{
     common::new_crawler_with_config,
-    db::log::{log_failed_op, log_success_op},
+    db::tracker::TaskTracker,
     handlers::{AppError, AutoCrawlConfig, TaskType},
 };
 use luneth_db::{DbOperator, OperationType};
@@ -40,12 +40,17 @@ impl super::Task {
         Ok(Self {
             db,
             task_type,
+            parent_id: None,
             app_handle,
         })
     }
 
-    pub(super) async fn crawl_auto(&self, config: &AutoCrawlConfig) -> Result<(), AppError> {
-        auto_crawl_page(&self.app_handle, self.db.as_ref(), config).await
+    pub(super) async fn crawl_auto(
+        &self,
+        config: &AutoCrawlConfig,
+        tracker: &TaskTracker,
+    ) -> Result<(), AppError> {
+        auto_crawl_page(&self.app_handle, self.db.as_ref(), config, tracker).await
     }
 }
 
@@ -53,6 +58,7 @@ async fn auto_crawl_page(
     app_handle: &AppHandle,
     db: &DbOperator,
     config: &AutoCrawlConfig,
+    tracker: &TaskTracker,
 ) -> Result<(), AppError> {
     let start_url = config.start_url.clone();
     let with_image = config.with_image;
@@ -69,6 +75,9 @@ async fn auto_crawl_page(
 
         // Send page start event to frontend
         report_crawl_page_start(app_handle, &page_name);
+        tracker
+            .add_targets(std::slice::from_ref(&page_name))
+            .await?;
 
         // crawl_page_start(page_i)
         let record_pieces = match crawler.crawl_page(&url).await {
@@ -76,7 +85,9 @@ async fn auto_crawl_page(
                 // crawl_page_success(page_i, len);
                 log::debug!("Successfully crawled page: {url}");
 
-                log_success_op(db, OperationType::CrawlPage, &page_name).await?;
+                tracker
+                    .success(OperationType::CrawlPage, &page_name)
+                    .await?;
 
                 // Send page success event to frontend
                 report_crawl_page_success(app_handle, &page_name, record_pieces.len());
@@ -87,7 +98,9 @@ async fn auto_crawl_page(
                 // crawl_page_failed(page_i, msg);
                 log::error!("Failed to crawl page {url}: {e}");
 
-                log_failed_op(db, OperationType::CrawlPage, &page_name, e.to_string()).await?;
+                tracker
+                    .failure(OperationType::CrawlPage, &page_name, e.to_string())
+                    .await?;
 
                 // Send page failed event to frontend
                 report_crawl_page_failed(app_handle, &page_name, e.to_string());
@@ -107,8 +120,14 @@ async fn auto_crawl_page(
         }
 
         let total_count = record_inputs.len();
+        let codes = record_inputs
+            .iter()
+            .map(|input| input.get_code().to_owned())
+            .collect::<Vec<_>>();
+        tracker.add_targets(&codes).await?;
 
-        super::batch::crawl_codes(app_handle, db, &crawler, record_inputs, with_image).await?;
+        super::batch::crawl_codes(app_handle, db, &crawler, tracker, record_inputs, with_image)
+            .await?;
         // crawl_page_finished(page_i);
         log::info!("Successfully crawled page: {url}, found {total_count} records",);
 
@@ -7,11 +7,11 @@ use serde::Serialize;
 use tauri::{AppHandle, Emitter as _};
 
 use crate::common::{new_crawler_with_config, EXIST_IDS};
-use crate::db::log::{log_failed_op, log_success_op};
+use crate::db::tracker::TaskTracker;
+use crate::db::write::save_crawled_record;
 use crate::handlers::images::crawl_record_image;
 use crate::handlers::{BatchCrawlConfig, TaskType};
 use crate::AppError;
-use luneth_db::entities::record_local::Model as RecorderModel;
 use luneth_db::{DbOperator, OperationType};
 
 impl super::Task {
@@ -45,11 +45,16 @@ impl super::Task {
         Ok(Self {
             db,
             task_type,
+            parent_id: None,
             app_handle,
         })
     }
 
-    pub(super) async fn crawl_batch(&self, config: &BatchCrawlConfig) -> Result<(), AppError> {
+    pub(super) async fn crawl_batch(
+        &self,
+        config: &BatchCrawlConfig,
+        tracker: &TaskTracker,
+    ) -> Result<(), AppError> {
         let crawler = new_crawler_with_config(config.crawl_config.clone())
             .await?
             .start()
@@ -65,6 +70,7 @@ impl super::Task {
             &self.app_handle,
             self.db.as_ref(),
             &crawler,
+            tracker,
             inputs,
             config.with_image,
         )
@@ -76,6 +82,7 @@ pub async fn crawl_codes(
     app_handle: &AppHandle,
     db: &DbOperator,
     crawler: &WebCrawler,
+    tracker: &TaskTracker,
     inputs: Vec<CrawlInput>,
     with_image: bool,
 ) -> Result<(), AppError> {
@@ -100,6 +107,7 @@ pub async fn crawl_codes(
                 CrawlStatus::Exist,
                 "Record already exists".to_owned(),
             );
+            tracker.skip(&code).await;
             success_count += 1;
             continue;
         }
@@ -109,20 +117,23 @@ pub async fn crawl_codes(
         match crawler.crawl_recorder(input).await {
             Ok(record) => {
                 let mut image_path_dir = None;
-                let record_model = if with_image {
+                if with_image {
                     image_path_dir = crawl_record_image(app_handle, crawler, &record).await.ok();
-                    RecorderModel::from_recorder_with_image_local(&record)
-                } else {
-                    RecorderModel::from_recorder(&record)
-                };
+                }
 
-                let insert_result = db.insert_local(record_model).await;
+                let insert_result = save_crawled_record(
+                    db,
+                    &record,
+                    image_path_dir.is_some(),
+                    &tracker.revision_source(OperationType::CrawlRecord),
+                )
+                .await;
 
                 match insert_result {
                     Ok(_) => {
                         // crawl_code_report
                         // log_success_crawl_record_op(db, code).await?;
-                        log_success_op(db, OperationType::CrawlRecord, &code).await?;
+                        tracker.success(OperationType::CrawlRecord, &code).await?;
                         success_count += 1;
                         log::info!("Successfully crawled and saved code: {code}");
 
@@ -140,7 +151,9 @@ pub async fn crawl_codes(
                             fs::remove_dir_all(image_path_dir).map_err(AppError::from)?;
                         }
                         log::error!("Failed to insert record for code {code}: {e}");
-                        log_failed_op(db, OperationType::CrawlRecord, &code, e.to_string()).await?;
+                        tracker
+                            .failure(OperationType::CrawlRecord, &code, e.to_string())
+                            .await?;
                         error_count += 1;
 
                         // Send progress event to frontend
@@ -156,7 +169,9 @@ pub async fn crawl_codes(
             Err(e) => {
                 // crawl_code_report
                 log::warn!("Failed to crawl code {code}: {e}");
-                log_failed_op(db, OperationType::CrawlRecord, &code, e.to_string()).await?;
+                tracker
+                    .failure(OperationType::CrawlRecord, &code, e.to_string())
+                    .await?;
                 error_count += 1;
 
                 // Send progress event to frontend
@@ -1,12 +1,13 @@
 use std::sync::Arc;
 
 use luneth::common::UploadImageDto;
-use luneth_db::DbOperator;
+use luneth_db::{DbOperator, OperationType};
 use serde::Serialize;
 use tauri::{AppHandle, Emitter as _};
 
 use crate::{
     common::{new_crawler, new_postman},
+    db::tracker::TaskTracker,
     handlers::TaskType,
     AppError,
 };
@@ -19,11 +20,12 @@ impl super::Task {
         Self {
             db,
             task_type,
+            parent_id: None,
             app_handle,
         }
     }
 
-    pub(super) async fn crawl_idol(&self) -> Result<(), AppError> {
+    pub(super) async fn crawl_idol(&self, tracker: &TaskTracker) -> Result<(), AppError> {
         log::debug!("Executing idol crawl task");
 
         let crawler = new_crawler().await?.start().await?;
@@ -41,6 +43,12 @@ impl super::Task {
         // Report start event
         report_idol_crawl_start(&self.app_handle, total_count);
 
+        let idol_ids = idol_without_image
+            .iter()
+            .map(|idol| idol.id.to_string())
+            .collect::<Vec<_>>();
+        tracker.add_targets(&idol_ids).await?;
+
         let idol_links = idol_without_image
             .iter()
             .map(|idol| (idol.id, idol.link.as_str()))
@@ -52,6 +60,7 @@ impl super::Task {
 
         for (id, link) in idol_links {
             processed_count += 1;
+            let idol_id = id.to_string();
 
             let image = crawler.crawl_idol_image(link).await;
 
@@ -62,6 +71,9 @@ impl super::Task {
                         log::error!("Crawled HTML content for idol {}", image.name);
                         let error_msg = format!("Crawled HTML content for idol {}", image.name);
                         error.push_str(&format!("{error_msg}\n"));
+                        tracker
+                            .failure(OperationType::CrawlIdol, &idol_id, error_msg.clone())
+                            .await?;
                         report_idol_crawl_progress(&self.app_handle, processed_count, error_msg);
                         continue;
                     }
@@ -76,6 +88,7 @@ impl super::Task {
                         Ok(resp) => {
                             success_count += 1;
                             log::info!("Image uploaded successfully for idol {id}: {resp}");
+                            tracker.success(OperationType::CrawlIdol, &idol_id).await?;
                             report_idol_crawl_progress(
                                 &self.app_handle,
                                 processed_count,
@@ -86,6 +99,9 @@ impl super::Task {
                             log::error!("Failed to upload image for idol {id}: {e}");
                             let error_msg = format!("Failed to upload image for idol {id}: {e}");
                             error.push_str(&format!("{error_msg}\n"));
+                            tracker
+                                .failure(OperationType::CrawlIdol, &idol_id, error_msg.clone())
+                                .await?;
                             report_idol_crawl_progress(
                                 &self.app_handle,
                                 processed_count,
@@ -98,6 +114,9 @@ impl super::Task {
                     log::error!("Failed to crawl image: {e}");
                     let error_msg = format!("Failed to crawl image: {e}");
                     error.push_str(&format!("{error_msg}\n"));
+                    tracker
+                        .failure(OperationType::CrawlIdol, &idol_id, error_msg.clone())
+                        .await?;
                     report_idol_crawl_progress(&self.app_handle, processed_count, error_msg);
                 }
             }
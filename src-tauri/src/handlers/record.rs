@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use luneth_db::DbOperator;
+use luneth_db::{impl_remote::RemoteUpsertSummary, DbOperator};
 use serde::Serialize;
 use tauri::{AppHandle, Emitter as _};
 
@@ -15,6 +15,7 @@ impl super::Task {
             db,
             app_handle,
             task_type,
+            parent_id: None,
         }
     }
 
@@ -44,25 +45,33 @@ async fn pull_record_slim(app_handle: &AppHandle, db: &DbOperator) -> Result<(),
     // Report start event
     report_record_pull_start(app_handle, total_count);
 
-    // Report progress
-    report_record_pull_progress(
-        app_handle,
-        0,
-        "Starting to save records to local database...".to_owned(),
-    );
-
-    let success_cnt = save_remote_records(db, records).await.map_err(|e| {
+    let summary = save_remote_records(db, records, |summary| {
+        report_record_pull_progress(
+            app_handle,
+            summary.processed(),
+            total_count,
+            format!(
+                "Saved {}/{total_count} records to local database",
+                summary.processed()
+            ),
+        );
+    })
+    .await
+    .map_err(|e| {
         let error_msg = format!("Failed to save records to database: {e}");
         report_record_pull_failed(app_handle, error_msg.clone());
         e
     })?;
 
     log::info!(
-        "Successfully pulled {total_count} records from remote server, and saved {success_cnt}"
+        "Successfully pulled {total_count} records from remote server: {} inserted, {} updated, {} unchanged",
+        summary.inserted,
+        summary.updated,
+        summary.unchanged
     );
 
     // Report completion
-    report_record_pull_complete(app_handle, success_cnt, total_count);
+    report_record_pull_complete(app_handle, summary, total_count);
 
     Ok(())
 }
@@ -82,6 +91,7 @@ struct RecordPullStartEvent {
 #[serde(rename_all = "camelCase")]
 struct RecordPullProgressEvent {
     processed: usize,
+    total_count: usize,
     message: String,
 }
 
@@ -89,6 +99,9 @@ struct RecordPullProgressEvent {
 #[serde(rename_all = "camelCase")]
 struct RecordPullCompleteEvent {
     success_count: usize,
+    inserted: usize,
+    updated: usize,
+    unchanged: usize,
     total_count: usize,
 }
 
@@ -107,17 +120,34 @@ fn report_record_pull_start(app_handle: &AppHandle, total_count: usize) {
     }
 }
 
-fn report_record_pull_progress(app_handle: &AppHandle, processed: usize, message: String) {
-    let event = RecordPullProgressEvent { processed, message };
+fn report_record_pull_progress(
+    app_handle: &AppHandle,
+    processed: usize,
+    total_count: usize,
+    message: String,
+) {
+    let event = RecordPullProgressEvent {
+        processed,
+        total_count,
+        message,
+    };
     match app_handle.emit("record-pull-progress", &event) {
         Ok(_) => log::debug!("Emitted record-pull-progress event: {processed} processed"),
         Err(e) => log::error!("Failed to emit record-pull-progress event: {e}"),
     }
 }
 
-fn report_record_pull_complete(app_handle: &AppHandle, success_count: usize, total_count: usize) {
+fn report_record_pull_complete(
+    app_handle: &AppHandle,
+    summary: RemoteUpsertSummary,
+    total_count: usize,
+) {
+    let success_count = summary.processed();
     let event = RecordPullCompleteEvent {
         success_count,
+        inserted: summary.inserted,
+        updated: summary.updated,
+        unchanged: summary.unchanged,
         total_count,
     };
     match app_handle.emit("record-pull-complete", &event) {
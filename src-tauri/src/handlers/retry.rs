@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use luneth_db::{history_task, DbOperator, TaskStatus};
+use tauri::AppHandle;
+
+use crate::{
+    handlers::{BatchCrawlConfig, StoredCrawlConfig, TaskType},
+    AppError,
+};
+
+impl super::Task {
+    /// 根据已结束的任务运行重建任务，只处理其中失败的目标
+    pub async fn new_retry(
+        app_handle: AppHandle,
+        db: Arc<DbOperator>,
+        task_id: String,
+    ) -> Result<Self, AppError> {
+        log::debug!("Creating retry task for task run {task_id}");
+        let Some(parent) = db.find_history_task_by_id(&task_id).await? else {
+            return Err(AppError::TaskRetryError(format!(
+                "task run {task_id} not found"
+            )));
+        };
+
+        let plan = RetryPlan::from_parent(&parent)?;
+        log::debug!(
+            "Retry task created for task run {task_id}: {:?}",
+            plan.task_type
+        );
+        Ok(Self {
+            app_handle,
+            db,
+            task_type: plan.task_type,
+            parent_id: Some(plan.parent_id),
+        })
+    }
+}
+
+/// 重试任务的类型与其指向的原任务运行
+#[derive(Debug)]
+struct RetryPlan {
+    task_type: TaskType,
+    parent_id: String,
+}
+
+impl RetryPlan {
+    fn from_parent(parent: &history_task::Model) -> Result<Self, AppError> {
+        // 仍在运行的任务尚未确定失败的目标
+        if parent.status == TaskStatus::Pending {
+            return Err(AppError::TaskPending(parent.id.clone()));
+        }
+
+        let failed_ids = parent
+            .get_failed_ids()
+            .map_err(|e| AppError::TaskRetryError(format!("invalid failed ids: {e}")))?;
+        if failed_ids.is_empty() {
+            return Err(AppError::TaskRetryError(format!(
+                "task run {} has no failed targets",
+                parent.id
+            )));
+        }
+
+        let history_type = parent.task_type;
+        let task_type = match history_type {
+            luneth_db::TaskType::Crawl => TaskType::Batch(stored_config(parent, failed_ids)?),
+            luneth_db::TaskType::Update => TaskType::Update(stored_config(parent, failed_ids)?),
+            luneth_db::TaskType::Submit => TaskType::Submit(failed_ids),
+            luneth_db::TaskType::Auto
+            | luneth_db::TaskType::Idol
+            | luneth_db::TaskType::Pull
+            | luneth_db::TaskType::Unknown => {
+                return Err(AppError::TaskRetryError(format!(
+                    "{history_type} task runs can not be retried"
+                )));
+            }
+        };
+
+        Ok(Self {
+            task_type,
+            parent_id: parent.id.clone(),
+        })
+    }
+}
+
+/// 使用原任务的爬取配置处理失败的编号
+fn stored_config(
+    parent: &history_task::Model,
+    batch: Vec<String>,
+) -> Result<BatchCrawlConfig, AppError> {
+    let Some(config) = parent.config.clone() else {
+        return Err(AppError::TaskRetryError(format!(
+            "task run {} has no stored crawl configuration",
+            parent.id
+        )));
+    };
+    let stored = serde_json::from_value::<StoredCrawlConfig>(config)
+        .map_err(|e| AppError::TaskRetryError(format!("invalid crawl configuration: {e}")))?;
+    Ok(BatchCrawlConfig::from_stored(batch, stored))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn parent(status: TaskStatus) -> history_task::Model {
+        let now = chrono::Utc::now();
+        history_task::Model {
+            id: "parent".to_owned(),
+            task_type: luneth_db::TaskType::Crawl,
+            start_time: now,
+            end_time: Some(now),
+            status,
+            target_ids: json!(["A-1", "A-2", "A-3"]),
+            failed_ids: json!(["A-2", "A-3"]),
+            total_count: 3,
+            failed_count: 2,
+            error_message: None,
+            parent_id: None,
+            config: Some(json!({
+                "base_url": "https://example.com",
+                "with_image": true,
+                "headless": false,
+                "load_timeout": 30,
+                "request_delay": 2,
+                "webdriver_port": 4444,
+            })),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn retry_keeps_parent_config_and_id() {
+        let plan = RetryPlan::from_parent(&parent(TaskStatus::Failed)).expect("retry plan");
+
+        assert_eq!(plan.parent_id, "parent");
+        let TaskType::Batch(config) = plan.task_type else {
+            panic!(
+                "crawl runs are retried as batch tasks, got {:?}",
+                plan.task_type
+            );
+        };
+        assert_eq!(config.batch, ["A-2", "A-3"]);
+        assert!(config.with_image);
+        assert_eq!(config.crawl_config.base_url, "https://example.com");
+        assert!(!config.crawl_config.headless);
+        assert_eq!(config.crawl_config.page_load_timeout, 30);
+        assert_eq!(config.crawl_config.request_delay, Duration::from_secs(2));
+        assert_eq!(config.crawl_config.webdriver_port, 4444);
+    }
+
+    #[test]
+    fn retry_rejects_pending_parent() {
+        let result = RetryPlan::from_parent(&parent(TaskStatus::Pending));
+        assert!(matches!(result, Err(AppError::TaskPending(id)) if id == "parent"));
+    }
+}
@@ -7,10 +7,7 @@ use tauri::{AppHandle, Emitter as _};
 
 use crate::{
     common::new_postman,
-    db::{
-        log::{log_failed_op, log_success_op},
-        write::mark_record_submitted,
-    },
+    db::{tracker::TaskTracker, write::mark_record_submitted},
     handlers::{images, TaskType},
     AppError,
 };
@@ -25,12 +22,17 @@ impl super::Task {
         Self {
             db,
             task_type,
+            parent_id: None,
             app_handle,
         }
     }
 
     #[expect(clippy::too_many_lines)]
-    pub(super) async fn submit_codes(&self, codes: &[String]) -> Result<(), AppError> {
+    pub(super) async fn submit_codes(
+        &self,
+        codes: &[String],
+        tracker: &TaskTracker,
+    ) -> Result<(), AppError> {
         log::debug!("Executing submit crawl task for {} codes", codes.len());
 
         let mut client = new_postman().await?;
@@ -51,13 +53,9 @@ impl super::Task {
             let Some(local_record) = self.db.as_ref().find_record_local_by_id(code).await? else {
                 let error_msg = format!("Failed to find record for code: {code}");
                 log::error!("{error_msg}");
-                log_failed_op(
-                    self.db.as_ref(),
-                    OperationType::Submit,
-                    code,
-                    error_msg.clone(),
-                )
-                .await?;
+                tracker
+                    .failure(OperationType::Submit, code, error_msg.clone())
+                    .await?;
 
                 error_count += 1;
                 report_submit_code_result(&self.app_handle, code, SubmitStatus::Failed, error_msg);
@@ -79,13 +77,9 @@ impl super::Task {
                                 images.len()
                             );
                             log::error!("{error_msg}");
-                            log_failed_op(
-                                self.db.as_ref(),
-                                OperationType::Submit,
-                                code,
-                                error_msg.clone(),
-                            )
-                            .await?;
+                            tracker
+                                .failure(OperationType::Submit, code, error_msg.clone())
+                                .await?;
 
                             error_count += 1;
                             report_submit_code_result(
@@ -102,13 +96,9 @@ impl super::Task {
                         let error_msg =
                             format!("Failed to read local images for code: {code}. Error: {e}");
                         log::error!("{error_msg}");
-                        log_failed_op(
-                            self.db.as_ref(),
-                            OperationType::Submit,
-                            code,
-                            error_msg.clone(),
-                        )
-                        .await?;
+                        tracker
+                            .failure(OperationType::Submit, code, error_msg.clone())
+                            .await?;
 
                         error_count += 1;
                         report_submit_code_result(
@@ -133,7 +123,7 @@ impl super::Task {
                 (Ok(_), Ok(_)) => {
                     success_count += 1;
                     mark_record_submitted(self.db.as_ref(), record.id.as_str()).await?;
-                    log_success_op(self.db.as_ref(), OperationType::Submit, code).await?;
+                    tracker.success(OperationType::Submit, code).await?;
                     log::info!("Successfully submitted record for code: {code}");
 
                     report_submit_code_result(
@@ -148,13 +138,9 @@ impl super::Task {
                         "Failed to submit record and images for code: {code}. Errors: {e1}, {e2}"
                     );
                     log::error!("{error_msg}");
-                    log_failed_op(
-                        self.db.as_ref(),
-                        OperationType::Submit,
-                        code,
-                        error_msg.clone(),
-                    )
-                    .await?;
+                    tracker
+                        .failure(OperationType::Submit, code, error_msg.clone())
+                        .await?;
 
                     error_count += 1;
                     report_submit_code_result(
@@ -168,13 +154,9 @@ impl super::Task {
                     let error_msg =
                         format!("Failed to submit record for code: {code}. Error: {err}");
                     log::error!("{error_msg}");
-                    log_failed_op(
-                        self.db.as_ref(),
-                        OperationType::Submit,
-                        code,
-                        error_msg.clone(),
-                    )
-                    .await?;
+                    tracker
+                        .failure(OperationType::Submit, code, error_msg.clone())
+                        .await?;
 
                     error_count += 1;
                     report_submit_code_result(
@@ -188,13 +170,9 @@ impl super::Task {
                     let error_msg =
                         format!("Failed to submit images for code: {code}. Error: {err}");
                     log::error!("{error_msg}");
-                    log_failed_op(
-                        self.db.as_ref(),
-                        OperationType::Submit,
-                        code,
-                        error_msg.clone(),
-                    )
-                    .await?;
+                    tracker
+                        .failure(OperationType::Submit, code, error_msg.clone())
+                        .await?;
 
                     error_count += 1;
                     report_submit_code_result(
@@ -1,4 +1,4 @@
-use std::{ops::Not as _, sync::Arc};
+use std::{fs, ops::Not as _, sync::Arc};
 
 use luneth::crawl::CrawlInput;
 use luneth_db::DbOperator;
@@ -7,7 +7,7 @@ use tauri::{AppHandle, Emitter as _};
 
 use crate::{
     common::new_crawler_with_config,
-    db::log::{log_failed_op, log_success_op},
+    db::{tracker::TaskTracker, write::save_crawled_record},
     handlers::{images::crawl_record_image, BatchCrawlConfig, TaskType},
     AppError,
 };
@@ -24,11 +24,16 @@ impl super::Task {
             app_handle,
             db,
             task_type,
+            parent_id: None,
         })
     }
 
     #[expect(clippy::too_many_lines)]
-    pub async fn update_codes(&self, config: &BatchCrawlConfig) -> Result<(), AppError> {
+    pub async fn update_codes(
+        &self,
+        config: &BatchCrawlConfig,
+        tracker: &TaskTracker,
+    ) -> Result<(), AppError> {
         let batch = &config.batch;
         let config = config.crawl_config.clone();
         log::debug!("Executing update crawl task for {} codes", batch.len());
@@ -49,79 +54,117 @@ impl super::Task {
 
         for input in inputs {
             let code = input.get_code().to_owned();
+            let Some(local_record) = self.db.find_record_local_by_id(&code).await? else {
+                log::error!("Record {code} not found in local database, skipping update");
+                tracker
+                    .failure(OperationType::Update, &code, "Record not found".to_owned())
+                    .await?;
+                error_count += 1;
+
+                report_update_code_result(
+                    &self.app_handle,
+                    &code,
+                    UpdateStatus::Failed,
+                    "Record not found in local database".to_owned(),
+                );
+                continue;
+            };
+            // 回收站中的记录不再更新，也不下载图片
+            if local_record.deleted_at.is_some() {
+                log::info!("Record {code} is in the trash, skipping update");
+                tracker.skip(&code).await;
+                success_count += 1;
+
+                report_update_code_result(
+                    &self.app_handle,
+                    &code,
+                    UpdateStatus::Success,
+                    "Record is in the trash, skipped".to_owned(),
+                );
+                continue;
+            }
+
             match crawler.crawl_recorder(input).await {
                 Ok(recorder) => {
-                    let Some(local_record) = self.db.find_record_local_by_id(&code).await? else {
-                        log::error!("Record {code} not found in local database, skipping update");
-                        log_failed_op(
-                            self.db.as_ref(),
-                            OperationType::Update,
-                            &code,
-                            "Record not found".to_owned(),
-                        )
-                        .await?;
-                        error_count += 1;
-
-                        report_update_code_result(
-                            &self.app_handle,
-                            &code,
-                            UpdateStatus::Failed,
-                            "Record not found in local database".to_owned(),
-                        );
-                        continue;
-                    };
+                    let crawl_images = local_record.is_cached_locally.not();
+                    let mut image_path_dir = None;
+                    if crawl_images {
+                        match crawl_record_image(&self.app_handle, &crawler, &recorder).await {
+                            Ok(dir) => {
+                                image_path_dir = Some(dir);
+                                log::info!(
+                                    "Successfully crawled images for record {code} by update"
+                                );
+                            }
+                            Err(e) => {
+                                log::error!("Failed to crawl images for record {code}: {e}");
+                                tracker
+                                    .failure(
+                                        OperationType::Update,
+                                        &code,
+                                        format!("Failed to crawl images: {e}"),
+                                    )
+                                    .await?;
+                                error_count += 1;
 
-                    let mut updated_something = false;
-                    let mut update_messages = Vec::new();
+                                report_update_code_result(
+                                    &self.app_handle,
+                                    &code,
+                                    UpdateStatus::Failed,
+                                    format!("Failed to crawl images: {e}"),
+                                );
+                                continue;
+                            }
+                        }
+                    }
 
-                    if local_record.is_cached_locally.not() {
-                        if let Err(e) =
-                            crawl_record_image(&self.app_handle, &crawler, &recorder).await
-                        {
-                            log::error!("Failed to crawl images for record {code}: {e}");
-                            log_failed_op(
-                                self.db.as_ref(),
-                                OperationType::Update,
-                                &code,
-                                format!("Failed to crawl images: {e}"),
-                            )
-                            .await?;
+                    let save_result = save_crawled_record(
+                        &self.db,
+                        &recorder,
+                        crawl_images,
+                        &tracker.revision_source(OperationType::Update),
+                    )
+                    .await;
+                    let updated = match save_result {
+                        Ok(updated) => updated,
+                        Err(e) => {
+                            // 保存失败时清理本次下载的图片
+                            if let Some(dir) = image_path_dir {
+                                if let Err(e) = fs::remove_dir_all(&dir) {
+                                    log::warn!("Failed to remove images {}: {e}", dir.display());
+                                }
+                            }
+                            log::error!("Failed to save updated record {code}: {e}");
+                            tracker
+                                .failure(OperationType::Update, &code, e.to_string())
+                                .await?;
                             error_count += 1;
 
                             report_update_code_result(
                                 &self.app_handle,
                                 &code,
                                 UpdateStatus::Failed,
-                                format!("Failed to crawl images: {e}"),
+                                format!("Failed to save: {e}"),
                             );
                             continue;
-                        } else {
-                            let updated_model = local_record
-                                .clone()
-                                .set_local_image_cached(recorder.record.local_image_count, true);
-                            self.db.update_record_local(updated_model).await?;
-                            log::info!("Successfully crawled images for record {code} by update");
-                            updated_something = true;
-                            update_messages.push("crawled images".to_owned());
                         }
+                    };
+                    let mut update_messages = Vec::new();
+                    if updated.is_cached_locally != local_record.is_cached_locally {
+                        update_messages.push("crawled images".to_owned());
                     }
-
-                    let local_record_entry = local_record.into_record();
-                    if local_record_entry.share_magnet_links.len()
-                        < recorder.record.share_magnet_links.len()
-                    {
-                        let updated_model =
-                            local_record.update_links(recorder.record.share_magnet_links.clone());
-                        self.db.update_record_local(updated_model).await?;
-                        log::info!("Updated share links for record {code}");
-                        updated_something = true;
+                    if updated.share_magnet_links != local_record.share_magnet_links {
                         update_messages.push("updated magnet links".to_owned());
                     }
+                    if updated.updated_at != local_record.updated_at && update_messages.is_empty() {
+                        update_messages.push("updated metadata".to_owned());
+                    }
+                    let updated_something = update_messages.is_empty().not();
 
                     if updated_something {
                         update_count += 1;
                         success_count += 1;
-                        log_success_op(self.db.as_ref(), OperationType::Update, &code).await?;
+                        tracker.success(OperationType::Update, &code).await?;
                         log::debug!("Successfully updated {code}");
 
                         report_update_code_result(
@@ -132,6 +175,7 @@ impl super::Task {
                         );
                     } else {
                         success_count += 1;
+                        tracker.skip(&code).await;
                         report_update_code_result(
                             &self.app_handle,
                             &code,
@@ -142,13 +186,9 @@ impl super::Task {
                 }
                 Err(e) => {
                     log::error!("Failed crawl {code} to update: {e}");
-                    log_failed_op(
-                        self.db.as_ref(),
-                        OperationType::Update,
-                        &code,
-                        e.to_string(),
-                    )
-                    .await?;
+                    tracker
+                        .failure(OperationType::Update, &code, e.to_string())
+                        .await?;
                     error_count += 1;
 
                     report_update_code_result(
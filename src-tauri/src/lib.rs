@@ -1,16 +1,22 @@
 mod db {
+    pub mod backup;
+    pub mod integrity;
     pub mod log;
     pub mod read;
+    pub mod tracker;
     pub mod write;
 }
 mod common;
 mod handlers;
 mod command {
+    pub mod backup;
     pub mod config;
     pub mod extract;
     pub mod image;
+    pub mod integrity;
     pub mod interaction;
     pub mod log;
+    pub mod organize;
     pub mod task;
 }
 
@@ -21,17 +27,34 @@ use tauri::Manager as _;
 use tauri_plugin_log::{RotationStrategy, Target, TargetKind, TimezoneStrategy};
 
 use crate::command::{
+    backup::{
+        cancel_backup_restore, create_backup_snapshot, has_pending_backup_restore,
+        list_backup_snapshots, restore_backup_snapshot, set_backup_schedule,
+    },
     config::{clear_client_auth, pull_record_slim, set_client_auth, set_task_base_url},
     extract::{export_to_file, process_text, toggle_line_selection},
     image::{get_app_local_data_dir, read_local_record_image},
+    integrity::{repair_library_integrity, scan_library_integrity},
     interaction::{
-        get_all_exist_records, get_all_op_history, get_local_records_paginator, mark_record_liked,
-        mark_record_unliked, mark_record_viewed, query_record_count, search_records,
+        export_records, finish_record_view, get_all_exist_records, get_local_records_paginator,
+        import_records, inspect_task_run, list_most_viewed, list_never_viewed,
+        list_recently_viewed, list_record_revisions, list_task_runs, list_trash_records,
+        mark_record_liked, mark_record_unliked, mark_record_viewed, prune_view_events,
+        purge_record, query_op_history, query_op_history_daily, query_record_count,
+        query_record_facets, rate_record, reconcile_catalogs, restore_record,
+        rollback_record_revision, search_records, set_record_note, trash_record,
     },
     log::get_log_dir,
+    organize::{
+        add_to_collection, create_collection, create_tag, delete_collection, delete_tag,
+        list_attributes, list_collection_records, list_collections, list_record_attributes,
+        list_record_collections, list_record_tags, list_tags, remove_from_collection,
+        rename_attribute, rename_tag, reorder_collection, tag_records, untag_records,
+        update_collection,
+    },
     task::{
         launch_auto_scrap_task, launch_batch_scrap_task, launch_idol_scrap_task,
-        launch_record_pull_task, launch_submit_task, launch_update_task,
+        launch_record_pull_task, launch_retry_task, launch_submit_task, launch_update_task,
     },
 };
 
@@ -64,6 +87,42 @@ pub enum AppError {
 
     #[error("Send request Failed: {0}")]
     SendRequestFailed(String),
+
+    #[error("Task retry error: {0}")]
+    TaskRetryError(String),
+
+    #[error("Task run is still pending: {0}")]
+    TaskPending(String),
+}
+
+impl AppError {
+    /// 供前端识别的错误代码，数据库错误沿用 [`luneth_db::DbError::code`]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::CrawlError(_) => "CRAWL_ERROR",
+            Self::DatabaseError(e) => e.code(),
+            Self::ImageError(_) => "FILE_ERROR",
+            Self::FileSystemError(_) => "FILE_SYSTEM_ERROR",
+            Self::CrawlImageError(_) => "CRAWL_IMAGE_ERROR",
+            Self::UnknownError(_) => "UNKNOWN_ERROR",
+            Self::GetAuthFailed(_) => "AUTH_NOT_SET",
+            Self::SendRequestFailed(_) => "SEND_REQUEST_FAILED",
+            Self::TaskRetryError(_) => "TASK_RETRY_ERROR",
+            Self::TaskPending(_) => "TASK_PENDING",
+        }
+    }
+}
+
+/// 以 `{ code, message }` 形式返回给前端
+impl serde::Serialize for AppError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct as _;
+
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
 }
 
 impl From<AppError> for String {
@@ -147,13 +206,36 @@ pub fn run() {
                 Box::new(std::io::Error::other(e.to_string()))
             })?;
 
+            // 上次暂存的快照须在打开数据库前换入；换入失败时不打开数据库，
+            // 避免在用户以为已恢复的旧数据上继续写入
+            match crate::db::backup::apply_pending_restore(&app_handle) {
+                Ok(true) => log::info!("Database restored from staged snapshot"),
+                Ok(false) => {}
+                Err(e) => {
+                    log::error!("Failed to restore staged snapshot: {e}");
+                    return Err(Box::new(std::io::Error::other(format!(
+                        "Failed to restore staged snapshot: {e}"
+                    ))));
+                }
+            }
+
             log::debug!("Initializing database connection");
-            let db_result = rt.block_on(async { luneth_db::DbOperator::init(&app_handle).await });
+            let db_result = rt.block_on(async {
+                let db = luneth_db::DbOperator::init(&app_handle).await?;
+                // 上次退出时仍在运行的任务已无法继续
+                let aborted = db.abort_pending_history_tasks().await?;
+                if aborted > 0 {
+                    log::warn!("Marked {aborted} unfinished task runs as aborted");
+                }
+                Ok::<_, luneth_db::DbError>(db)
+            });
 
             match db_result {
                 Ok(db) => {
                     log::info!("Database initialized successfully");
-                    let app_state = AppState { db: Arc::new(db) };
+                    let db = Arc::new(db);
+                    crate::db::backup::spawn_backup_scheduler(app_handle, Arc::clone(&db));
+                    let app_state = AppState { db };
                     app.manage(Arc::new(app_state));
                     log::info!("Application setup completed successfully");
                     log::debug!("App state managed and ready for operations");
@@ -172,9 +254,13 @@ pub fn run() {
             search_records,
             get_local_records_paginator,
             query_record_count,
+            query_record_facets,
             launch_auto_scrap_task,
             launch_batch_scrap_task,
-            get_all_op_history,
+            query_op_history,
+            query_op_history_daily,
+            list_task_runs,
+            inspect_task_run,
             set_task_base_url,
             set_client_auth,
             clear_client_auth,
@@ -182,13 +268,57 @@ pub fn run() {
             get_app_local_data_dir,
             read_local_record_image,
             mark_record_viewed,
+            finish_record_view,
+            list_recently_viewed,
+            list_most_viewed,
+            list_never_viewed,
+            prune_view_events,
             mark_record_liked,
             mark_record_unliked,
+            rate_record,
+            set_record_note,
+            trash_record,
+            restore_record,
+            purge_record,
+            list_trash_records,
+            list_record_revisions,
+            rollback_record_revision,
+            export_records,
+            import_records,
+            reconcile_catalogs,
+            scan_library_integrity,
+            repair_library_integrity,
+            create_backup_snapshot,
+            list_backup_snapshots,
+            set_backup_schedule,
+            restore_backup_snapshot,
+            cancel_backup_restore,
+            has_pending_backup_restore,
+            list_attributes,
+            list_record_attributes,
+            rename_attribute,
+            list_tags,
+            list_record_tags,
+            create_tag,
+            rename_tag,
+            delete_tag,
+            tag_records,
+            untag_records,
+            list_collections,
+            list_record_collections,
+            list_collection_records,
+            create_collection,
+            update_collection,
+            delete_collection,
+            add_to_collection,
+            remove_from_collection,
+            reorder_collection,
             launch_idol_scrap_task,
             launch_record_pull_task,
             launch_submit_task,
             get_log_dir,
             launch_update_task,
+            launch_retry_task,
             get_all_exist_records
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,40 @@
+// 数据库快照与恢复
+
+import { invoke } from '@tauri-apps/api/core';
+import type { BackupSchedule, SnapshotInfo } from '@/types/app';
+
+export async function createBackupSnapshot(withImages: boolean): Promise<SnapshotInfo> {
+  try {
+    return await invoke<SnapshotInfo>('create_backup_snapshot', { with_images: withImages });
+  } catch (error) {
+    console.error('Failed to create backup snapshot:', error);
+    throw error;
+  }
+}
+
+export async function fetchBackupSnapshots(): Promise<SnapshotInfo[]> {
+  return invoke<SnapshotInfo[]>('list_backup_snapshots');
+}
+
+// 传入 null 时停止定时快照
+export async function setBackupSchedule(schedule: BackupSchedule | null): Promise<void> {
+  await invoke('set_backup_schedule', { schedule });
+}
+
+// 校验并暂存快照，重启应用后生效
+export async function restoreBackupSnapshot(name: string): Promise<void> {
+  try {
+    await invoke('restore_backup_snapshot', { name });
+  } catch (error) {
+    console.error('Failed to stage backup snapshot:', error);
+    throw error;
+  }
+}
+
+export async function cancelBackupRestore(): Promise<void> {
+  await invoke('cancel_backup_restore');
+}
+
+export async function hasPendingBackupRestore(): Promise<boolean> {
+  return invoke<boolean>('has_pending_backup_restore');
+}
@@ -183,7 +183,7 @@ export async function initializeGlobalEventListeners() {
       console.log('[Event] Idol crawl progress:', event.payload);
       updateTaskProgress('idolCrawl', {
         processed: event.payload.processed,
-        total: -1 // Unknown total for progress events
+        total: event.payload.totalCount
       });
       updateTaskMessage('idolCrawl', event.payload.message);
     });
@@ -215,7 +215,7 @@ export async function initializeGlobalEventListeners() {
       console.log('[Event] Record pull progress:', event.payload);
       updateTaskProgress('recordPull', {
         processed: event.payload.processed,
-        total: -1 // Unknown total for progress events
+        total: event.payload.totalCount
       });
       updateTaskMessage('recordPull', event.payload.message);
     });
@@ -224,7 +224,11 @@ export async function initializeGlobalEventListeners() {
     const unlistenRecordComplete = await listen<RecordPullCompleteEvent>('record-pull-complete', (event) => {
       console.log('[Event] Record pull completed:', event.payload);
       updateTaskStatus('recordPull', 'success');
-      updateTaskMessage('recordPull', `Completed: ${event.payload.successCount}/${event.payload.totalCount} successful`);
+      const { inserted, updated, unchanged } = event.payload;
+      updateTaskMessage(
+        'recordPull',
+        `Completed: ${inserted} new, ${updated} updated, ${unchanged} unchanged`
+      );
     });
     unlistenFunctions.push(unlistenRecordComplete);
 
@@ -21,5 +21,11 @@ export * from './pagination';
 // Extract 相关
 export * from './extract';
 
+// 数据库快照
+export * from './backup';
+
+// 标签与列表
+export * from './organize';
+
 // 事件管理
 export * from './eventManager';
@@ -0,0 +1,154 @@
+// 属性、标签与列表
+
+import { invoke } from '@tauri-apps/api/core';
+import type {
+  AttributeKind,
+  AttributeModel,
+  CollectionModel,
+  CollectionSummary,
+  RecordModel,
+  TagModel,
+  TagSummary
+} from '@/types/record';
+
+export async function fetchAttributes(kind: AttributeKind): Promise<AttributeModel[]> {
+  return invoke<AttributeModel[]>('list_attributes', { kind });
+}
+
+export async function fetchRecordAttributes(code: string): Promise<AttributeModel[]> {
+  return invoke<AttributeModel[]>('list_record_attributes', { code });
+}
+
+// 同类别下已有同名属性时合并到该属性，返回合并后的属性
+export async function renameAttribute(id: number, name: string): Promise<AttributeModel> {
+  try {
+    return await invoke<AttributeModel>('rename_attribute', { id, name });
+  } catch (error) {
+    console.error('Failed to rename attribute:', error);
+    throw error;
+  }
+}
+
+export async function fetchTags(): Promise<TagSummary[]> {
+  return invoke<TagSummary[]>('list_tags');
+}
+
+export async function fetchRecordTags(code: string): Promise<TagModel[]> {
+  return invoke<TagModel[]>('list_record_tags', { code });
+}
+
+export async function createTag(name: string): Promise<TagModel> {
+  try {
+    return await invoke<TagModel>('create_tag', { name });
+  } catch (error) {
+    console.error('Failed to create tag:', error);
+    throw error;
+  }
+}
+
+export async function renameTag(id: number, name: string): Promise<TagModel> {
+  try {
+    return await invoke<TagModel>('rename_tag', { id, name });
+  } catch (error) {
+    console.error('Failed to rename tag:', error);
+    throw error;
+  }
+}
+
+export async function deleteTag(id: number): Promise<void> {
+  try {
+    await invoke('delete_tag', { id });
+  } catch (error) {
+    console.error('Failed to delete tag:', error);
+    throw error;
+  }
+}
+
+// 为多条记录批量添加多个标签，返回新增的关联数
+export async function tagRecords(tagIds: number[], codes: string[]): Promise<number> {
+  try {
+    return await invoke<number>('tag_records', { tag_ids: tagIds, codes });
+  } catch (error) {
+    console.error('Failed to tag records:', error);
+    throw error;
+  }
+}
+
+// 从多条记录批量移除多个标签，返回移除的关联数
+export async function untagRecords(tagIds: number[], codes: string[]): Promise<number> {
+  try {
+    return await invoke<number>('untag_records', { tag_ids: tagIds, codes });
+  } catch (error) {
+    console.error('Failed to untag records:', error);
+    throw error;
+  }
+}
+
+export async function fetchCollections(): Promise<CollectionSummary[]> {
+  return invoke<CollectionSummary[]>('list_collections');
+}
+
+export async function fetchRecordCollections(code: string): Promise<CollectionModel[]> {
+  return invoke<CollectionModel[]>('list_record_collections', { code });
+}
+
+// 列表中的记录，按列表内顺序排列
+export async function fetchCollectionRecords(id: number): Promise<RecordModel[]> {
+  return invoke<RecordModel[]>('list_collection_records', { id });
+}
+
+export async function createCollection(name: string, description?: string): Promise<CollectionModel> {
+  try {
+    return await invoke<CollectionModel>('create_collection', { name, description });
+  } catch (error) {
+    console.error('Failed to create collection:', error);
+    throw error;
+  }
+}
+
+export async function updateCollection(id: number, name: string, description?: string): Promise<CollectionModel> {
+  try {
+    return await invoke<CollectionModel>('update_collection', { id, name, description });
+  } catch (error) {
+    console.error('Failed to update collection:', error);
+    throw error;
+  }
+}
+
+export async function deleteCollection(id: number): Promise<void> {
+  try {
+    await invoke('delete_collection', { id });
+  } catch (error) {
+    console.error('Failed to delete collection:', error);
+    throw error;
+  }
+}
+
+// 按给定顺序追加到列表末尾，返回新加入的记录数
+export async function addToCollection(id: number, codes: string[]): Promise<number> {
+  try {
+    return await invoke<number>('add_to_collection', { id, codes });
+  } catch (error) {
+    console.error('Failed to add records to collection:', error);
+    throw error;
+  }
+}
+
+export async function removeFromCollection(id: number, codes: string[]): Promise<number> {
+  try {
+    return await invoke<number>('remove_from_collection', { id, codes });
+  } catch (error) {
+    console.error('Failed to remove records from collection:', error);
+    throw error;
+  }
+}
+
+// codes 中的记录按给定顺序排在最前，其余记录保持相对顺序
+export async function reorderCollection(id: number, codes: string[]): Promise<void> {
+  try {
+    await invoke('reorder_collection', { id, codes });
+  } catch (error) {
+    console.error('Failed to reorder collection:', error);
+    throw error;
+  }
+}
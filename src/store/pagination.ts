@@ -2,7 +2,8 @@
 
 import { reactive } from 'vue';
 import { invoke } from '@tauri-apps/api/core';
-import type { RecordModel, RecordFilterOptions } from '@/types/record';
+import { formatError } from '@/utils/error';
+import type { RecordModel, RecordFilterOptions, SearchHit, LocalFilterCondition, SortSpec, PageStart, RecordPage, RecordFacets } from '@/types/record';
 
 export interface PaginationState {
   currentPage: number;
@@ -15,6 +16,11 @@ export interface PaginationState {
   searchQuery: string;
   filters: RecordFilterOptions;
   totalRecordsCount: number; // 添加总记录数（未过滤的）
+  sort: SortSpec;
+  sortSearchByRelevance: boolean; // 搜索时按相关度排序，忽略 sort
+  nextCursor: string | null; // 列表模式下一页游标
+  prevCursor: string | null; // 列表模式上一页游标
+  facets: RecordFacets; // 当前过滤条件下各类别的属性值计数
 }
 
 // 全局分页状态
@@ -32,15 +38,20 @@ export const paginationState = reactive<PaginationState>({
     isViewed: null,
     isSubmitted: null,
     hasLocalImages: null,
+    tags: null,
   },
   totalRecordsCount: 0, // 添加总记录数初始值
+  sort: { field: 'updated_at', order: 'desc' },
+  sortSearchByRelevance: true,
+  nextCursor: null,
+  prevCursor: null,
+  facets: {},
 });
 
 // 构建过滤器数组
-function buildFilters(): string[] {
-  const filters: string[] = [];
+export function buildFilters(): LocalFilterCondition[] {
+  const filters: LocalFilterCondition[] = [];
 
-  // 注意：后端期望简单的字符串标识符
   if (paginationState.filters.isLiked === true) {
     filters.push('liked');
   }
@@ -57,6 +68,11 @@ function buildFilters(): string[] {
     filters.push('local');
   }
 
+  const tags = paginationState.filters.tags;
+  if (tags && tags.length > 0) {
+    filters.push({ tag: tags });
+  }
+
   return filters;
 }
 
@@ -76,13 +92,13 @@ export async function fetchRecordCount(): Promise<void> {
 
     if (hasSearchQuery) {
       // 使用搜索命令获取计数，调用搜索但只取第一个元素（计数）
-      const searchResult = await invoke<[number, RecordModel[]]>('search_records', {
+      const searchPage = await invoke<RecordPage<SearchHit>>('search_records', {
         name: paginationState.searchQuery.trim(),
-        offset: 0,
+        start: 'first',
         limit: 1, // 只需要计数，所以限制为1条记录
         filters,
       });
-      count = searchResult[0];
+      count = searchPage.total;
     } else {
       // 获取过滤后的记录数
       count = await invoke<number>('query_record_count', { filters });
@@ -104,13 +120,34 @@ export async function fetchRecordCount(): Promise<void> {
     }
   } catch (error) {
     console.error('[Pagination] Failed to fetch record count:', error);
-    paginationState.error = `Failed to fetch record count: ${error}`;
+    paginationState.error = `Failed to fetch record count: ${formatError(error)}`;
     throw error;
   }
 }
 
-// 获取当前页的记录
-export async function fetchCurrentPageRecords(): Promise<void> {
+// 获取当前过滤条件与搜索词下的属性值计数
+export async function fetchFacets(): Promise<void> {
+  try {
+    const searchQuery = paginationState.searchQuery.trim();
+    paginationState.facets = await invoke<RecordFacets>('query_record_facets', {
+      filters: buildFilters(),
+      name: searchQuery === '' ? null : searchQuery,
+    });
+  } catch (error) {
+    // 计数仅用于展示，失败时不影响列表加载
+    console.error('[Pagination] Failed to fetch facets:', error);
+    paginationState.facets = {};
+  }
+}
+
+// 当前页的起点：第一页从头开始，其余按偏移定位
+function currentPageStart(): PageStart {
+  const offset = (paginationState.currentPage - 1) * paginationState.pageSize;
+  return offset > 0 ? { offset } : 'first';
+}
+
+// 获取当前页的记录，可传入游标起点
+export async function fetchCurrentPageRecords(start?: PageStart): Promise<void> {
   console.log('[Pagination] Starting fetchCurrentPageRecords...');
   paginationState.isLoading = true;
   paginationState.error = '';
@@ -118,7 +155,7 @@ export async function fetchCurrentPageRecords(): Promise<void> {
   try {
     // 首先获取总数
     console.log('[Pagination] Fetching record count...');
-    await fetchRecordCount();
+    await Promise.all([fetchRecordCount(), fetchFacets()]);
     console.log(`[Pagination] Total count: ${paginationState.totalCount}, Total pages: ${paginationState.totalPages}`);
 
     // 计算偏移量
@@ -132,27 +169,33 @@ export async function fetchCurrentPageRecords(): Promise<void> {
 
     if (hasSearchQuery) {
       console.log(`[Pagination] Using search with query: "${paginationState.searchQuery}"`);
-      const searchResult = await invoke<[number, RecordModel[]]>('search_records', {
+      const page = await invoke<RecordPage<SearchHit>>('search_records', {
         name: paginationState.searchQuery.trim(),
-        offset,
+        start: start ?? currentPageStart(),
         limit: paginationState.pageSize,
         filters,
+        sort: paginationState.sortSearchByRelevance ? null : paginationState.sort,
       });
-      // 注意：search_records 返回 (count, records) 元组，但这里的 count 应该与 fetchRecordCount 中获取的一致
-      records = searchResult[1];
+      records = page.records;
+      paginationState.nextCursor = page.next_cursor;
+      paginationState.prevCursor = page.prev_cursor;
     } else {
-      records = await invoke<RecordModel[]>('get_local_records_paginator', {
-        offset,
+      const page = await invoke<RecordPage>('get_local_records_paginator', {
+        start: start ?? currentPageStart(),
         limit: paginationState.pageSize,
         filters,
+        sort: paginationState.sort,
       });
+      records = page.records;
+      paginationState.nextCursor = page.next_cursor;
+      paginationState.prevCursor = page.prev_cursor;
     }
 
     paginationState.records = records;
     console.log(`[Pagination] Loaded page ${paginationState.currentPage}, ${records.length} records`);
   } catch (error) {
     console.error('[Pagination] Failed to fetch records:', error);
-    paginationState.error = `Failed to fetch records: ${error}`;
+    paginationState.error = `Failed to fetch records: ${formatError(error)}`;
     paginationState.records = [];
   } finally {
     paginationState.isLoading = false;
@@ -165,8 +208,16 @@ export async function goToPage(page: number): Promise<void> {
     return;
   }
 
+  // 相邻页优先使用游标，避免翻页期间新增记录导致结果错位
+  let start: PageStart | undefined;
+  if (page === paginationState.currentPage + 1 && paginationState.nextCursor) {
+    start = { cursor: paginationState.nextCursor };
+  } else if (page === paginationState.currentPage - 1 && paginationState.prevCursor) {
+    start = { cursor: paginationState.prevCursor };
+  }
+
   paginationState.currentPage = page;
-  await fetchCurrentPageRecords();
+  await fetchCurrentPageRecords(start);
 }
 
 // 下一页
@@ -201,6 +252,14 @@ export function setFilters(filters: Partial<RecordFilterOptions>): void {
   Object.assign(paginationState.filters, filters);
 }
 
+// 设置排序方式并重新加载
+export async function setSort(sort: SortSpec, searchByRelevance = false): Promise<void> {
+  paginationState.sort = sort;
+  paginationState.sortSearchByRelevance = searchByRelevance;
+  paginationState.currentPage = 1;
+  await fetchCurrentPageRecords();
+}
+
 // 清除过滤器
 export function clearFilters(): void {
   paginationState.filters = {
@@ -208,6 +267,7 @@ export function clearFilters(): void {
     isViewed: null,
     isSubmitted: null,
     hasLocalImages: null,
+    tags: null,
   };
   paginationState.searchQuery = '';
 }
@@ -2,9 +2,9 @@
 
 import { reactive } from 'vue';
 import { invoke } from '@tauri-apps/api/core';
-import type { RecordFilterOptions, RecordModel } from '@/types/record';
+import type { ExportFormat, ImportReport, IntegrityReport, PageStart, ReconcileReport, RecordFilterOptions, RecordModel, RecordPage, RecordRevisionModel, RepairRequest, RepairSummary, ViewRetention, ViewStat } from '@/types/record';
 import { appState } from './app';
-import { paginationState } from './pagination';
+import { buildFilters, paginationState } from './pagination';
 
 // 记录筛选状态
 export const recordFilterState = reactive<RecordFilterOptions>({
@@ -70,23 +70,81 @@ function updateRecordInPagination(recordId: string, updates: Partial<RecordModel
 }
 
 // 记录交互功能
-export async function markRecordViewed(recordId: string): Promise<void> {
+// 记录一次查看，返回查看事件编号，离开时以 finishRecordView 补充停留时长
+export async function markRecordViewed(recordId: string): Promise<number | null> {
   try {
-    await invoke('mark_record_viewed', { code: recordId });
-
-    // 更新分页状态中的记录
-    updateRecordInPagination(recordId, { viewed: true });
+    const viewId = await invoke<number | null>('mark_record_viewed', { code: recordId });
+    if (viewId === null) {
+      return null;
+    }
 
-    // 如果当前选中的记录是这个，也要更新
+    const current = appState.selectedRecord?.id === recordId
+      ? appState.selectedRecord
+      : paginationState.records.find(r => r.id === recordId);
+    const updates = {
+      viewed: true,
+      view_count: (current?.view_count ?? 0) + 1,
+      last_viewed_at: new Date().toISOString()
+    };
+    updateRecordInPagination(recordId, updates);
     if (appState.selectedRecord?.id === recordId) {
-      appState.selectedRecord.viewed = true;
+      Object.assign(appState.selectedRecord, updates);
     }
+    return viewId;
   } catch (error) {
     console.error('Failed to mark record as viewed:', error);
     throw error;
   }
 }
 
+export async function finishRecordView(viewId: number, durationSecs: number): Promise<void> {
+  try {
+    await invoke('finish_record_view', { view_id: viewId, duration_secs: durationSecs });
+  } catch (error) {
+    console.error('Failed to finish record view:', error);
+    throw error;
+  }
+}
+
+// since 为空时不限时间
+export async function fetchRecentlyViewed(since: string | null, limit: number): Promise<RecordModel[]> {
+  try {
+    return await invoke<RecordModel[]>('list_recently_viewed', { since, limit });
+  } catch (error) {
+    console.error('Failed to fetch recently viewed records:', error);
+    throw error;
+  }
+}
+
+// since 为空时按累计查看次数统计
+export async function fetchMostViewed(since: string | null, limit: number): Promise<ViewStat[]> {
+  try {
+    return await invoke<ViewStat[]>('list_most_viewed', { since, limit });
+  } catch (error) {
+    console.error('Failed to fetch most viewed records:', error);
+    throw error;
+  }
+}
+
+export async function fetchNeverViewed(start: PageStart, limit: number): Promise<RecordPage> {
+  try {
+    return await invoke<RecordPage>('list_never_viewed', { start, limit });
+  } catch (error) {
+    console.error('Failed to fetch never viewed records:', error);
+    throw error;
+  }
+}
+
+// 按保留策略清理查看事件，返回删除的事件数
+export async function pruneViewEvents(retention: ViewRetention): Promise<number> {
+  try {
+    return await invoke<number>('prune_view_events', { retention });
+  } catch (error) {
+    console.error('Failed to prune view events:', error);
+    throw error;
+  }
+}
+
 export async function markRecordLiked(recordId: string): Promise<void> {
   try {
     await invoke('mark_record_liked', { code: recordId });
@@ -120,3 +178,160 @@ export async function markRecordUnliked(recordId: string): Promise<void> {
     throw error;
   }
 }
+
+// 设置评分（1–5），0 表示清除评分
+export async function rateRecord(recordId: string, rating: number): Promise<void> {
+  try {
+    await invoke('rate_record', { code: recordId, rating });
+
+    const updates = { rating, rated_at: rating > 0 ? new Date().toISOString() : null };
+    updateRecordInPagination(recordId, updates);
+    if (appState.selectedRecord?.id === recordId) {
+      Object.assign(appState.selectedRecord, updates);
+    }
+  } catch (error) {
+    console.error('Failed to rate record:', error);
+    throw error;
+  }
+}
+
+// 设置 Markdown 私人备注，空白内容表示清除备注
+export async function setRecordNote(recordId: string, note: string): Promise<void> {
+  try {
+    await invoke('set_record_note', { code: recordId, note });
+
+    const trimmed = note.trim();
+    const updates = { note: trimmed, note_updated_at: trimmed ? new Date().toISOString() : null };
+    updateRecordInPagination(recordId, updates);
+    if (appState.selectedRecord?.id === recordId) {
+      Object.assign(appState.selectedRecord, updates);
+    }
+  } catch (error) {
+    console.error('Failed to set record note:', error);
+    throw error;
+  }
+}
+
+// 回收站
+export async function trashRecord(recordId: string): Promise<void> {
+  try {
+    await invoke('trash_record', { code: recordId });
+
+    // 回收站中的记录不再出现在列表中
+    paginationState.records = paginationState.records.filter(r => r.id !== recordId);
+    if (appState.selectedRecord?.id === recordId) {
+      appState.selectedRecord.deleted_at = new Date().toISOString();
+    }
+  } catch (error) {
+    console.error('Failed to move record to trash:', error);
+    throw error;
+  }
+}
+
+export async function restoreRecord(recordId: string): Promise<void> {
+  try {
+    await invoke('restore_record', { code: recordId });
+
+    if (appState.selectedRecord?.id === recordId) {
+      appState.selectedRecord.deleted_at = null;
+    }
+  } catch (error) {
+    console.error('Failed to restore record:', error);
+    throw error;
+  }
+}
+
+// 彻底删除回收站中的记录及其本地图片
+export async function purgeRecord(recordId: string): Promise<void> {
+  try {
+    await invoke('purge_record', { code: recordId });
+  } catch (error) {
+    console.error('Failed to purge record:', error);
+    throw error;
+  }
+}
+
+export async function fetchTrashRecords(start: PageStart, limit: number): Promise<RecordPage> {
+  return invoke<RecordPage>('list_trash_records', { start, limit });
+}
+
+// 修订历史
+export async function fetchRecordRevisions(recordId: string): Promise<RecordRevisionModel[]> {
+  return invoke<RecordRevisionModel[]>('list_record_revisions', { code: recordId });
+}
+
+// 将记录回滚到修订发生前的状态
+export async function rollbackRecordRevision(revisionId: number): Promise<RecordModel> {
+  try {
+    const record = await invoke<RecordModel>('rollback_record_revision', { revision_id: revisionId });
+
+    updateRecordInPagination(record.id, record);
+    if (appState.selectedRecord?.id === record.id) {
+      Object.assign(appState.selectedRecord, record);
+    }
+    return record;
+  } catch (error) {
+    console.error('Failed to roll back record revision:', error);
+    throw error;
+  }
+}
+
+// 按当前筛选条件导出记录，返回导出的记录数
+export async function exportRecords(path: string, format: ExportFormat): Promise<number> {
+  try {
+    return await invoke<number>('export_records', {
+      path,
+      format,
+      filters: buildFilters(),
+      sort: paginationState.sort,
+    });
+  } catch (error) {
+    console.error('Failed to export records:', error);
+    throw error;
+  }
+}
+
+// 从 JSON Lines 或 CSV 文件导入记录，dryRun 时只返回报告不写入
+export async function importRecords(path: string, format: ExportFormat, dryRun: boolean): Promise<ImportReport> {
+  try {
+    return await invoke<ImportReport>('import_records', { path, format, dry_run: dryRun });
+  } catch (error) {
+    console.error('Failed to import records:', error);
+    throw error;
+  }
+}
+
+// 检查记录标记与本地图片是否一致
+export async function scanLibraryIntegrity(): Promise<IntegrityReport> {
+  try {
+    return await invoke<IntegrityReport>('scan_library_integrity');
+  } catch (error) {
+    console.error('Failed to scan library integrity:', error);
+    throw error;
+  }
+}
+
+// 返回的 redownload 需通过 launch_update_task 重新下载图片
+export async function repairLibraryIntegrity(request: RepairRequest): Promise<RepairSummary> {
+  try {
+    return await invoke<RepairSummary>('repair_library_integrity', { request });
+  } catch (error) {
+    console.error('Failed to repair library integrity:', error);
+    throw error;
+  }
+}
+
+// 比对本地与远端记录，已在远端的记录会被标记为已提交
+export async function reconcileCatalogs(): Promise<ReconcileReport> {
+  try {
+    const report = await invoke<ReconcileReport>('reconcile_catalogs');
+
+    for (const recordId of report.marked_submitted) {
+      updateRecordInPagination(recordId, { is_submitted: true });
+    }
+    return report;
+  } catch (error) {
+    console.error('Failed to reconcile catalogs:', error);
+    throw error;
+  }
+}
@@ -27,3 +27,25 @@ export interface AppState {
   clientAuth: ClientAuthState;
   pullRecordsState: PullRecordsState;
 }
+
+// 命令返回的错误，code 为机器可读的错误代码（如 DB_UNIQUE_VIOLATION）
+export interface AppErrorPayload {
+  code: string;
+  message: string;
+}
+
+// 数据库快照
+export interface SnapshotInfo {
+  name: string;
+  path: string;
+  images_archive: string | null; // 同名的图片归档
+  size: number; // 数据库文件大小（字节）
+  created_at: string;
+}
+
+// 定时快照配置
+export interface BackupSchedule {
+  interval_hours: number;
+  retention: number; // 保留个数，为 0 时不清理
+  with_images: boolean;
+}
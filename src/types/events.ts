@@ -56,11 +56,15 @@ export interface RecordPullStartEvent {
 
 export interface RecordPullProgressEvent {
   processed: number;
+  totalCount: number;
   message: string;
 }
 
 export interface RecordPullCompleteEvent {
   successCount: number;
+  inserted: number;
+  updated: number;
+  unchanged: number;
   totalCount: number;
 }
 
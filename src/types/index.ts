@@ -5,15 +5,56 @@ export type {
   ViewType,
   ClientAuthState,
   PullRecordsState,
-  AppState
+  AppState,
+  AppErrorPayload,
+  SnapshotInfo,
+  BackupSchedule
 } from './app';
 
 // 记录相关类型
 export type {
   RecordModel,
+  SearchHit,
   MagnetLink,
   HistoryOpModel,
-  RecordFilterOptions
+  OperationType,
+  OperationStatus,
+  HistoryOpFilter,
+  HistoryOpSortField,
+  HistoryOpSort,
+  HistoryOpPage,
+  HistoryOpDailyCount,
+  HistoryTaskModel,
+  RecordRevisionModel,
+  TaskRun,
+  LocalFilterCondition,
+  SortField,
+  SortOrder,
+  SortSpec,
+  PageStart,
+  RecordPage,
+  AttributeKind,
+  FacetValue,
+  RecordFacets,
+  RecordFilterOptions,
+  ExportFormat,
+  ImportLineError,
+  ImportReport,
+  FlagMismatch,
+  BrokenImage,
+  InvalidJsonColumn,
+  IntegrityReport,
+  RepairRequest,
+  RepairSummary,
+  RicherRecord,
+  ReconcileReport,
+  AttributeModel,
+  TagModel,
+  TagSummary,
+  CollectionModel,
+  CollectionSummary,
+  ViewStat,
+  ViewRetention
 } from './record';
 
 // 任务相关类型
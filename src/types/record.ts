@@ -15,12 +15,25 @@ export interface RecordModel {
   share_magnet_links: MagnetLink[];
   sample_image_links: string[];
   local_image_count: number;
-  viewed: boolean;
+  viewed: boolean; // 至少查看过一次
   is_liked: boolean;
   is_submitted: boolean;
   is_cached_locally: boolean;
+  rating: number; // 1–5，0 表示未评分
+  rated_at: string | null;
+  note: string; // Markdown 私人备注，为空表示没有备注
+  note_updated_at: string | null;
+  view_count: number; // 累计查看次数，不受查看事件清理影响
+  last_viewed_at: string | null;
   created_at: string;
   updated_at: string;
+  deleted_at: string | null; // 移入回收站的时间
+}
+
+// 全文检索命中的记录
+export interface SearchHit extends RecordModel {
+  rank: number; // bm25 相关度，越小越相关
+  snippet?: string | null; // 命中片段，匹配部分以 <mark> 包裹
 }
 
 export interface MagnetLink {
@@ -32,19 +45,287 @@ export interface MagnetLink {
 export interface HistoryOpModel {
   id: number;
   recorder_id: string;
-  operation: string;
+  operation: OperationType;
   timestamp: string;
-  status: string;
+  status: OperationStatus;
   user: string;
   error_message?: string;
+  // 产生该操作的任务运行，手动操作为空
+  task_id?: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export type OperationType =
+  | 'CRAWL_RECORD'
+  | 'CRAWL_PAGE'
+  | 'CRAWL_IDOL'
+  | 'VIEWED'
+  | 'LIKED'
+  | 'UNLIKED'
+  | 'SUBMIT'
+  | 'CREATE'
+  | 'UPDATE'
+  | 'DELETE'
+  | 'RATED'
+  | 'NOTED'
+  | 'UNKNOWN';
+
+export type OperationStatus = 'SUCCESS' | 'FAILED' | 'UNKNOWN';
+
+// 操作历史过滤条件，与 luneth_db::impl_history::HistoryOpFilter 对应，未设置的项不限
+export interface HistoryOpFilter {
+  operations?: OperationType[];
+  statuses?: OperationStatus[];
+  recorder_id?: string | null;
+  // RFC 3339 时间，含端点
+  from?: string | null;
+  to?: string | null;
+  error_contains?: string | null;
+}
+
+export type HistoryOpSortField = 'timestamp' | 'operation' | 'status' | 'recorder_id';
+
+export interface HistoryOpSort {
+  field: HistoryOpSortField;
+  order: SortOrder;
+}
+
+export interface HistoryOpPage {
+  items: HistoryOpModel[];
+  total: number;
+}
+
+export interface HistoryOpDailyCount {
+  // YYYY-MM-DD
+  day: string;
+  operation: OperationType;
+  status: OperationStatus;
+  count: number;
+}
+
+// 与 luneth_db::history_task::Model 对应的任务运行记录
+export interface HistoryTaskModel {
+  id: string;
+  task_type: 'CRAWL' | 'AUTO' | 'SUBMIT' | 'UPDATE' | 'IDOL' | 'PULL' | 'UNKNOWN';
+  start_time: string;
+  end_time?: string | null;
+  status: 'PENDING' | 'SUCCESS' | 'FAILED' | 'ABORTED' | 'UNKNOWN';
+  target_ids: string[];
+  failed_ids: string[];
+  total_count: number;
+  failed_count: number;
+  error_message?: string | null;
+  // 重试时指向原任务运行
+  parent_id?: string | null;
+  config?: Record<string, unknown> | null;
   created_at: string;
   updated_at: string;
 }
 
+// 与 luneth_db::record_revision::Model 对应的记录元数据修订
+export interface RecordRevisionModel {
+  id: number;
+  record_id: string;
+  operation: OperationType;
+  task_id?: string | null;
+  // 由回滚产生时指向被回滚的修订
+  rollback_of?: number | null;
+  // 修订发生前的元数据
+  snapshot: Partial<RecordModel>;
+  diff: Record<string, { old: unknown; new: unknown }>;
+  created_at: string;
+}
+
+// 任务运行及其逐项操作结果
+export interface TaskRun extends HistoryTaskModel {
+  operations: HistoryOpModel[];
+  retries: HistoryTaskModel[];
+}
+
+// 后端记录过滤条件，与 luneth_db::impl_local::LocalFilterCondition 的序列化格式一致
+export type LocalFilterCondition =
+  | 'viewed'
+  | 'liked'
+  | 'submit'
+  | 'local'
+  | 'has_note'
+  | { not: LocalFilterCondition }
+  | { any: LocalFilterCondition[] }
+  | { all: LocalFilterCondition[] }
+  | { release_date: { from?: string | null; to?: string | null } }
+  | { rating: { min?: number | null; max?: number | null } } // 0 表示未评分
+  | { length: { min?: number | null; max?: number | null } }
+  | { genre: string[] }
+  | { idol: string[] }
+  | { studio: string[] }
+  | { label: string[] }
+  | { series: string[] }
+  | { director: string[] }
+  | { tag: string[] }
+  | { collection: number[] };
+
+// 记录排序方式，与 luneth_db::impl_local::SortSpec 的序列化格式一致
+export type SortField = 'release_date' | 'created_at' | 'updated_at' | 'title' | 'id' | 'local_image_count' | 'rating' | 'view_count';
+export type SortOrder = 'asc' | 'desc';
+
+export interface SortSpec {
+  field: SortField;
+  order: SortOrder;
+}
+
+// 分页起点，与 luneth_db::impl_local::PageStart 的序列化格式一致
+export type PageStart = 'first' | { offset: number } | { cursor: string };
+
+// 游标分页结果
+export interface RecordPage<T = RecordModel> {
+  records: T[];
+  total: number;
+  next_cursor: string | null;
+  prev_cursor: string | null;
+}
+
+// 属性类别，与 luneth_db::AttributeKind 的序列化格式一致
+export type AttributeKind = 'idol' | 'genre' | 'studio' | 'label' | 'series' | 'director';
+
+// 属性值及其在当前结果中的记录数
+export interface FacetValue {
+  id: number;
+  name: string;
+  count: number;
+}
+
+export type RecordFacets = Partial<Record<AttributeKind, FacetValue[]>>;
+
 // 记录筛选选项
 export interface RecordFilterOptions {
   isLiked?: boolean | null; // null = all, true = liked only, false = not liked only
   isViewed?: boolean | null; // null = all, true = viewed only, false = not viewed only
   isSubmitted?: boolean | null; // null = all, true = submitted only, false = not submitted only
   hasLocalImages?: boolean | null; // null = all, true = has local images, false = no local images
+  tags?: string[] | null; // null or empty = all, otherwise records with any of the tags
+}
+
+// 导出格式：jsonl 可重新导入，csv 仅用于导出
+export type ExportFormat = 'jsonl' | 'csv';
+
+export interface ImportLineError {
+  line: number; // 从 1 开始的行号
+  id?: string | null;
+  message: string;
+}
+
+// 导入结果，试运行时为将要产生的结果
+export interface ImportReport {
+  dry_run: boolean;
+  total: number;
+  inserted: number;
+  updated: number;
+  unchanged: number;
+  errors: ImportLineError[];
+}
+
+// 完整性检查
+export interface FlagMismatch {
+  record_id: string;
+  is_cached_locally: boolean;
+  local_image_count: number;
+  usable_images: number; // 磁盘上可按顺序读取的图片数
+}
+
+export interface BrokenImage {
+  record_id: string;
+  file: string;
+  reason: string;
+}
+
+export interface InvalidJsonColumn {
+  record_id: string;
+  column: string;
+  message: string;
+}
+
+export interface IntegrityReport {
+  scanned: number;
+  flag_mismatches: FlagMismatch[];
+  orphan_folders: string[]; // 没有对应记录的图片目录
+  broken_images: BrokenImage[];
+  invalid_json: InvalidJsonColumn[];
+}
+
+export interface RepairRequest {
+  fix_flags?: string[];
+  redownload?: string[];
+  quarantine?: string[];
+}
+
+// redownload 为需要交给更新任务重新下载图片的记录编号
+export interface RepairSummary {
+  fixed_flags: string[];
+  redownload: string[];
+  quarantined: string[];
+  errors: string[];
+}
+
+// 本地与远端记录比对
+export interface RicherRecord {
+  id: string;
+  missing_links: number; // 远端没有的磁链数
+  local_image_count: number;
+  is_cached_locally: boolean;
+}
+
+export interface ReconcileReport {
+  local_only: string[];
+  remote_only: string[];
+  both: string[];
+  richer_than_remote: RicherRecord[];
+  trashed_in_remote: string[]; // 本地已移入回收站、远端仍存在
+  marked_submitted: string[]; // 已在远端、本次标记为已提交
+  to_submit: string[]; // 可直接交给 launch_submit_task
+}
+
+// 记录属性，与 luneth_db::attribute::Model 对应
+export interface AttributeModel {
+  id: number;
+  kind: AttributeKind;
+  name: string;
+  link: string;
+}
+
+// 用户自定义标签，与 luneth_db::tag::Model 对应
+export interface TagModel {
+  id: number;
+  name: string;
+  created_at: string;
+}
+
+// 标签及其关联的记录数，不含回收站中的记录
+export interface TagSummary extends TagModel {
+  record_count: number;
+}
+
+// 手动维护的记录列表，与 luneth_db::collection::Model 对应
+export interface CollectionModel {
+  id: number;
+  name: string;
+  description: string;
+  created_at: string;
+  updated_at: string;
+}
+
+// 列表及其中的记录数，不含回收站中的记录
+export interface CollectionSummary extends CollectionModel {
+  record_count: number;
+}
+
+// 记录及其在统计区间内的查看次数
+export interface ViewStat extends RecordModel {
+  views: number;
+}
+
+// 查看事件的保留策略，未设置的项不限
+export interface ViewRetention {
+  max_age_days: number | null;
+  max_per_record: number | null;
 }
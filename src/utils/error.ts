@@ -0,0 +1,20 @@
+import type { AppErrorPayload } from '@/types';
+
+/**
+ * 判断命令返回的错误是否为带错误代码的 AppError
+ */
+export function isAppError(error: unknown): error is AppErrorPayload {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    typeof (error as AppErrorPayload).code === 'string' &&
+    typeof (error as AppErrorPayload).message === 'string'
+  );
+}
+
+/**
+ * 将命令返回的错误转换为可显示的文本
+ */
+export function formatError(error: unknown): string {
+  return isAppError(error) ? error.message : String(error);
+}